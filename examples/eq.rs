@@ -4,29 +4,7 @@ This example shows to generate all solutions of an equation of the form:
 
     x0 + x1 + x2 + ... + xn-2 = xn-1
 
-For example:
-
-    a + b = c
-    c - a = b
-    c - b = a
-
-Each solution is a node in a generated graph.
-An edge tells how to swap sides and sign to get from one node to another.
-
-To get from one solution to another, one only needs to move maximum two terms.
-
-If the right side is negative, automatic inversion is used.
-This improves the performance of the graph generation.
-
-The number of nodes from `n` terms and `m` right-side terms is:
-
-    bin(n, m)
-
-The number of edges is the number of pairs between nodes:
-
-    pairs(bin(n, m))
-
-    pairs(n) = n * (n-1) / 2
+See the `eq` module for more information.
 
 */
 
@@ -35,6 +13,7 @@ extern crate graph_builder;
 use std::str::FromStr;
 
 use graph_builder::*;
+use graph_builder::eq::gen_eq;
 
 fn main() {
     // Change this to control the number of terms in the equation.
@@ -50,129 +29,23 @@ fn main() {
         1
     };
 
-    // Putting all terms except the last one
-    let start = Eq {
-        side: {
-            if solution_terms == 1 && n > 0 {
-                let mut res = vec![true; n-1];
-                res.push(false);
-                res
-            } else {
-                vec![true; n]
-            }
-        },
-        positive: vec![true; n],
-    };
-
-    // Swap side and sign on the chosen term.
-    let f = |eq: &Eq, ind: usize| {
-        let mut eq = eq.clone();
-        eq.side[ind] = !eq.side[ind];
-        eq.positive[ind] = !eq.positive[ind];
-        Ok((eq, Swap(vec![ind])))
-    };
-    // Filter nodes to those with the specified number of solutions.
-    let g = |eq: &Eq| eq.len_right() == solution_terms;
-    // Join operations.
-    // Since these swap operations are commutative, require order.
-    let h = |a: &Swap, b: &Swap| if a.0 >= b.0 {Err(None)} else {Ok(Swap({
-        let mut a = a.0.clone();
-        a.extend_from_slice(&b.0);
-        a.sort();
-        a
-    }))};
-
-    let settings = GenerateSettings {
-        max_nodes: 1000,
-        max_edges: 1000,
-    };
+    let settings = GenerateSettings::new(1000, 1000);
 
-    let seed = (vec![start], vec![]);
     // Generate graph.
-    let (eqs, mut edges) = match gen(seed, n, f, g, h, &settings) {
+    let (eqs, mut edges) = match gen_eq(n, solution_terms, &settings) {
         Ok(x) => x,
-        Err((x, ())) => x,
+        Err((x, _)) => x,
     };
 
     // Remove all edges that are not bidirectional.
     bidir(&mut edges);
     edges.sort();
-    for i in 0..eqs.len() {
-        println!("{}: {}", i, eqs[i]);
+    for (i, eq) in eqs.iter().enumerate() {
+        println!("{}: {}", i, eq);
     }
-    for i in 0..edges.len() {
-        println!("{:?}", edges[i]);
+    for edge in &edges {
+        println!("{:?}", edge);
     }
 
     println!("(nodes, edges): ({}, {})", eqs.len(), edges.len());
 }
-
-
-/// Stores an equation.
-#[derive(PartialEq, Eq, Clone, Hash)]
-pub struct Eq {
-    /// The sides of the terms.
-    pub side: Vec<bool>,
-    /// The signs of the terms.
-    pub positive: Vec<bool>,
-}
-
-impl Eq {
-    /// Returns the number of terms on the right.
-    pub fn len_right(&self) -> usize {
-        self.side.iter().filter(|&&n| n).count()
-    }
-
-    /// Returns an index if the equation has a unique right side.
-    pub fn unique_right(&self) -> Option<usize> {
-        let mut found = None;
-        for i in 0..self.side.len() {
-            if self.side[i] {
-                if found.is_some() {return None};
-                found = Some(i);
-            }
-        }
-        found
-    }
-
-    /// Returns a tuple of signs, one when positive and one when negative.
-    pub fn signs(&self) -> (&'static str, &'static str) {
-        if let Some(ind) = self.unique_right() {
-            if self.positive[ind] {("+", "-")}
-            else {("-", "+")}
-        } else {("+", "-")}
-    }
-}
-
-/// Stores swap operations.
-#[derive(Debug, PartialOrd, Ord, PartialEq, Eq)]
-pub struct Swap(pub Vec<usize>);
-
-impl std::fmt::Display for Eq {
-    fn fmt(&self, w: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-        let mut left: usize = 0;
-        let (plus, minus) = self.signs();
-        for i in 0..self.side.len() {
-            if !self.side[i] {
-                if self.positive[i] {write!(w, "{}", plus)?}
-                else {write!(w, "{}", minus)?}
-                write!(w, "x{} ", i)?;
-                left += 1;
-            }
-        }
-        if left == 0 {write!(w, "0 ")?}
-        write!(w, "= ")?;
-        if self.side.len() - left == 0 {
-            write!(w, "0")?;
-        } else {
-            for i in 0..self.side.len() {
-                if self.side[i] {
-                    if self.positive[i] {write!(w, "{}", plus)?}
-                    else {write!(w, "{}", minus)?}
-                    write!(w, "x{} ", i)?
-                }
-            }
-        }
-        Ok(())
-    }
-}
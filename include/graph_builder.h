@@ -0,0 +1,52 @@
+/* C interface of graph_builder, built with the `ffi` feature. See `src/ffi.rs`. */
+
+#ifndef GRAPH_BUILDER_H
+#define GRAPH_BUILDER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define GB_MAX_NODES (-1)
+#define GB_MAX_EDGES (-2)
+#define GB_MAX_COMPOSITIONS (-3)
+
+typedef int (*GbGen)(void *ctx, uint64_t node, size_t op, uint64_t *out_node, uint64_t *out_edge);
+typedef int (*GbFilter)(void *ctx, uint64_t node);
+typedef int (*GbCompose)(void *ctx, uint64_t a, uint64_t b, uint64_t *out);
+
+typedef struct GbCallbacks {
+    GbGen f; /* Required, `gb_gen` returns NULL when this is NULL. */
+    GbFilter g;
+    GbCompose h;
+    void *ctx;
+} GbCallbacks;
+
+typedef struct GbSettings {
+    size_t max_nodes;
+    size_t max_edges;
+    size_t max_compositions;
+} GbSettings;
+
+typedef struct GbGraph GbGraph;
+
+GbSettings gb_settings(size_t max_nodes, size_t max_edges);
+/* Returns NULL when `callbacks.f` is NULL or generation panics. */
+GbGraph *gb_gen(const uint64_t *seed, size_t seed_len, size_t n,
+                GbCallbacks callbacks, GbSettings settings);
+int gb_graph_error(const GbGraph *graph);
+size_t gb_graph_node_count(const GbGraph *graph);
+const uint64_t *gb_graph_nodes(const GbGraph *graph);
+size_t gb_graph_edge_count(const GbGraph *graph);
+const size_t *gb_graph_edge_endpoints(const GbGraph *graph);
+const uint64_t *gb_graph_edges(const GbGraph *graph);
+void gb_graph_free(GbGraph *graph);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,1413 @@
+//! Algorithms for analyzing generated graphs.
+//!
+//! These algorithms work directly on the tuple representation of graphs.
+//! Most of them build an `Incidence` index internally.
+
+use crate::{into_result, GenResult, Graph, Incidence};
+
+/// Finds all subgraphs of `graph` that are isomorphic to `pattern`.
+///
+/// Returns a list of mappings, where every mapping contains
+/// the node in `graph` for every node in `pattern`.
+///
+/// - `node_match` tells whether a pattern node is compatible with a node
+/// - `edge_match` tells whether a pattern edge is compatible with an edge
+///
+/// Every pattern edge must map to a compatible edge between the mapped nodes.
+/// Edges in `graph` that are not in the pattern are allowed,
+/// such that the subgraphs are not necessarily induced.
+/// Parallel pattern edges might map to the same edge.
+///
+/// Symmetric patterns are found once per automorphism.
+/// For example, a commuting square where both paths are equal is found twice.
+///
+/// The search extends partial mappings along edges of the pattern,
+/// similar to the VF2 algorithm.
+pub fn find_subgraph_isomorphisms<P, Q, T, U, NM, EM>(
+    pattern: &Graph<P, Q>,
+    graph: &Graph<T, U>,
+    node_match: NM,
+    edge_match: EM,
+) -> Vec<Vec<usize>>
+    where NM: Fn(&P, &T) -> bool,
+          EM: Fn(&Q, &U) -> bool
+{
+    subgraph_search(pattern, graph, node_match, edge_match, None)
+}
+
+/// Accepts a complete mapping, or accepts all mappings when `None`.
+type Accept<'a> = Option<&'a dyn Fn(&[usize]) -> bool>;
+
+/// Searches for subgraph isomorphisms, see `find_subgraph_isomorphisms`.
+///
+/// When `accept` is given, stops at the first mapping that it accepts
+/// and returns only that mapping.
+fn subgraph_search<P, Q, T, U, NM, EM>(
+    pattern: &Graph<P, Q>,
+    graph: &Graph<T, U>,
+    node_match: NM,
+    edge_match: EM,
+    accept: Accept,
+) -> Vec<Vec<usize>>
+    where NM: Fn(&P, &T) -> bool,
+          EM: Fn(&Q, &U) -> bool
+{
+    let pn = pattern.0.len();
+    let p_inc = Incidence::new(pattern);
+    let g_inc = Incidence::new(graph);
+
+    // Order pattern nodes such that every node is connected to previous ones when possible.
+    let mut order: Vec<usize> = vec![];
+    let mut ordered = vec![false; pn];
+    for _ in 0..pn {
+        let degree = |i: usize| p_inc.out[i].len() + p_inc.inc[i].len();
+        let connections = |i: usize| p_inc.out[i].iter().chain(&p_inc.inc[i])
+            .filter(|&&(j, _)| ordered[j]).count();
+        let next = (0..pn).filter(|&i| !ordered[i])
+            .max_by_key(|&i| (connections(i), degree(i), std::cmp::Reverse(i))).unwrap();
+        ordered[next] = true;
+        order.push(next);
+    }
+
+    let mut state = Subgraph {
+        pattern,
+        graph,
+        p_inc: &p_inc,
+        g_inc: &g_inc,
+        node_match: &node_match,
+        edge_match: &edge_match,
+        order: &order,
+        map: vec![None; pn],
+        used: vec![false; graph.0.len()],
+        accept,
+        res: vec![],
+    };
+    state.search(0);
+    state.res
+}
+
+/// Stores the state of subgraph isomorphism search.
+struct Subgraph<'a, P, Q, T, U, NM, EM> {
+    pattern: &'a Graph<P, Q>,
+    graph: &'a Graph<T, U>,
+    p_inc: &'a Incidence,
+    g_inc: &'a Incidence,
+    node_match: &'a NM,
+    edge_match: &'a EM,
+    order: &'a [usize],
+    map: Vec<Option<usize>>,
+    used: Vec<bool>,
+    accept: Accept<'a>,
+    res: Vec<Vec<usize>>,
+}
+
+impl<'a, P, Q, T, U, NM, EM> Subgraph<'a, P, Q, T, U, NM, EM>
+    where NM: Fn(&P, &T) -> bool,
+          EM: Fn(&Q, &U) -> bool
+{
+    fn search(&mut self, k: usize) {
+        if k >= self.order.len() {
+            let map: Vec<usize> = self.map.iter().map(|m| m.unwrap()).collect();
+            if self.accept.map(|accept| accept(&map)).unwrap_or(true) {self.res.push(map)};
+            return;
+        }
+
+        let p = self.order[k];
+        // Pick candidates from the neighbours of a mapped node when possible.
+        let candidates: Vec<usize> = if let Some(&(q, _)) = self.p_inc.inc[p].iter()
+            .find(|&&(q, _)| self.map[q].is_some())
+        {
+            let mut list: Vec<usize> = self.g_inc.out[self.map[q].unwrap()].iter()
+                .map(|&(j, _)| j).collect();
+            list.sort_unstable();
+            list.dedup();
+            list
+        } else if let Some(&(q, _)) = self.p_inc.out[p].iter()
+            .find(|&&(q, _)| self.map[q].is_some())
+        {
+            let mut list: Vec<usize> = self.g_inc.inc[self.map[q].unwrap()].iter()
+                .map(|&(j, _)| j).collect();
+            list.sort_unstable();
+            list.dedup();
+            list
+        } else {
+            (0..self.graph.0.len()).collect()
+        };
+
+        for c in candidates {
+            if self.accept.is_some() && !self.res.is_empty() {return};
+            if self.used[c] || !(self.node_match)(&self.pattern.0[p], &self.graph.0[c]) {continue};
+
+            self.map[p] = Some(c);
+            if self.edges_match(p) {
+                self.used[c] = true;
+                self.search(k + 1);
+                self.used[c] = false;
+            }
+            self.map[p] = None;
+        }
+    }
+
+    /// Checks that every pattern edge of `p` to a mapped node has a compatible edge.
+    fn edges_match(&self, p: usize) -> bool {
+        let edges = self.p_inc.out[p].iter().chain(&self.p_inc.inc[p]);
+        for &(_, e) in edges {
+            let ([a, b], ref q) = self.pattern.1[e];
+            if let (Some(ga), Some(gb)) = (self.map[a], self.map[b]) {
+                if !self.g_inc.edges_between(ga, gb)
+                    .any(|k| (self.edge_match)(q, &self.graph.1[k].1)) {return false}
+            }
+        }
+        true
+    }
+}
+
+/// Finds an isomorphism between two graphs.
+///
+/// Returns the node in `b` for every node in `a`, or `None` if the graphs are not isomorphic.
+/// Unlike `find_subgraph_isomorphisms`, every edge must map to its own edge,
+/// such that parallel edges and self-loops are counted.
+///
+/// - `node_match` tells whether a node in `a` is compatible with a node in `b`
+/// - `edge_match` tells whether an edge in `a` is compatible with an edge in `b`
+///
+/// This is used to compare generated graphs where the order of nodes and edges
+/// does not matter, e.g. in tests.
+pub fn isomorphism<P, Q, T, U, NM, EM>(
+    a: &Graph<P, Q>,
+    b: &Graph<T, U>,
+    node_match: NM,
+    edge_match: EM,
+) -> Option<Vec<usize>>
+    where NM: Fn(&P, &T) -> bool,
+          EM: Fn(&Q, &U) -> bool
+{
+    if a.0.len() != b.0.len() || a.1.len() != b.1.len() {return None};
+    let (a_inc, b_inc) = (Incidence::new(a), Incidence::new(b));
+    let degree = |inc: &Incidence, i: usize| (inc.out[i].len(), inc.inc[i].len());
+    // Search on graphs of indices, such that nodes with different degrees are not matched.
+    let a_index: Graph<usize, usize> = ((0..a.0.len()).collect(),
+        a.1.iter().enumerate().map(|(k, e)| (e.0, k)).collect());
+    let b_index: Graph<usize, usize> = ((0..b.0.len()).collect(),
+        b.1.iter().enumerate().map(|(k, e)| (e.0, k)).collect());
+    // Every pair of nodes needs a compatible edge in `b` for every edge in `a`.
+    let accept = |map: &[usize]| (0..a.0.len()).all(|i| {
+        let mut targets: Vec<usize> = a_inc.out[i].iter().map(|&(j, _)| j).collect();
+        targets.sort_unstable();
+        targets.dedup();
+        targets.into_iter().all(|j| {
+            let ea: Vec<usize> = a_inc.edges_between(i, j).collect();
+            let eb: Vec<usize> = b_inc.edges_between(map[i], map[j]).collect();
+            ea.len() == eb.len() &&
+            perfect_matching(ea.len(), |x, y| edge_match(&a.1[ea[x]].1, &b.1[eb[y]].1))
+        })
+    });
+    subgraph_search(&a_index, &b_index,
+        |&i, &j| degree(&a_inc, i) == degree(&b_inc, j) && node_match(&a.0[i], &b.0[j]),
+        |&k, &l| edge_match(&a.1[k].1, &b.1[l].1),
+        Some(&accept)).pop()
+}
+
+/// Returns `true` if two graphs are isomorphic, comparing nodes and edges with `==`.
+///
+/// See `isomorphism`.
+pub fn is_isomorphic<T, U>(a: &Graph<T, U>, b: &Graph<T, U>) -> bool
+    where T: PartialEq, U: PartialEq
+{
+    isomorphism(a, b, |x, y| x == y, |x, y| x == y).is_some()
+}
+
+/// Asserts that two graphs are isomorphic, comparing nodes and edges with `==`.
+///
+/// This works like `assert_eq!`, but ignores the order of nodes and edges,
+/// see `algo::is_isomorphic`.
+/// On failure, both graphs are printed with `Debug`.
+///
+/// ```
+/// use graph_builder::assert_graph_iso;
+///
+/// let a = (vec!["x", "y"], vec![([0, 1], 1)]);
+/// let b = (vec!["y", "x"], vec![([1, 0], 1)]);
+/// assert_graph_iso!(a, b);
+/// ```
+#[macro_export]
+macro_rules! assert_graph_iso {
+    ($a:expr, $b:expr $(,)?) => {
+        match (&$a, &$b) {
+            (a, b) => if !$crate::algo::is_isomorphic(a, b) {
+                panic!("assertion failed: graphs are not isomorphic\n left: {:?}\nright: {:?}",
+                    a, b);
+            }
+        }
+    };
+    ($a:expr, $b:expr, $($arg:tt)+) => {
+        match (&$a, &$b) {
+            (a, b) => if !$crate::algo::is_isomorphic(a, b) {
+                panic!("assertion failed: graphs are not isomorphic\n left: {:?}\nright: {:?}\n{}",
+                    a, b, format_args!($($arg)+));
+            }
+        }
+    };
+}
+
+/// Returns `true` if every left item `0..n` can be matched to its own right item `0..n`.
+///
+/// Uses augmenting paths, which is fast enough for the small number of parallel edges.
+fn perfect_matching<M: Fn(usize, usize) -> bool>(n: usize, ok: M) -> bool {
+    fn augment<M: Fn(usize, usize) -> bool>(
+        x: usize,
+        n: usize,
+        ok: &M,
+        seen: &mut [bool],
+        right: &mut [Option<usize>],
+    ) -> bool {
+        for y in 0..n {
+            if seen[y] || !ok(x, y) {continue};
+            seen[y] = true;
+            if right[y].is_none() || augment(right[y].unwrap(), n, ok, seen, right) {
+                right[y] = Some(x);
+                return true;
+            }
+        }
+        false
+    }
+
+    let mut right = vec![None; n];
+    (0..n).all(|x| augment(x, n, &ok, &mut vec![false; n], &mut right))
+}
+
+/// Stores the number of small directed motifs in a graph.
+///
+/// Parallel edges are counted once and edges from a node to itself are ignored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Motifs {
+    /// The number of paths `a -> b -> c` where `a`, `b` and `c` are distinct.
+    pub paths: usize,
+    /// The number of paths `a -> b -> c` with an edge `a -> c`.
+    pub feed_forward: usize,
+    /// The number of cycles `a -> b -> c -> a`, where every cycle is counted once.
+    pub cycles: usize,
+}
+
+/// Counts small directed motifs in a graph.
+///
+/// These counts summarize the compositional structure of a graph.
+pub fn count_motifs<T, U>(graph: &Graph<T, U>) -> Motifs {
+    let succ = simple_successors(&Incidence::new(graph));
+    let mut motifs = Motifs::default();
+    for a in 0..succ.len() {
+        for &b in &succ[a] {
+            for &c in &succ[b] {
+                if c == a {continue};
+                motifs.paths += 1;
+                if succ[a].binary_search(&c).is_ok() {motifs.feed_forward += 1};
+                if succ[c].binary_search(&a).is_ok() {motifs.cycles += 1};
+            }
+        }
+    }
+    // Every cycle is found once per rotation.
+    motifs.cycles /= 3;
+    motifs
+}
+
+/// Returns the successors of every node,
+/// sorted and without duplicates nor the node itself.
+fn simple_successors(inc: &Incidence) -> Vec<Vec<usize>> {
+    inc.out.iter().enumerate().map(|(i, out)| {
+        let mut list: Vec<usize> = out.iter().map(|&(j, _)| j).filter(|&j| j != i).collect();
+        list.sort_unstable();
+        list.dedup();
+        list
+    }).collect()
+}
+
+/// Stores how closed a graph is under composition of edges.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Closure {
+    /// The number of paths `a -> b -> c` with an edge `a -> c`.
+    pub closed: usize,
+    /// The number of paths `a -> b -> c` without an edge `a -> c`.
+    pub open: usize,
+}
+
+impl Closure {
+    /// Returns the ratio of closed paths to all paths.
+    ///
+    /// A graph without paths of length two is closed under composition, with ratio one.
+    pub fn ratio(&self) -> f64 {
+        let total = self.closed + self.open;
+        if total == 0 {1.0} else {self.closed as f64 / total as f64}
+    }
+}
+
+/// Counts paths `a -> b -> c` with and without the composed edge `a -> c`.
+///
+/// For every morphism `A -> B` and `B -> C`, there exists a morphism `A -> C`.
+/// This measures how much of this symmetry that is captured by a graph.
+///
+/// Paths where `a`, `b` and `c` are not distinct are ignored,
+/// the same way as in `count_motifs`.
+pub fn composition_closure<T, U>(graph: &Graph<T, U>) -> Closure {
+    let motifs = count_motifs(graph);
+    Closure {
+        closed: motifs.feed_forward,
+        open: motifs.paths - motifs.feed_forward,
+    }
+}
+
+/// Stores a composed edge that is missing from a graph, see `missing_composites`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MissingComposite<U> {
+    /// The nodes `a`, `b` and `c` of the path `a -> b -> c`.
+    pub path: [usize; 3],
+    /// The indices of the edges `a -> b` and `b -> c`.
+    pub edges: [usize; 2],
+    /// The composed data of the missing edge `a -> c`.
+    pub data: U,
+}
+
+/// Finds paths `a -> b -> c` where no edge `a -> c` has the data composed by `h`.
+///
+/// For every morphism `A -> B` and `B -> C`, there exists a morphism `A -> C`.
+/// This checks that a graph is closed under composition, comparing edge data with `==`.
+/// When `h` returns `Err(None)`, the edges do not compose and the path is skipped.
+///
+/// Paths where `a`, `b` and `c` are not distinct are ignored,
+/// the same way as in `composition_closure`.
+///
+/// Errors are handled the same way as when composing edges in `gen`.
+pub fn missing_composites<T, U, H, E>(
+    graph: &Graph<T, U>,
+    h: H,
+) -> GenResult<Vec<MissingComposite<U>>, E>
+    where U: PartialEq,
+          H: Fn(&U, &U) -> Result<U, Option<E>>
+{
+    let inc = Incidence::new(graph);
+    let mut res = vec![];
+    let mut error: Option<E> = None;
+    for (k, ([a, b], u)) in graph.1.iter().enumerate() {
+        let (a, b) = (*a, *b);
+        if a == b {continue};
+        for &(c, l) in &inc.out[b] {
+            if c == a || c == b {continue};
+            match h(u, &graph.1[l].1) {
+                Ok(data) => {
+                    if !inc.edges_between(a, c).any(|m| graph.1[m].1 == data) {
+                        res.push(MissingComposite {path: [a, b, c], edges: [k, l], data});
+                    }
+                }
+                Err(None) => {}
+                Err(Some(err)) => if error.is_none() {error = Some(err)},
+            }
+        }
+    }
+    into_result(res, error)
+}
+
+/// Asserts that a graph is closed under composition with `h`.
+///
+/// On failure, the missing composites from `algo::missing_composites` are printed with `Debug`,
+/// together with the first error from `h`, if any.
+///
+/// ```
+/// use graph_builder::assert_composition_closed;
+///
+/// let graph = (vec!["a", "b", "c"], vec![([0, 1], 1), ([1, 2], 2), ([0, 2], 3)]);
+/// assert_composition_closed!(graph, |x: &u32, y: &u32| Ok::<_, Option<()>>(x + y));
+/// ```
+#[macro_export]
+macro_rules! assert_composition_closed {
+    ($graph:expr, $h:expr $(,)?) => {
+        match $crate::algo::missing_composites(&$graph, $h) {
+            Ok(missing) => if !missing.is_empty() {
+                panic!("assertion failed: graph is not closed under composition\nmissing: {:?}",
+                    missing);
+            }
+            Err((missing, err)) => {
+                panic!("assertion failed: composing edges failed: {:?}\nmissing: {:?}",
+                    err, missing);
+            }
+        }
+    };
+}
+
+/// The image of every edge under a node mapping, see `functor_images`.
+///
+/// Every image is a path of edge indices in the target, where an empty path is an identity,
+/// or `None` when the edge has no image.
+pub type EdgeImages = Vec<Option<Vec<usize>>>;
+
+/// Returns `true` if a node mapping is a graph homomorphism,
+/// such that every edge `a -> b` in `source` has an edge `map[a] -> map[b]` in `target`.
+///
+/// Edge data is ignored. Use `functor_images` to compare edge data or map edges to paths.
+pub fn is_homomorphism<P, Q, T, U>(
+    source: &Graph<P, Q>,
+    target: &Graph<T, U>,
+    map: &[usize],
+) -> bool {
+    let inc = Incidence::new(target);
+    source.1.iter().all(|&([a, b], _)| inc.edges_between(map[a], map[b]).next().is_some())
+}
+
+/// Finds the image of every edge in `source` under a node mapping to `target`.
+///
+/// An edge `a -> b` maps to a path from `map[a]` to `map[b]` with at most `max_len` edges,
+/// where the data along the path is composed with `h`,
+/// and `edge_match` tells whether the composed data is compatible with the edge.
+/// When `map[a] == map[b]`, the edge might also map to the empty path,
+/// which is checked by calling `edge_match` with `None`.
+/// Shorter paths are preferred.
+///
+/// The mapping is a functor when every edge has an image.
+/// With `max_len` set to one, this checks a graph homomorphism that preserves edge data.
+///
+/// The number of paths grows exponentially with `max_len`, so it should be kept small.
+/// Errors are handled the same way as when composing edges in `gen`.
+pub fn functor_images<P, Q, T, U, H, EM, E>(
+    source: &Graph<P, Q>,
+    target: &Graph<T, U>,
+    map: &[usize],
+    max_len: usize,
+    h: H,
+    edge_match: EM,
+) -> GenResult<EdgeImages, E>
+    where U: Clone,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          EM: Fn(&Q, Option<&U>) -> bool
+{
+    /// Searches for paths with exactly `len` more edges.
+    #[allow(clippy::too_many_arguments)]
+    fn search<T, U, H, M, E>(
+        target: &Graph<T, U>,
+        inc: &Incidence,
+        i: usize,
+        end: usize,
+        data: Option<&U>,
+        len: usize,
+        path: &mut Vec<usize>,
+        h: &H,
+        found: &M,
+        error: &mut Option<E>,
+    ) -> bool
+        where U: Clone,
+              H: Fn(&U, &U) -> Result<U, Option<E>>,
+              M: Fn(&U) -> bool
+    {
+        if len == 0 {return i == end && data.map(found).unwrap_or(false)};
+        for &(j, k) in &inc.out[i] {
+            let next = match data {
+                None => target.1[k].1.clone(),
+                Some(data) => match h(data, &target.1[k].1) {
+                    Ok(next) => next,
+                    Err(None) => continue,
+                    Err(Some(err)) => {
+                        if error.is_none() {*error = Some(err)};
+                        continue;
+                    }
+                },
+            };
+            path.push(k);
+            if search(target, inc, j, end, Some(&next), len - 1, path, h, found, error) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+
+    let inc = Incidence::new(target);
+    let mut error: Option<E> = None;
+    let images = source.1.iter().map(|([a, b], q)| {
+        let (start, end) = (map[*a], map[*b]);
+        if start == end && edge_match(q, None) {return Some(vec![])};
+        let found = |u: &U| edge_match(q, Some(u));
+        let mut path = vec![];
+        (1..=max_len).find(|&len| {
+            search(target, &inc, start, end, None, len, &mut path, &h, &found, &mut error)
+        }).map(|_| path)
+    }).collect();
+    into_result(images, error)
+}
+
+/// Finds all maximal cliques with at least `min_size` nodes.
+///
+/// The direction of edges is ignored.
+/// A clique is a set of nodes where every pair is connected by an edge.
+/// A clique is maximal when no other node can be added to it.
+///
+/// Every clique is sorted by node index.
+///
+/// Uses the Bron-Kerbosch algorithm with pivoting.
+pub fn maximal_cliques<T, U>(graph: &Graph<T, U>, min_size: usize) -> Vec<Vec<usize>> {
+    let adj = Incidence::new(graph).undirected();
+    let mut res = vec![];
+    bron_kerbosch(&adj, &mut vec![], (0..adj.len()).collect(), vec![], min_size, &mut res);
+    res
+}
+
+/// Extends the clique `r` with nodes in `p`, where nodes in `x` were already tried.
+fn bron_kerbosch(
+    adj: &[Vec<usize>],
+    r: &mut Vec<usize>,
+    mut p: Vec<usize>,
+    mut x: Vec<usize>,
+    min_size: usize,
+    res: &mut Vec<Vec<usize>>,
+) {
+    if r.len() + p.len() < min_size {return};
+    if p.is_empty() {
+        if x.is_empty() {
+            let mut clique = r.clone();
+            clique.sort_unstable();
+            res.push(clique);
+        }
+        return;
+    }
+
+    // Pick the pivot with most neighbours in `p`.
+    let pivot = *p.iter().chain(&x)
+        .max_by_key(|&&u| p.iter().filter(|v| adj[u].binary_search(v).is_ok()).count())
+        .unwrap();
+    let candidates: Vec<usize> = p.iter().cloned()
+        .filter(|v| adj[pivot].binary_search(v).is_err()).collect();
+    for v in candidates {
+        let new_p = p.iter().cloned().filter(|w| adj[v].binary_search(w).is_ok()).collect();
+        let new_x = x.iter().cloned().filter(|w| adj[v].binary_search(w).is_ok()).collect();
+        r.push(v);
+        bron_kerbosch(adj, r, new_p, new_x, min_size, res);
+        r.pop();
+        p.retain(|&w| w != v);
+        x.push(v);
+    }
+}
+
+/// Finds a maximal independent set by picking nodes with fewest neighbours first.
+///
+/// The direction of edges and edges from a node to itself are ignored.
+/// An independent set is a set of nodes where no pair is connected by an edge.
+/// It is maximal when no other node can be added to it,
+/// but there might exist larger independent sets.
+///
+/// Returns the nodes sorted by index.
+pub fn greedy_independent_set<T, U>(graph: &Graph<T, U>) -> Vec<usize> {
+    let adj = Incidence::new(graph).undirected();
+    let mut order: Vec<usize> = (0..adj.len()).collect();
+    order.sort_by_key(|&i| adj[i].len());
+    let mut blocked = vec![false; adj.len()];
+    let mut res = vec![];
+    for i in order {
+        if blocked[i] {continue};
+        res.push(i);
+        blocked[i] = true;
+        for &j in &adj[i] {blocked[j] = true}
+    }
+    res.sort_unstable();
+    res
+}
+
+/// Finds a maximum independent set, which is an independent set with most nodes.
+///
+/// The direction of edges and edges from a node to itself are ignored.
+///
+/// This uses branch and bound, which takes exponential time in the worst case.
+/// Use `greedy_independent_set` for large graphs.
+///
+/// Returns the nodes sorted by index.
+pub fn maximum_independent_set<T, U>(graph: &Graph<T, U>) -> Vec<usize> {
+    let adj = Incidence::new(graph).undirected();
+    let mut best = greedy_independent_set(graph);
+    let mut alive = vec![true; adj.len()];
+    independent_branch(&adj, &mut alive, adj.len(), &mut vec![], &mut best);
+    best.sort_unstable();
+    best
+}
+
+/// Extends the independent set `cur` with nodes that are alive.
+fn independent_branch(
+    adj: &[Vec<usize>],
+    alive: &mut Vec<bool>,
+    alive_count: usize,
+    cur: &mut Vec<usize>,
+    best: &mut Vec<usize>,
+) {
+    if alive_count == 0 {
+        if cur.len() > best.len() {*best = cur.clone()};
+        return;
+    }
+    if cur.len() + alive_count <= best.len() {return};
+    // Branch on the alive node with most alive neighbours.
+    let degree = |i: usize, alive: &[bool]| adj[i].iter().filter(|&&j| alive[j]).count();
+    let v = (0..adj.len()).filter(|&i| alive[i]).max_by_key(|&i| degree(i, alive)).unwrap();
+    if degree(v, alive) == 0 {
+        // All alive nodes are isolated, so include all of them.
+        let len = cur.len();
+        cur.extend((0..adj.len()).filter(|&i| alive[i]));
+        if cur.len() > best.len() {*best = cur.clone()};
+        cur.truncate(len);
+        return;
+    }
+
+    // Include `v`, which removes its neighbours.
+    let removed: Vec<usize> = std::iter::once(v)
+        .chain(adj[v].iter().cloned().filter(|&j| alive[j])).collect();
+    for &i in &removed {alive[i] = false}
+    cur.push(v);
+    independent_branch(adj, alive, alive_count - removed.len(), cur, best);
+    cur.pop();
+    for &i in &removed {alive[i] = true}
+
+    // Exclude `v`.
+    alive[v] = false;
+    independent_branch(adj, alive, alive_count - 1, cur, best);
+    alive[v] = true;
+}
+
+/// Colors nodes such that no pair of connected nodes has the same color.
+///
+/// The direction of edges and edges from a node to itself are ignored.
+/// Returns the color of every node, where colors are numbered from zero.
+///
+/// Nodes are colored greedily in order of most neighbours first,
+/// using the smallest color not used by any neighbour.
+/// This does not necessarily use the fewest number of colors.
+pub fn greedy_coloring<T, U>(graph: &Graph<T, U>) -> Vec<usize> {
+    let adj = Incidence::new(graph).undirected();
+    let mut order: Vec<usize> = (0..adj.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(adj[i].len()));
+    let mut color: Vec<Option<usize>> = vec![None; adj.len()];
+    let mut used: Vec<bool> = vec![];
+    for i in order {
+        used.clear();
+        used.resize(adj[i].len() + 1, false);
+        for &j in &adj[i] {
+            if let Some(c) = color[j] {
+                if c < used.len() {used[c] = true};
+            }
+        }
+        color[i] = used.iter().position(|&u| !u);
+    }
+    color.into_iter().map(|c| c.unwrap()).collect()
+}
+
+/// Splits nodes into two parts such that every edge connects nodes of different parts.
+///
+/// The direction of edges is ignored.
+/// Returns `None` if the graph is not bipartite,
+/// for example when there is an edge from a node to itself.
+///
+/// The first node of every connected component is put in the first part.
+/// Both parts are sorted by node index.
+pub fn bipartition<T, U>(graph: &Graph<T, U>) -> Option<(Vec<usize>, Vec<usize>)> {
+    use std::collections::VecDeque;
+
+    if graph.1.iter().any(|&([a, b], _)| a == b) {return None};
+
+    let adj = Incidence::new(graph).undirected();
+    let mut side: Vec<Option<bool>> = vec![None; adj.len()];
+    let mut queue = VecDeque::new();
+    for s in 0..adj.len() {
+        if side[s].is_some() {continue};
+        side[s] = Some(false);
+        queue.push_back(s);
+        while let Some(i) = queue.pop_front() {
+            let si = side[i].unwrap();
+            for &j in &adj[i] {
+                match side[j] {
+                    None => {
+                        side[j] = Some(!si);
+                        queue.push_back(j);
+                    }
+                    Some(sj) if sj == si => return None,
+                    Some(_) => {}
+                }
+            }
+        }
+    }
+    let (a, b): (Vec<usize>, Vec<usize>) = (0..adj.len()).partition(|&i| side[i] == Some(false));
+    Some((a, b))
+}
+
+/// Stores a spanning tree of the nodes reachable from a root.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpanningTree<U> {
+    /// The index of the tree edge entering every node.
+    ///
+    /// This is `None` for the root and nodes that are not reachable.
+    pub parent: Vec<Option<usize>>,
+    /// The edges along the tree path from the root to every node, composed into one.
+    ///
+    /// This is `None` for the root, nodes that are not reachable,
+    /// and nodes where composing failed.
+    pub ops: Vec<Option<U>>,
+}
+
+/// Extracts a spanning tree from a root, following edges in their direction.
+///
+/// When `depth_first` is `true`, the tree is found by depth-first search,
+/// otherwise by breadth-first search, which gives the shortest path to every node.
+///
+/// The edges along the path from the root to every node are composed with `h`.
+/// This gives one canonical derivation for every reachable node.
+///
+/// Errors are handled the same way as when composing edges in `gen`.
+pub fn spanning_tree<T, U, H, E>(
+    graph: &Graph<T, U>,
+    root: usize,
+    depth_first: bool,
+    h: H,
+) -> GenResult<SpanningTree<U>, E>
+    where U: Clone,
+          H: Fn(&U, &U) -> Result<U, Option<E>>
+{
+    use crate::traverse::{Bfs, Dfs};
+
+    let inc = Incidence::new(graph);
+    let n = graph.0.len();
+    let mut tree = SpanningTree {parent: vec![None; n], ops: vec![None; n]};
+    let mut error: Option<E> = None;
+    let order: Box<dyn Iterator<Item = (usize, Option<usize>)>> = if depth_first {
+        Box::new(Dfs::with_incidence(&inc, root))
+    } else {
+        Box::new(Bfs::with_incidence(&inc, root))
+    };
+    for (i, e) in order {
+        if let Some(e) = e {
+            let ([a, _], ref u) = graph.1[e];
+            tree.parent[i] = Some(e);
+            tree.ops[i] = if a == root {Some(u.clone())}
+                else if let Some(prev) = &tree.ops[a] {
+                    match h(prev, u) {
+                        Ok(op) => Some(op),
+                        Err(None) => None,
+                        Err(Some(err)) => {
+                            if error.is_none() {error = Some(err)};
+                            None
+                        }
+                    }
+                } else {None};
+        }
+    }
+    into_result(tree, error)
+}
+
+/// Finds a minimum spanning forest, where `cost` computes the cost of an edge.
+///
+/// The direction of edges is ignored.
+/// Returns the indices of the selected edges, sorted by cost.
+/// When the graph is not connected, there is one tree per connected component.
+///
+/// Uses Kruskal's algorithm.
+pub fn minimum_spanning_tree<T, U, K, C>(graph: &Graph<T, U>, cost: K) -> Vec<usize>
+    where K: Fn(&U) -> C, C: PartialOrd
+{
+    use std::cmp::Ordering;
+
+    let costs: Vec<C> = graph.1.iter().map(|(_, u)| cost(u)).collect();
+    let mut order: Vec<usize> = (0..graph.1.len()).collect();
+    order.sort_by(|&a, &b| costs[a].partial_cmp(&costs[b]).unwrap_or(Ordering::Equal));
+    let mut sets = DisjointSets::new(graph.0.len());
+    order.into_iter().filter(|&k| {
+        let [a, b] = graph.1[k].0;
+        sets.union(a, b)
+    }).collect()
+}
+
+/// Stores disjoint sets of nodes.
+struct DisjointSets {
+    parent: Vec<usize>,
+}
+
+impl DisjointSets {
+    fn new(n: usize) -> DisjointSets {
+        DisjointSets {parent: (0..n).collect()}
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    /// Joins the sets of two nodes, returning `false` if they were already joined.
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {return false};
+        self.parent[b] = a;
+        true
+    }
+}
+
+/// Computes the PageRank of every node.
+///
+/// The rank of a node is the probability of being at the node
+/// when walking randomly along edges, where the walk jumps to a random node
+/// with probability `1 - damping` at every step.
+/// A typical value of `damping` is `0.85`.
+///
+/// Nodes without outgoing edges distribute their rank evenly to all nodes.
+/// Parallel edges are followed with probability proportional to their number.
+///
+/// Runs the given number of iterations, starting with equal ranks.
+/// The ranks sum to one.
+#[allow(clippy::needless_range_loop)]
+pub fn pagerank<T, U>(graph: &Graph<T, U>, damping: f64, iterations: usize) -> Vec<f64> {
+    let n = graph.0.len();
+    if n == 0 {return vec![]};
+
+    let inc = Incidence::new(graph);
+    let mut rank = vec![1.0 / n as f64; n];
+    let mut next = vec![0.0; n];
+    for _ in 0..iterations {
+        let dangling: f64 = (0..n).filter(|&i| inc.out[i].is_empty()).map(|i| rank[i]).sum();
+        let base = (1.0 - damping + damping * dangling) / n as f64;
+        for x in next.iter_mut() {*x = base}
+        for i in 0..n {
+            if inc.out[i].is_empty() {continue};
+            let share = damping * rank[i] / inc.out[i].len() as f64;
+            for &(j, _) in &inc.out[i] {next[j] += share}
+        }
+        std::mem::swap(&mut rank, &mut next);
+    }
+    rank
+}
+
+/// Stores the betweenness centrality of nodes and edges.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Betweenness {
+    /// The betweenness of every node.
+    pub nodes: Vec<f64>,
+    /// The betweenness of every edge.
+    pub edges: Vec<f64>,
+}
+
+/// Computes the betweenness centrality of every node and edge.
+///
+/// The betweenness of a node is the number of shortest paths between other nodes
+/// that pass through it, where every pair of nodes shares one unit between
+/// all shortest paths between them.
+/// The betweenness of an edge is computed the same way.
+/// Edges are followed in their direction and all edges have length one.
+///
+/// Nodes and edges with high betweenness are bottlenecks that shortest paths depend on.
+///
+/// Uses Brandes' algorithm, which takes `O(nodes * edges)` time.
+/// For large graphs, use `betweenness_sampled`.
+pub fn betweenness<T, U>(graph: &Graph<T, U>) -> Betweenness {
+    brandes(graph, 0..graph.0.len(), 1.0)
+}
+
+/// Estimates the betweenness centrality from shortest paths starting at random nodes.
+///
+/// This works the same way as `betweenness`,
+/// but only counts shortest paths from `samples` distinct random nodes,
+/// scaling the result by the ratio of nodes to samples.
+///
+/// The function `rand` returns a random number below its argument.
+pub fn betweenness_sampled<T, U, R>(graph: &Graph<T, U>, samples: usize, mut rand: R) -> Betweenness
+    where R: FnMut(usize) -> usize
+{
+    let n = graph.0.len();
+    let samples = samples.min(n);
+    let mut sources: Vec<usize> = (0..n).collect();
+    for i in 0..samples {
+        let j = i + rand(n - i);
+        sources.swap(i, j);
+    }
+    sources.truncate(samples);
+    let scale = if samples == 0 {0.0} else {n as f64 / samples as f64};
+    brandes(graph, sources, scale)
+}
+
+/// Accumulates betweenness from shortest paths starting at the sources.
+fn brandes<T, U, I>(graph: &Graph<T, U>, sources: I, scale: f64) -> Betweenness
+    where I: IntoIterator<Item = usize>
+{
+    use std::collections::VecDeque;
+
+    let n = graph.0.len();
+    let inc = Incidence::new(graph);
+    let mut res = Betweenness {nodes: vec![0.0; n], edges: vec![0.0; graph.1.len()]};
+    let mut dist: Vec<Option<usize>> = vec![None; n];
+    let mut sigma: Vec<f64> = vec![0.0; n];
+    let mut delta: Vec<f64> = vec![0.0; n];
+    let mut order: Vec<usize> = vec![];
+    let mut queue = VecDeque::new();
+    for s in sources {
+        for i in 0..n {
+            dist[i] = None;
+            sigma[i] = 0.0;
+            delta[i] = 0.0;
+        }
+        order.clear();
+        dist[s] = Some(0);
+        sigma[s] = 1.0;
+        queue.push_back(s);
+        while let Some(v) = queue.pop_front() {
+            order.push(v);
+            let dv = dist[v].unwrap();
+            for &(w, _) in &inc.out[v] {
+                if dist[w].is_none() {
+                    dist[w] = Some(dv + 1);
+                    queue.push_back(w);
+                }
+                if dist[w] == Some(dv + 1) {sigma[w] += sigma[v]};
+            }
+        }
+        for &w in order.iter().rev() {
+            let dw = dist[w].unwrap();
+            for &(v, k) in &inc.inc[w] {
+                if dw > 0 && dist[v] == Some(dw - 1) {
+                    let c = sigma[v] / sigma[w] * (1.0 + delta[w]);
+                    res.edges[k] += c * scale;
+                    delta[v] += c;
+                }
+            }
+            if w != s {res.nodes[w] += delta[w] * scale};
+        }
+    }
+    res
+}
+
+/// Stores the condensation of a graph, where every strongly connected component is one node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Condensation {
+    /// The acyclic graph of components.
+    ///
+    /// Every node contains the sorted list of nodes in the component.
+    /// Components are sorted in topological order, such that edges go from lower to higher index.
+    /// There is one edge between two connected components,
+    /// which contains the index of the first edge in the original graph between them.
+    pub graph: Graph<Vec<usize>, usize>,
+    /// The component of every node.
+    pub component: Vec<usize>,
+}
+
+/// Collapses every strongly connected component into one node.
+///
+/// A strongly connected component is a set of nodes where every node can reach every other.
+/// The result is an acyclic graph, which for rewriting systems tells the termination structure.
+///
+/// Uses Tarjan's algorithm.
+pub fn condense<T, U>(graph: &Graph<T, U>) -> Condensation {
+    let n = graph.0.len();
+    let inc = Incidence::new(graph);
+    let mut index: Vec<Option<usize>> = vec![None; n];
+    let mut low: Vec<usize> = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut stack: Vec<usize> = vec![];
+    let mut components: Vec<Vec<usize>> = vec![];
+    let mut counter = 0;
+    // Stores nodes being visited with the position of the next edge.
+    let mut call: Vec<(usize, usize)> = vec![];
+    for root in 0..n {
+        if index[root].is_some() {continue};
+        call.push((root, 0));
+        while let Some(&(v, pos)) = call.last() {
+            if pos == 0 {
+                index[v] = Some(counter);
+                low[v] = counter;
+                counter += 1;
+                stack.push(v);
+                on_stack[v] = true;
+            }
+            if let Some(&(w, _)) = inc.out[v].get(pos) {
+                call.last_mut().unwrap().1 += 1;
+                match index[w] {
+                    None => call.push((w, 0)),
+                    Some(iw) if on_stack[w] => low[v] = low[v].min(iw),
+                    Some(_) => {}
+                }
+                continue;
+            }
+            call.pop();
+            if let Some(&(parent, _)) = call.last() {low[parent] = low[parent].min(low[v])};
+            if Some(low[v]) == index[v] {
+                let mut comp = vec![];
+                loop {
+                    let w = stack.pop().unwrap();
+                    on_stack[w] = false;
+                    comp.push(w);
+                    if w == v {break};
+                }
+                comp.sort_unstable();
+                components.push(comp);
+            }
+        }
+    }
+    // Tarjan's algorithm finds components in reverse topological order.
+    components.reverse();
+    let mut component = vec![0; n];
+    for (c, comp) in components.iter().enumerate() {
+        for &i in comp {component[i] = c}
+    }
+    let mut has_edge = std::collections::HashSet::new();
+    let mut edges = vec![];
+    for (k, &([a, b], _)) in graph.1.iter().enumerate() {
+        let key = [component[a], component[b]];
+        if key[0] != key[1] && has_edge.insert(key) {edges.push((key, k))};
+    }
+    Condensation {graph: (components, edges), component}
+}
+
+/// Computes the immediate dominator of every node reachable from a root.
+///
+/// A node `d` dominates `i` when every path from the root to `i` passes through `d`.
+/// The immediate dominator of `i` is the dominator closest to `i`, other than `i` itself.
+/// These form a tree, where the dominators of a node are found by following
+/// immediate dominators up to the root.
+///
+/// Dominators tell which intermediate nodes are unavoidable on every derivation of a node.
+///
+/// Returns `None` for the root and nodes that are not reachable.
+///
+/// Uses the algorithm by Cooper, Harvey and Kennedy.
+pub fn dominators<T, U>(graph: &Graph<T, U>, root: usize) -> Vec<Option<usize>> {
+    use crate::traverse::{visit_dfs, Visitor};
+
+    struct PostOrder(Vec<usize>);
+
+    impl<T, U> Visitor<T, U> for PostOrder {
+        fn on_finish(&mut self, i: usize, _node: &T) {self.0.push(i)}
+    }
+
+    let n = graph.0.len();
+    let inc = Incidence::new(graph);
+    let mut post = PostOrder(vec![]);
+    visit_dfs(graph, Some(root), &mut post);
+    let post = post.0;
+    let mut post_index: Vec<Option<usize>> = vec![None; n];
+    for (k, &i) in post.iter().enumerate() {post_index[i] = Some(k)}
+
+    let mut idom: Vec<Option<usize>> = vec![None; n];
+    idom[root] = Some(root);
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &i in post.iter().rev() {
+            if i == root {continue};
+            let mut new_idom: Option<usize> = None;
+            for &(p, _) in &inc.inc[i] {
+                if idom[p].is_none() {continue};
+                new_idom = Some(if let Some(mut a) = new_idom {
+                    // Walk up the tree until both fingers meet.
+                    let mut b = p;
+                    while a != b {
+                        while post_index[a] < post_index[b] {a = idom[a].unwrap()}
+                        while post_index[b] < post_index[a] {b = idom[b].unwrap()}
+                    }
+                    a
+                } else {p});
+            }
+            if new_idom != idom[i] {
+                idom[i] = new_idom;
+                changed = true;
+            }
+        }
+    }
+    idom[root] = None;
+    idom
+}
+
+/// Assigns every node of an acyclic graph to a layer by the longest path to it.
+///
+/// Nodes without incoming edges are in layer zero,
+/// and every other node is one layer after its furthest predecessor.
+/// This means that every edge goes from a lower layer to a higher one.
+///
+/// Returns the layer of every node and the nodes grouped by layer,
+/// or `None` if the graph has a cycle.
+pub fn layers<T, U>(graph: &Graph<T, U>) -> Option<(Vec<usize>, Vec<Vec<usize>>)> {
+    let n = graph.0.len();
+    let inc = Incidence::new(graph);
+    let mut remaining: Vec<usize> = (0..n).map(|i| inc.inc[i].len()).collect();
+    let mut layer = vec![0; n];
+    let mut ready: Vec<usize> = (0..n).filter(|&i| remaining[i] == 0).collect();
+    let mut count = 0;
+    while let Some(i) = ready.pop() {
+        count += 1;
+        for &(j, _) in &inc.out[i] {
+            layer[j] = layer[j].max(layer[i] + 1);
+            remaining[j] -= 1;
+            if remaining[j] == 0 {ready.push(j)};
+        }
+    }
+    if count < n {return None};
+
+    let mut groups: Vec<Vec<usize>> = vec![vec![]; layer.iter().map(|&l| l + 1).max().unwrap_or(0)];
+    for i in 0..n {groups[layer[i]].push(i)}
+    Some((layer, groups))
+}
+
+/// Computes the transitive reduction of an acyclic graph.
+///
+/// An edge is removed when its target can be reached from its source by another path.
+/// Self-loops and parallel edges are also removed.
+/// For a partial order, the result is the covering relation, known as the Hasse diagram.
+///
+/// Returns the indices of the kept edges in increasing order,
+/// or `None` if the graph has a cycle other than self-loops.
+///
+/// Stores the set of reachable nodes for every node as a bitset,
+/// which uses `n * n / 8` bytes for `n` nodes.
+pub fn transitive_reduction<T, U>(graph: &Graph<T, U>) -> Option<Vec<usize>> {
+    let n = graph.0.len();
+    let words = n.div_ceil(64);
+    let loopless: Graph<(), ()> = (vec![(); n],
+        graph.1.iter().filter(|(e, _)| e[0] != e[1]).map(|&(e, _)| (e, ())).collect());
+    let (layer, groups) = layers(&loopless)?;
+    let inc = Incidence::new(graph);
+    let mut reach: Vec<Vec<u64>> = vec![vec![]; n];
+    let mut keep = vec![];
+    // Visit targets in increasing layer order, such that when a target can be reached
+    // through another target, the other target has already been visited.
+    for group in groups.iter().rev() {
+        for &a in group {
+            let mut out: Vec<(usize, usize)> =
+                inc.out[a].iter().filter(|&&(b, _)| b != a).cloned().collect();
+            out.sort_by_key(|&(b, k)| (layer[b], k));
+            let mut r = vec![0u64; words];
+            for (b, k) in out {
+                if (r[b / 64] >> (b % 64)) & 1 == 1 {continue};
+                keep.push(k);
+                r[b / 64] |= 1 << (b % 64);
+                for w in 0..words {r[w] |= reach[b][w]}
+            }
+            reach[a] = r;
+        }
+    }
+    keep.sort_unstable();
+    Some(keep)
+}
+
+/// Constructs the minor of a graph given by a partition of nodes into branch sets.
+///
+/// The branch set of every node is given by `branch`, where `None` deletes the node.
+/// Branch sets are numbered `0..k`, and become the nodes of the minor,
+/// which store the nodes of the branch set in increasing order.
+///
+/// Edges inside a branch set are contracted and edges to deleted nodes are removed.
+/// Edges between two branch sets become edges between the nodes of the minor,
+/// where parallel edges in the same direction are combined with `combine`
+/// in the order they are stored in the graph.
+///
+/// Returns `None` if a branch set is empty or is not connected by its own edges,
+/// ignoring the direction of edges.
+pub fn minor<T, U, C>(
+    graph: &Graph<T, U>,
+    branch: &[Option<usize>],
+    combine: C,
+) -> Option<Graph<Vec<usize>, U>>
+    where U: Clone, C: Fn(&U, &U) -> U
+{
+    use std::collections::HashMap;
+
+    let k = branch.iter().filter_map(|&b| b).max().map(|b| b + 1).unwrap_or(0);
+    let mut nodes: Vec<Vec<usize>> = vec![vec![]; k];
+    for (i, &b) in branch.iter().enumerate() {
+        if let Some(b) = b {nodes[b].push(i)};
+    }
+    if nodes.iter().any(|set| set.is_empty()) {return None};
+    let mut sets = DisjointSets::new(graph.0.len());
+    let mut edges: Vec<([usize; 2], U)> = vec![];
+    let mut edge_pos: HashMap<[usize; 2], usize> = HashMap::new();
+    for ([a, b], u) in &graph.1 {
+        if let (Some(x), Some(y)) = (branch[*a], branch[*b]) {
+            if x == y {
+                sets.union(*a, *b);
+            } else if let Some(&pos) = edge_pos.get(&[x, y]) {
+                edges[pos].1 = combine(&edges[pos].1, u);
+            } else {
+                edge_pos.insert([x, y], edges.len());
+                edges.push(([x, y], u.clone()));
+            }
+        }
+    }
+    for set in &nodes {
+        let root = sets.find(set[0]);
+        if set.iter().any(|&i| sets.find(i) != root) {return None};
+    }
+    Some((nodes, edges))
+}
+
+/// Stores the distribution of out-degrees and the nodes with most outgoing edges.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Degrees {
+    /// The number of nodes with out-degree `d` at index `d`.
+    pub histogram: Vec<usize>,
+    /// The nodes with highest out-degree, as pairs of node index and out-degree,
+    /// sorted by decreasing out-degree and then by node index.
+    pub hubs: Vec<(usize, usize)>,
+}
+
+/// Computes the out-degree distribution and the `k` nodes with highest out-degree.
+///
+/// Parallel edges and edges from a node to itself are counted.
+/// The histogram tells how many operations typically produce a new edge,
+/// which helps choosing limits and filters.
+pub fn out_degrees<T, U>(graph: &Graph<T, U>, k: usize) -> Degrees {
+    let mut degree = vec![0; graph.0.len()];
+    for &([a, _], _) in &graph.1 {degree[a] += 1}
+    let mut histogram = vec![0; degree.iter().max().map(|&d| d + 1).unwrap_or(0)];
+    for &d in &degree {histogram[d] += 1}
+    let mut hubs: Vec<(usize, usize)> = degree.into_iter().enumerate().collect();
+    hubs.sort_by_key(|&(i, d)| (std::cmp::Reverse(d), i));
+    hubs.truncate(k);
+    Degrees {histogram, hubs}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subgraph_isomorphisms() {
+        // The square `0 -> 1 -> 3`, `0 -> 2 -> 3` with a diagonal `0 -> 3`.
+        let graph = (vec!['a', 'b', 'b', 'c'],
+            vec![([0, 1], 1), ([1, 3], 2), ([0, 2], 1), ([2, 3], 2), ([0, 3], 3)]);
+        let path = (vec![(); 3], vec![([0, 1], ()), ([1, 2], ())]);
+        let mut found = find_subgraph_isomorphisms(&path, &graph, |_, _| true, |_, _| true);
+        found.sort();
+        assert_eq!(found, vec![vec![0, 1, 3], vec![0, 2, 3]]);
+
+        // Node and edge data restrict the matches.
+        let labeled = (vec!['a', 'c'], vec![([0, 1], 3)]);
+        let found = find_subgraph_isomorphisms(&labeled, &graph, |a, b| a == b, |a, b| a == b);
+        assert_eq!(found, vec![vec![0, 3]]);
+        let found = find_subgraph_isomorphisms(&labeled, &graph, |a, b| a == b, |_, b| *b == 1);
+        assert!(found.is_empty());
+
+        // The symmetric square is found once per automorphism.
+        let square = (vec![(); 4], vec![([0, 1], ()), ([1, 3], ()), ([0, 2], ()), ([2, 3], ())]);
+        let mut found = find_subgraph_isomorphisms(&square, &graph, |_, _| true, |_, _| true);
+        found.sort();
+        assert_eq!(found, vec![vec![0, 1, 2, 3], vec![0, 2, 1, 3]]);
+
+        // Mappings are injective.
+        let cycle = (vec![(); 2], vec![([0, 1], ()), ([1, 0], ())]);
+        assert!(find_subgraph_isomorphisms(&cycle, &graph, |_, _| true, |_, _| true).is_empty());
+        let empty: Graph<(), ()> = (vec![], vec![]);
+        assert_eq!(find_subgraph_isomorphisms(&empty, &graph, |_, _| true, |_, _| true),
+            vec![Vec::<usize>::new()]);
+    }
+
+    #[test]
+    fn isomorphisms() {
+        let a = (vec!["x", "y", "z"], vec![([0, 1], 1), ([1, 2], 2), ([0, 1], 3)]);
+        let b = (vec!["z", "x", "y"], vec![([2, 0], 2), ([1, 2], 3), ([1, 2], 1)]);
+        assert_eq!(isomorphism(&a, &b, |x, y| x == y, |x, y| x == y), Some(vec![1, 2, 0]));
+        assert_graph_iso!(a, b);
+        assert_graph_iso!(a, b, "with message {}", 1);
+
+        // Parallel edges must match one to one.
+        let c = (vec!["z", "x", "y"], vec![([2, 0], 2), ([1, 2], 3), ([1, 2], 3)]);
+        assert!(!is_isomorphic(&a, &c));
+        // Reversed edges are different.
+        let d = (vec!["x", "y", "z"], vec![([1, 0], 1), ([1, 2], 2), ([0, 1], 3)]);
+        assert!(!is_isomorphic(&a, &d));
+        // Different node data.
+        let e = (vec!["x", "y", "w"], vec![([0, 1], 1), ([1, 2], 2), ([0, 1], 3)]);
+        assert!(!is_isomorphic(&a, &e));
+        assert!(isomorphism(&a, &e, |_, _| true, |x, y| x == y).is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "graphs are not isomorphic")]
+    fn assert_graph_iso_fails() {
+        assert_graph_iso!((vec![0, 1], vec![([0, 1], ())]), (vec![0, 1], vec![([1, 0], ())]));
+    }
+
+    #[test]
+    fn composition_closed() {
+        let h = |x: &u32, y: &u32| if *x == 0 {Err(None)} else {Ok::<_, Option<()>>(x + y)};
+        let graph = (vec![(); 4], vec![([0, 1], 1), ([1, 2], 2), ([0, 2], 3), ([2, 3], 0)]);
+        assert_eq!(missing_composites(&graph, h), Ok(vec![
+            MissingComposite {path: [1, 2, 3], edges: [1, 3], data: 2},
+            MissingComposite {path: [0, 2, 3], edges: [2, 3], data: 3},
+        ]));
+
+        let graph = (vec![(); 4], vec![([0, 1], 1), ([1, 2], 2), ([0, 2], 3), ([2, 3], 0),
+            ([0, 3], 3), ([1, 3], 2)]);
+        assert_composition_closed!(graph, h);
+
+        // Wrong data counts as missing.
+        let graph = (vec![(); 3], vec![([0, 1], 1), ([1, 2], 2), ([0, 2], 4)]);
+        assert_eq!(missing_composites(&graph, h).unwrap().len(), 1);
+        let failing = |_: &u32, _: &u32| Err::<u32, _>(Some("fail"));
+        assert_eq!(missing_composites(&graph, failing), Err((vec![], "fail")));
+    }
+
+    #[test]
+    #[should_panic(expected = "graph is not closed under composition")]
+    fn assert_composition_closed_fails() {
+        let graph = (vec![(); 3], vec![([0, 1], 1), ([1, 2], 2)]);
+        assert_composition_closed!(graph, |x: &u32, y: &u32| Ok::<_, Option<()>>(x + y));
+    }
+
+    #[test]
+    fn condensation() {
+        // Components `{0, 1}`, `{2}` and `{3, 4, 5}`, with `2` reached from both others.
+        let graph = (vec![(); 6], vec![([0, 1], ()), ([1, 0], ()), ([1, 2], ()), ([3, 4], ()),
+            ([4, 5], ()), ([5, 3], ()), ([5, 2], ()), ([0, 2], ()), ([2, 2], ())]);
+        let c = condense(&graph);
+        let (nodes, edges) = &c.graph;
+        let mut sorted = nodes.clone();
+        sorted.sort();
+        assert_eq!(sorted, vec![vec![0, 1], vec![2], vec![3, 4, 5]]);
+        for (i, comp) in nodes.iter().enumerate() {
+            assert!(comp.iter().all(|&j| c.component[j] == i));
+        }
+        // One edge per connected pair of components, in topological order,
+        // storing the first original edge.
+        assert_eq!(edges.len(), 2);
+        assert!(edges.iter().all(|&([a, b], _)| a < b));
+        let first = |a: usize, b: usize| edges.iter()
+            .find(|e| e.0 == [c.component[a], c.component[b]]).map(|e| e.1);
+        assert_eq!(first(0, 2), Some(2));
+        assert_eq!(first(3, 2), Some(6));
+
+        let acyclic = (vec![(); 3], vec![([2, 1], ()), ([1, 0], ())]);
+        let c = condense(&acyclic);
+        assert_eq!(c.graph, (vec![vec![2], vec![1], vec![0]], vec![([0, 1], 0), ([1, 2], 1)]));
+        assert_eq!(c.component, vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn dominator_tree() {
+        // `0 -> 1 -> {2, 3} -> 4 -> 5`, with a back edge `4 -> 1` and an unreachable `6 -> 4`.
+        let graph = (vec![(); 7], vec![([0, 1], ()), ([1, 2], ()), ([1, 3], ()), ([2, 4], ()),
+            ([3, 4], ()), ([4, 5], ()), ([4, 1], ()), ([6, 4], ())]);
+        assert_eq!(dominators(&graph, 0),
+            vec![None, Some(0), Some(1), Some(1), Some(1), Some(4), None]);
+        assert_eq!(dominators(&graph, 2),
+            vec![None, Some(4), None, Some(1), Some(2), Some(4), None]);
+        // A shortcut `0 -> 4` removes `1` as a dominator of `4`.
+        let mut graph = graph;
+        graph.1.push(([0, 4], ()));
+        assert_eq!(dominators(&graph, 0),
+            vec![None, Some(0), Some(1), Some(1), Some(0), Some(4), None]);
+    }
+
+    #[test]
+    fn transitive_reductions() {
+        // Divisibility on `1, 2, 3, 6, 12`, with self-loops and a parallel edge.
+        let nodes = vec![1, 2, 3, 6, 12];
+        let mut edges = vec![];
+        for a in 0..5 {
+            for b in 0..5 {
+                if nodes[b] % nodes[a] == 0 {edges.push(([a, b], ()))};
+            }
+        }
+        edges.push(([2, 3], ()));
+        let graph = (nodes, edges);
+        let kept = transitive_reduction(&graph).unwrap();
+        let kept: Vec<[usize; 2]> = kept.into_iter().map(|k| graph.1[k].0).collect();
+        assert_eq!(kept, vec![[0, 1], [0, 2], [1, 3], [2, 3], [3, 4]]);
+
+        let cycle = (vec![(); 3], vec![([0, 1], ()), ([1, 2], ()), ([2, 0], ())]);
+        assert_eq!(transitive_reduction(&cycle), None);
+    }
+}
@@ -0,0 +1,279 @@
+//! Random graphs and generator functions for property testing.
+//!
+//! This module is enabled by the `arbitrary` feature.
+//!
+//! Random numbers come from a function that returns a number below its argument,
+//! the same way as in `search::gen_monte_carlo`, such that any random number generator works.
+//! For example, when using the `rand` crate: `|k| rng.gen_range(0..k)`.
+//! `Rng` is a small generator for tests without dependencies.
+//!
+//! When a property fails, `check` shrinks the counterexample with a shrinking function,
+//! e.g. `shrink_graph`, until no smaller value fails.
+//! The same functions can be used to implement strategies for `proptest` or `quickcheck`.
+
+use crate::Graph;
+
+/// A small pseudo-random number generator (xorshift64*).
+///
+/// This is not suitable for cryptography.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rng(u64);
+
+impl Rng {
+    /// Creates a new generator from a seed.
+    pub fn new(seed: u64) -> Rng {
+        // The state must not be zero.
+        Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    /// Returns the next random number.
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a random number below `k`, or zero when `k` is zero.
+    pub fn below(&mut self, k: usize) -> usize {
+        if k == 0 {0} else {(self.next_u64() % k as u64) as usize}
+    }
+}
+
+/// Creates a random graph with the given number of nodes and edges.
+///
+/// Nodes and edges are created by `node` and `edge` from their index.
+/// The ends of every edge are picked with `rand`, which might create self-loops
+/// and parallel edges. When there are no nodes, no edges are created.
+pub fn random_graph<T, U, R, N, M>(
+    nodes: usize,
+    edges: usize,
+    mut rand: R,
+    node: N,
+    mut edge: M,
+) -> Graph<T, U>
+    where R: FnMut(usize) -> usize,
+          N: FnMut(usize) -> T,
+          M: FnMut(usize) -> U
+{
+    let edges = if nodes == 0 {0} else {edges};
+    ((0..nodes).map(node).collect(),
+     (0..edges).map(|k| ([rand(nodes), rand(nodes)], edge(k))).collect())
+}
+
+/// Returns graphs that are one step smaller than a graph, for shrinking.
+///
+/// First every graph with one node removed, together with its edges,
+/// then every graph with one edge removed.
+pub fn shrink_graph<T: Clone, U: Clone>(graph: &Graph<T, U>) -> Vec<Graph<T, U>> {
+    let mut res = vec![];
+    for i in 0..graph.0.len() {
+        let mut nodes = graph.0.clone();
+        nodes.remove(i);
+        let map = |j: usize| if j > i {j - 1} else {j};
+        let edges = graph.1.iter().filter(|(e, _)| e[0] != i && e[1] != i)
+            .map(|([a, b], u)| ([map(*a), map(*b)], u.clone())).collect();
+        res.push((nodes, edges));
+    }
+    for k in 0..graph.1.len() {
+        let mut edges = graph.1.clone();
+        edges.remove(k);
+        res.push((graph.0.clone(), edges));
+    }
+    res
+}
+
+/// A random generator function over the states `0..states`, stored as a table.
+///
+/// Calling step `j` on state `i` returns the state `next[i][j]`, with `j` as edge,
+/// or an error when the entry is `None`.
+/// Since nodes are state indices, generating from any seed terminates.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RandomFn {
+    /// The result of every step for every state.
+    pub next: Vec<Vec<Option<usize>>>,
+}
+
+impl RandomFn {
+    /// Creates a random function with `n` steps per state,
+    /// where about `error_percent` percent of the entries are errors.
+    pub fn new<R>(states: usize, n: usize, error_percent: usize, mut rand: R) -> RandomFn
+        where R: FnMut(usize) -> usize
+    {
+        let next = (0..states).map(|_| (0..n).map(|_| {
+            if rand(100) < error_percent {None} else {Some(rand(states))}
+        }).collect()).collect();
+        RandomFn {next}
+    }
+
+    /// Returns the number of states.
+    pub fn states(&self) -> usize {self.next.len()}
+
+    /// Returns the number of steps per state.
+    pub fn steps(&self) -> usize {self.next.first().map(|s| s.len()).unwrap_or(0)}
+
+    /// Calls the function, for use as `f` with `gen`.
+    ///
+    /// Errors are `E::default()`, e.g. `()`.
+    /// States outside the table and steps past the end return an error.
+    pub fn call<E: Default>(&self, &i: &usize, j: usize) -> Result<(usize, usize), E> {
+        match self.next.get(i).and_then(|s| s.get(j)) {
+            Some(&Some(k)) => Ok((k, j)),
+            _ => Err(E::default()),
+        }
+    }
+
+    /// Returns functions that are one step smaller, for shrinking.
+    ///
+    /// First the function without the last state, where steps to it go to state `0`,
+    /// then without the last step, then with one error replaced by a self-loop.
+    pub fn shrink(&self) -> Vec<RandomFn> {
+        let mut res = vec![];
+        let m = self.states();
+        if m > 1 {
+            let next = self.next[..m - 1].iter().map(|s| s.iter().map(|k| {
+                k.map(|k| if k == m - 1 {0} else {k})
+            }).collect()).collect();
+            res.push(RandomFn {next});
+        }
+        if self.steps() > 0 {
+            let next = self.next.iter().map(|s| s[..s.len() - 1].to_vec()).collect();
+            res.push(RandomFn {next});
+        }
+        for i in 0..m {
+            for j in 0..self.next[i].len() {
+                if self.next[i][j].is_none() {
+                    let mut next = self.next.clone();
+                    next[i][j] = Some(i);
+                    res.push(RandomFn {next});
+                }
+            }
+        }
+        res
+    }
+}
+
+/// Shrinks a value where a property fails, until no smaller value fails.
+///
+/// Picks the first candidate from `shrink` where `property` returns `false`,
+/// and repeats until there are none.
+pub fn minimize<X, S, P>(mut value: X, shrink: S, property: P) -> X
+    where S: Fn(&X) -> Vec<X>,
+          P: Fn(&X) -> bool
+{
+    while let Some(smaller) = shrink(&value).into_iter().find(|x| !property(x)) {
+        value = smaller;
+    }
+    value
+}
+
+/// Checks a property on `cases` random values.
+///
+/// The function `arbitrary` creates a value from the index of the case.
+/// Returns the first value where `property` returns `false`,
+/// after shrinking it with `minimize`.
+pub fn check<X, A, S, P>(cases: usize, mut arbitrary: A, shrink: S, property: P) -> Result<(), X>
+    where A: FnMut(usize) -> X,
+          S: Fn(&X) -> Vec<X>,
+          P: Fn(&X) -> bool
+{
+    for case in 0..cases {
+        let value = arbitrary(case);
+        if !property(&value) {
+            return Err(minimize(value, shrink, property));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arbitrary_graph(case: usize) -> Graph<usize, usize> {
+        let mut rng = Rng::new(case as u64);
+        let (nodes, edges) = (rng.below(8), rng.below(16));
+        random_graph(nodes, edges, |k| rng.below(k), |i| i, |k| k)
+    }
+
+    fn valid_graph<T, U>(graph: &Graph<T, U>) -> bool {
+        graph.1.iter().all(|(e, _)| e[0] < graph.0.len() && e[1] < graph.0.len())
+    }
+
+    fn valid_fn(f: &RandomFn) -> bool {
+        f.next.iter().all(|s| s.len() == f.steps() && s.iter().flatten().all(|&k| k < f.states()))
+    }
+
+    fn errors(f: &RandomFn) -> usize {
+        f.next.iter().flatten().filter(|k| k.is_none()).count()
+    }
+
+    #[test]
+    fn shrink_graph_keeps_edges_in_range() {
+        for case in 0..100 {
+            let graph = arbitrary_graph(case);
+            assert!(valid_graph(&graph));
+            let smaller = shrink_graph(&graph);
+            assert_eq!(smaller.len(), graph.0.len() + graph.1.len());
+            for (k, g) in smaller.iter().enumerate() {
+                assert!(valid_graph(g));
+                if k < graph.0.len() {
+                    // Removing a node removes its edges and keeps the others,
+                    // where nodes and edges store their original index.
+                    assert_eq!(g.0.len(), graph.0.len() - 1);
+                    let kept = graph.1.iter().filter(|(e, _)| e[0] != k && e[1] != k);
+                    assert!(kept.zip(&g.1).all(|((_, u), (e, v))| {
+                        u == v && graph.1[*u].0.iter().zip(e).all(|(&a, &b)| g.0[b] == a)
+                    }));
+                } else {
+                    assert_eq!(g.0, graph.0);
+                    assert_eq!(g.1.len(), graph.1.len() - 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn shrink_fn_stays_valid_and_smaller() {
+        // Every candidate has fewer states, or fewer steps, or fewer errors.
+        let size = |f: &RandomFn| (f.states(), f.steps(), errors(f));
+        for case in 0..100 {
+            let mut rng = Rng::new(case);
+            let (states, steps) = (1 + rng.below(5), rng.below(4));
+            let f = RandomFn::new(states, steps, 30, |k| rng.below(k));
+            assert!(valid_fn(&f));
+            for g in f.shrink() {
+                assert!(valid_fn(&g));
+                assert!(size(&g) < size(&f));
+            }
+        }
+        let f = RandomFn {next: vec![vec![Some(1), None], vec![Some(1), Some(0)]]};
+        assert_eq!(f.shrink(), vec![
+            RandomFn {next: vec![vec![Some(0), None]]},
+            RandomFn {next: vec![vec![Some(1)], vec![Some(1)]]},
+            RandomFn {next: vec![vec![Some(1), Some(0)], vec![Some(1), Some(0)]]},
+        ]);
+    }
+
+    #[test]
+    fn minimize_reaches_local_minimum() {
+        // Fails for graphs with a self-loop.
+        let property = |g: &Graph<usize, usize>| g.1.iter().all(|(e, _)| e[0] != e[1]);
+        let mut failed = 0;
+        for case in 0..100 {
+            let graph = arbitrary_graph(case);
+            if property(&graph) {continue};
+            failed += 1;
+            let min = minimize(graph, shrink_graph, property);
+            assert!(!property(&min));
+            assert!(shrink_graph(&min).iter().all(property));
+            assert_eq!((min.0.len(), min.1.len()), (1, 1));
+        }
+        assert!(failed > 0);
+
+        let res = check(100, arbitrary_graph, shrink_graph, property);
+        let min = res.unwrap_err();
+        assert_eq!(min.1[0].0, [0, 0]);
+        assert_eq!(check(100, arbitrary_graph, shrink_graph, valid_graph), Ok(()));
+    }
+}
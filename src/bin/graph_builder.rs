@@ -0,0 +1,73 @@
+//! Generates a graph of a string-rewriting system from a specification file.
+//!
+//! Usage: `graph_builder <spec> [--format dot|json|csv] [--output <file>]`
+//!
+//! The specification is read with `Spec::from_json` when the file name ends with `.json`,
+//! otherwise with `Spec::from_toml`. See the `spec` module for the format.
+//!
+//! The graph is written to standard output unless `--output` is given.
+//! When generation hits a limit, the partial graph is written and a warning is printed.
+
+use std::process::exit;
+
+use graph_builder::export::{to_csv, to_cytoscape, to_dot};
+use graph_builder::spec::Spec;
+
+const USAGE: &str = "Usage: graph_builder <spec> [--format dot|json|csv] [--output <file>]";
+
+fn run(args: &[String]) -> Result<(), String> {
+    let mut spec_file = None;
+    let mut format = "dot".to_string();
+    let mut output = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match &**arg {
+            "--format" => format = args.next().ok_or("Missing value for `--format`")?.clone(),
+            "--output" | "-o" => output = Some(args.next().ok_or("Missing value for `--output`")?),
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ if spec_file.is_none() && !arg.starts_with('-') => spec_file = Some(arg),
+            _ => return Err(format!("Unexpected argument `{}`\n{}", arg, USAGE)),
+        }
+    }
+    let spec_file = spec_file.ok_or(USAGE)?;
+    let src = std::fs::read_to_string(spec_file)
+        .map_err(|err| format!("Could not read `{}`: {}", spec_file, err))?;
+    let spec = if spec_file.ends_with(".json") {Spec::from_json(&src)}
+        else {Spec::from_toml(&src)};
+    let spec = spec.map_err(|err| format!("{}: {}", spec_file, err))?;
+    let graph = match spec.run() {
+        Ok(graph) => graph,
+        Err((graph, err)) => {
+            eprintln!("Warning: {}, writing partial graph", err);
+            graph
+        }
+    };
+
+    let text = match &*format {
+        "dot" => to_dot(&graph, |t| t.clone(), |u| u.clone()),
+        "json" => to_cytoscape(&graph,
+            |_, t| vec![("label", t.as_str().into())],
+            |_, u| vec![("label", u.as_str().into())]),
+        "csv" => to_csv(&graph, |t| t.clone(), |u| u.clone()),
+        _ => return Err(format!("Unknown format `{}`, expected `dot`, `json` or `csv`", format)),
+    };
+    match output {
+        Some(file) => std::fs::write(file, text)
+            .map_err(|err| format!("Could not write `{}`: {}", file, err)),
+        None => {
+            print!("{}", text);
+            Ok(())
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(err) = run(&args) {
+        eprintln!("Error: {}", err);
+        exit(1);
+    }
+}
@@ -0,0 +1,140 @@
+//! Derivations of composed edges.
+//!
+//! Every composed edge is composed from two other edges,
+//! which might be composed themselves.
+//! The derivation of an edge is a tree with seed and generated edges as leaves,
+//! and all derivations together form a directed acyclic graph.
+//!
+//! A derivation can be printed as a bracketed expression of the generated edges,
+//! such that the data of a composed edge can be checked, e.g. as a proof term.
+
+use std::hash::Hash;
+
+use crate::{
+    expand_unit, into_result, post_process_observe, GenResult, GenerateError,
+    GenerateSettings, Graph,
+};
+
+/// Stores how an edge was created.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Step<U> {
+    /// An edge from the seed or generated by `f`, with its data.
+    Generated(U),
+    /// An edge composed from two edges, by id.
+    Composed([usize; 2]),
+}
+
+/// Stores the derivation of every edge, see `gen_derivation`.
+///
+/// Edges are identified by their index before removing nodes,
+/// the same way as in `EdgeProvenance`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Derivation<U> {
+    /// The id of every edge in the result.
+    pub ids: Vec<usize>,
+    /// How every edge was created, by id.
+    pub steps: Vec<Step<U>>,
+}
+
+impl<U> Derivation<U> {
+    /// Returns the ids of the generated edges that edge `id` is composed of,
+    /// in the order along its path.
+    pub fn primitives(&self, id: usize) -> Vec<usize> {
+        let mut res = vec![];
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            match self.steps[id] {
+                Step::Composed([a, b]) => {
+                    stack.push(b);
+                    stack.push(a);
+                }
+                Step::Generated(_) => res.push(id),
+            }
+        }
+        res
+    }
+
+    /// Returns the derivation of edge `id` as a bracketed expression.
+    ///
+    /// Generated edges are printed with `label`,
+    /// and a composed edge is printed as `(a b)` where `a` and `b` are its parts.
+    pub fn expression<L>(&self, id: usize, label: L) -> String
+        where L: Fn(&U) -> String
+    {
+        // Visits every composed edge twice, printing its parts the second time.
+        let mut parts: Vec<String> = vec![];
+        let mut stack = vec![(id, false)];
+        while let Some((id, visited)) = stack.pop() {
+            match self.steps[id] {
+                Step::Generated(ref u) => parts.push(label(u)),
+                Step::Composed(_) if visited => {
+                    let b = parts.pop().unwrap();
+                    let a = parts.pop().unwrap();
+                    parts.push(format!("({} {})", a, b));
+                }
+                Step::Composed([a, b]) => {
+                    stack.push((id, true));
+                    stack.push((b, false));
+                    stack.push((a, false));
+                }
+            }
+        }
+        parts.pop().unwrap()
+    }
+
+    /// Returns the derivation graph.
+    ///
+    /// There is one node for every edge id, storing the data of generated edges.
+    /// Every composed edge has an edge to its two parts, labeled `0` and `1` in path order,
+    /// such that generated edges are leaves.
+    pub fn graph(&self) -> Graph<Option<U>, usize>
+        where U: Clone
+    {
+        let mut nodes = vec![];
+        let mut edges = vec![];
+        for (id, step) in self.steps.iter().enumerate() {
+            match *step {
+                Step::Generated(ref u) => nodes.push(Some(u.clone())),
+                Step::Composed([a, b]) => {
+                    nodes.push(None);
+                    edges.push(([id, a], 0));
+                    edges.push(([id, b], 1));
+                }
+            }
+        }
+        (nodes, edges)
+    }
+}
+
+/// Generates a graph and the derivation of every edge.
+///
+/// This works the same way as `gen`, but also returns a `Derivation`,
+/// which stores a copy of the data of every generated edge,
+/// including edges that were removed in post-processing.
+pub fn gen_derivation<T, U, F, G, H, E>(
+    seed: Graph<T, U>,
+    n: usize,
+    f: F,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<(Graph<T, U>, Derivation<U>), E>
+    where T: Eq + Hash + Clone,
+          U: Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let mut exp = expand_unit(seed, n, f, settings);
+    let mut steps: Vec<Step<U>> =
+        exp.graph.1.iter().map(|(_, u)| Step::Generated(u.clone())).collect();
+    let (graph, _, map_edges) = post_process_observe(exp.graph, &mut exp.has_edge,
+        |_, t| g(t), |_, _, _, x, y| h(x, y), settings, &mut exp.error,
+        |_, from, _| steps.push(Step::Composed(from)));
+    let mut ids = vec![0; graph.1.len()];
+    for (id, k) in map_edges.into_iter().enumerate() {
+        if let Some(k) = k {ids[k] = id};
+    }
+    into_result((graph, Derivation {ids, steps}), exp.error)
+}
@@ -0,0 +1,304 @@
+//! Generating all solutions of an equation of the form:
+//!
+//! ```text
+//! x0 + x1 + x2 + ... + xn-2 = xn-1
+//! ```
+//!
+//! For example:
+//!
+//! ```text
+//! a + b = c
+//! c - a = b
+//! c - b = a
+//! ```
+//!
+//! Each solution is a node in a generated graph.
+//! An edge tells how to swap sides and sign to get from one node to another.
+//!
+//! To get from one solution to another, one only needs to move maximum two terms.
+//!
+//! If the right side is negative, automatic inversion is used.
+//! This improves the performance of the graph generation.
+//!
+//! The number of nodes from `n` terms and `m` right-side terms is:
+//!
+//! ```text
+//! bin(n, m)
+//! ```
+//!
+//! The number of edges is the number of pairs between nodes,
+//! after removing edges that are not bidirectional:
+//!
+//! ```text
+//! pairs(bin(n, m))
+//!
+//! pairs(n) = n * (n-1) / 2
+//! ```
+//!
+//! For example, with 4 terms and 2 right-side terms:
+//!
+//! ```
+//! use graph_builder::{bidir, GenerateSettings};
+//! use graph_builder::eq::gen_eq;
+//!
+//! let (eqs, mut edges) = gen_eq(4, 2, &GenerateSettings::new(1000, 1000)).unwrap();
+//! bidir(&mut edges);
+//! assert_eq!(eqs.len(), 6);
+//! assert_eq!(edges.len(), 15);
+//! ```
+
+use crate::{gen, GenResult, GenerateError, GenerateSettings, Graph};
+
+/// Stores an equation.
+#[derive(PartialEq, Eq, Clone, Hash, Debug)]
+pub struct Eq {
+    /// The sides of the terms.
+    pub side: Vec<bool>,
+    /// The signs of the terms.
+    pub positive: Vec<bool>,
+}
+
+impl Eq {
+    /// Creates an equation with `n` terms to start generating solutions
+    /// with `solution_terms` terms on the right side.
+    ///
+    /// When there is one solution term, all terms except the last one are put on the right.
+    pub fn start(n: usize, solution_terms: usize) -> Eq {
+        Eq {
+            side: {
+                if solution_terms == 1 && n > 0 {
+                    let mut res = vec![true; n-1];
+                    res.push(false);
+                    res
+                } else {
+                    vec![true; n]
+                }
+            },
+            positive: vec![true; n],
+        }
+    }
+
+    /// Returns the number of terms on the right.
+    pub fn len_right(&self) -> usize {
+        self.side.iter().filter(|&&n| n).count()
+    }
+
+    /// Returns an index if the equation has a unique right side.
+    pub fn unique_right(&self) -> Option<usize> {
+        let mut found = None;
+        for i in 0..self.side.len() {
+            if self.side[i] {
+                if found.is_some() {return None};
+                found = Some(i);
+            }
+        }
+        found
+    }
+
+    /// Returns a tuple of signs, one when positive and one when negative.
+    pub fn signs(&self) -> (&'static str, &'static str) {
+        if let Some(ind) = self.unique_right() {
+            if self.positive[ind] {("+", "-")}
+            else {("-", "+")}
+        } else {("+", "-")}
+    }
+}
+
+/// Stores swap operations.
+#[derive(Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Hash)]
+pub struct Swap(pub Vec<usize>);
+
+impl std::fmt::Display for Eq {
+    fn fmt(&self, w: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        let mut left: usize = 0;
+        let (plus, minus) = self.signs();
+        for i in 0..self.side.len() {
+            if !self.side[i] {
+                if self.positive[i] {write!(w, "{}", plus)?}
+                else {write!(w, "{}", minus)?}
+                write!(w, "x{} ", i)?;
+                left += 1;
+            }
+        }
+        if left == 0 {write!(w, "0 ")?}
+        write!(w, "= ")?;
+        if self.side.len() - left == 0 {
+            write!(w, "0")?;
+        } else {
+            for i in 0..self.side.len() {
+                if self.side[i] {
+                    if self.positive[i] {write!(w, "{}", plus)?}
+                    else {write!(w, "{}", minus)?}
+                    write!(w, "x{} ", i)?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Swaps side and sign on the chosen term.
+///
+/// This is the function `f` for `gen`, with one operation per term.
+pub fn f<E>(eq: &Eq, ind: usize) -> Result<(Eq, Swap), E> {
+    let mut eq = eq.clone();
+    eq.side[ind] = !eq.side[ind];
+    eq.positive[ind] = !eq.positive[ind];
+    Ok((eq, Swap(vec![ind])))
+}
+
+/// Returns the filter `g` for `gen` that keeps equations
+/// with the specified number of terms on the right side.
+pub fn g(solution_terms: usize) -> impl Fn(&Eq) -> bool {
+    move |eq| eq.len_right() == solution_terms
+}
+
+/// Joins swap operations.
+///
+/// This is the composer `h` for `gen`.
+/// Since these swap operations are commutative, order is required.
+pub fn h<E>(a: &Swap, b: &Swap) -> Result<Swap, Option<E>> {
+    if a.0 >= b.0 {Err(None)} else {Ok(Swap({
+        let mut a = a.0.clone();
+        a.extend_from_slice(&b.0);
+        a.sort();
+        a
+    }))}
+}
+
+/// Stores swap operations inline, for composing without allocating.
+///
+/// Requires the `inline_vec` feature.
+#[cfg(feature = "inline_vec")]
+pub type InlineSwap = crate::inline_vec::InlineVec<usize, 4>;
+
+/// Swaps side and sign on the chosen term, the same way as `f`,
+/// storing the swap operation inline.
+///
+/// Requires the `inline_vec` feature.
+#[cfg(feature = "inline_vec")]
+pub fn f_inline<E>(eq: &Eq, ind: usize) -> Result<(Eq, InlineSwap), E> {
+    f(eq, ind).map(|(eq, swap)| (eq, InlineSwap::from_slice(&swap.0)))
+}
+
+/// Joins swap operations the same way as `h`,
+/// without allocating when the result has at most 4 operations.
+///
+/// Requires the `inline_vec` feature.
+#[cfg(feature = "inline_vec")]
+pub fn h_inline<E>(a: &InlineSwap, b: &InlineSwap) -> Result<InlineSwap, Option<E>> {
+    if a >= b {Err(None)} else {Ok(a.merge_sorted(b))}
+}
+
+/// Generates all solutions of an equation with `n` terms
+/// and `solution_terms` terms on the right side.
+///
+/// To get the number of edges described in the module documentation,
+/// remove edges that are not bidirectional with `bidir`.
+pub fn gen_eq(
+    n: usize,
+    solution_terms: usize,
+    settings: &GenerateSettings,
+) -> GenResult<Graph<Eq, Swap>, GenerateError> {
+    let seed = (vec![Eq::start(n, solution_terms)], vec![]);
+    gen(seed, n, f, g(solution_terms), h, settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bidir;
+
+    fn bin(n: usize, k: usize) -> usize {
+        (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1))
+    }
+
+    fn pairs(n: usize) -> usize {
+        if n == 0 {0} else {n * (n - 1) / 2}
+    }
+
+    #[test]
+    fn start() {
+        assert_eq!(Eq::start(0, 0), Eq {side: vec![], positive: vec![]});
+        assert_eq!(Eq::start(0, 1), Eq {side: vec![], positive: vec![]});
+        assert_eq!(Eq::start(3, 1).side, vec![true, true, false]);
+        assert_eq!(Eq::start(3, 2).side, vec![true, true, true]);
+        assert_eq!(Eq::start(3, 1).positive, vec![true; 3]);
+        assert_eq!(Eq::start(3, 1).len_right(), 2);
+    }
+
+    #[test]
+    fn unique_right_and_signs() {
+        let eq = Eq {side: vec![false, true, false], positive: vec![true, true, true]};
+        assert_eq!(eq.unique_right(), Some(1));
+        assert_eq!(eq.signs(), ("+", "-"));
+        let eq = Eq {side: vec![false, true, false], positive: vec![true, false, true]};
+        assert_eq!(eq.signs(), ("-", "+"));
+        let eq = Eq {side: vec![true, true, false], positive: vec![false, false, true]};
+        assert_eq!(eq.unique_right(), None);
+        assert_eq!(eq.signs(), ("+", "-"));
+        assert_eq!(Eq::start(0, 0).unique_right(), None);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Eq::start(3, 1).to_string(), "+x2 = +x0 +x1 ");
+        assert_eq!(Eq::start(2, 2).to_string(), "0 = +x0 +x1 ");
+        assert_eq!(Eq::start(0, 1).to_string(), "0 = 0");
+        let eq = Eq {side: vec![false, true], positive: vec![true, false]};
+        assert_eq!(eq.to_string(), "-x0 = +x1 ");
+    }
+
+    #[test]
+    fn f_and_g() {
+        let (eq, swap) = f::<()>(&Eq::start(3, 1), 2).unwrap();
+        assert_eq!(eq, Eq {side: vec![true; 3], positive: vec![true, true, false]});
+        assert_eq!(swap, Swap(vec![2]));
+        assert!(g(3)(&eq));
+        assert!(!g(2)(&eq));
+        assert!(g(2)(&Eq::start(3, 1)));
+    }
+
+    #[test]
+    fn h_requires_order() {
+        assert_eq!(h::<()>(&Swap(vec![0]), &Swap(vec![2])), Ok(Swap(vec![0, 2])));
+        assert_eq!(h::<()>(&Swap(vec![0, 3]), &Swap(vec![1])), Ok(Swap(vec![0, 1, 3])));
+        assert_eq!(h::<()>(&Swap(vec![1]), &Swap(vec![1])), Err(None));
+        assert_eq!(h::<()>(&Swap(vec![2]), &Swap(vec![0])), Err(None));
+        assert_eq!(h::<()>(&Swap(vec![2]), &Swap(vec![0, 1])), Err(None));
+    }
+
+    #[test]
+    fn gen_eq_counts() {
+        let settings = GenerateSettings::new(10000, 100000);
+        for n in 1..7 {
+            for m in 1..n + 1 {
+                let (eqs, mut edges) = gen_eq(n, m, &settings).unwrap();
+                bidir(&mut edges);
+                assert_eq!(eqs.len(), bin(n, m), "n = {}, m = {}", n, m);
+                assert_eq!(edges.len(), pairs(bin(n, m)), "n = {}, m = {}", n, m);
+            }
+        }
+    }
+
+    #[cfg(feature = "inline_vec")]
+    #[test]
+    fn inline_matches_vec() {
+        let eq = Eq::start(4, 2);
+        for ind in 0..4 {
+            let (a, swap) = f::<()>(&eq, ind).unwrap();
+            let (b, inline) = f_inline::<()>(&eq, ind).unwrap();
+            assert_eq!(a, b);
+            assert_eq!(&*inline, &swap.0[..]);
+        }
+        let swaps = [vec![0], vec![1], vec![2], vec![0, 2], vec![1, 3], vec![0, 1, 2, 3, 4]];
+        for a in &swaps {
+            for b in &swaps {
+                let expected = h::<()>(&Swap(a.clone()), &Swap(b.clone())).map(|s| s.0);
+                let (x, y) = (InlineSwap::from_slice(a), InlineSwap::from_slice(b));
+                let res = h_inline::<()>(&x, &y).map(|s| s.to_vec());
+                assert_eq!(res, expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,345 @@
+//! Exporting graphs to text formats used by other tools.
+//!
+//! Every exporter takes closures that produce labels of nodes and edges.
+
+use std::fmt::Write;
+
+use crate::Graph;
+
+/// Converts a graph to the DOT format of Graphviz.
+///
+/// Nodes are named `n0, n1, ...` by index, labeled with `node_label`.
+/// Edges are labeled with `edge_label`, where empty labels are left out.
+///
+/// The output can be read back with `import::from_dot`.
+pub fn to_dot<T, U, N, L>(graph: &Graph<T, U>, node_label: N, edge_label: L) -> String
+    where N: Fn(&T) -> String, L: Fn(&U) -> String
+{
+    let escape = |s: String| s.replace('\\', "\\\\").replace('"', "\\\"")
+        .replace('\n', "\\n");
+    let mut res = String::from("digraph {\n");
+    for (i, node) in graph.0.iter().enumerate() {
+        writeln!(res, "    n{} [label=\"{}\"];", i, escape(node_label(node))).unwrap();
+    }
+    for ([a, b], edge) in &graph.1 {
+        let label = edge_label(edge);
+        if label.is_empty() {
+            writeln!(res, "    n{} -> n{};", a, b).unwrap();
+        } else {
+            writeln!(res, "    n{} -> n{} [label=\"{}\"];", a, b, escape(label)).unwrap();
+        }
+    }
+    res.push_str("}\n");
+    res
+}
+
+/// Converts a graph to a CSV edge list with the header `source,target,label`.
+///
+/// Endpoints are labeled with `node_label` and edges with `edge_label`.
+/// Nodes without edges are written as a row with an empty target and label,
+/// such that no node is lost.
+/// Fields are quoted when they are empty or contain commas, quotes or line breaks.
+pub fn to_csv<T, U, N, L>(graph: &Graph<T, U>, node_label: N, edge_label: L) -> String
+    where N: Fn(&T) -> String, L: Fn(&U) -> String
+{
+    let escape = |s: String| if s.is_empty() || s.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {s};
+    let labels: Vec<String> = graph.0.iter().map(|t| escape(node_label(t))).collect();
+    let mut has_edge = vec![false; graph.0.len()];
+    let mut res = String::from("source,target,label\n");
+    for ([a, b], edge) in &graph.1 {
+        has_edge[*a] = true;
+        has_edge[*b] = true;
+        writeln!(res, "{},{},{}", labels[*a], labels[*b], escape(edge_label(edge))).unwrap();
+    }
+    for (i, label) in labels.iter().enumerate() {
+        if !has_edge[i] {writeln!(res, "{},,", label).unwrap()};
+    }
+    res
+}
+
+/// Converts a graph to a Mermaid flowchart description.
+///
+/// Nodes are named `n0, n1, ...` by index, labeled with `node_label`.
+/// Edges are labeled with `edge_label`, where empty labels are left out.
+///
+/// The output can be pasted into Markdown documents that support Mermaid.
+pub fn to_mermaid<T, U, N, L>(graph: &Graph<T, U>, node_label: N, edge_label: L) -> String
+    where N: Fn(&T) -> String, L: Fn(&U) -> String
+{
+    let escape = |s: String| s.replace('"', "#quot;");
+    let mut res = String::from("graph TD\n");
+    for (i, node) in graph.0.iter().enumerate() {
+        writeln!(res, "    n{}[\"{}\"]", i, escape(node_label(node))).unwrap();
+    }
+    for ([a, b], edge) in &graph.1 {
+        let label = edge_label(edge);
+        if label.is_empty() {
+            writeln!(res, "    n{} --> n{}", a, b).unwrap();
+        } else {
+            writeln!(res, "    n{} -->|\"{}\"| n{}", a, escape(label), b).unwrap();
+        }
+    }
+    res
+}
+
+/// Converts a graph to a TikZ picture for LaTeX documents.
+///
+/// Nodes are named `n0, n1, ...` by index, labeled with `node_label`.
+/// Edges are labeled with `edge_label`, where empty labels are left out.
+/// Labels are inserted as LaTeX code, so they can contain math, e.g. `$x_0$`.
+///
+/// The position of every node is taken from `layout` when given,
+/// otherwise nodes are placed on a circle.
+/// Edges in both directions between two nodes are bent, such that they do not overlap.
+///
+/// Requires `\usetikzlibrary{arrows}` or a newer TikZ version for arrow tips.
+pub fn to_tikz<T, U, N, L>(
+    graph: &Graph<T, U>,
+    node_label: N,
+    edge_label: L,
+    layout: Option<&[(f64, f64)]>,
+) -> String
+    where N: Fn(&T) -> String, L: Fn(&U) -> String
+{
+    use std::collections::HashSet;
+
+    let n = graph.0.len();
+    let pos = |i: usize| -> (f64, f64) {
+        use std::f64::consts::PI;
+
+        if let Some(layout) = layout {layout[i]}
+        else {
+            let radius = 1.0 + n as f64 / 4.0;
+            let angle = PI / 2.0 - 2.0 * PI * i as f64 / n as f64;
+            (radius * angle.cos(), radius * angle.sin())
+        }
+    };
+    let has_edge: HashSet<[usize; 2]> = graph.1.iter().map(|e| e.0).collect();
+    let mut res = String::from("\\begin{tikzpicture}[->, >=stealth, auto]\n");
+    for (i, node) in graph.0.iter().enumerate() {
+        let (x, y) = pos(i);
+        writeln!(res, "  \\node (n{}) at ({:.3}, {:.3}) {{{}}};", i, x, y, node_label(node))
+            .unwrap();
+    }
+    for ([a, b], edge) in &graph.1 {
+        let label = edge_label(edge);
+        let label = if label.is_empty() {String::new()}
+            else {format!(" node {{{}}}", label)};
+        let path = if a == b {"to[loop above]"}
+            else if has_edge.contains(&[*b, *a]) {"to[bend left=15]"}
+            else {"to"};
+        writeln!(res, "  \\draw (n{}) {}{} (n{});", a, path, label, b).unwrap();
+    }
+    res.push_str("\\end{tikzpicture}\n");
+    res
+}
+
+/// Stores the value of a node or edge attribute.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// A text value.
+    Str(String),
+    /// A number.
+    Num(f64),
+    /// A boolean.
+    Bool(bool),
+}
+
+impl From<String> for Value {
+    fn from(val: String) -> Value {Value::Str(val)}
+}
+
+impl<'a> From<&'a str> for Value {
+    fn from(val: &'a str) -> Value {Value::Str(val.into())}
+}
+
+impl From<f64> for Value {
+    fn from(val: f64) -> Value {Value::Num(val)}
+}
+
+impl From<usize> for Value {
+    fn from(val: usize) -> Value {Value::Num(val as f64)}
+}
+
+impl From<bool> for Value {
+    fn from(val: bool) -> Value {Value::Bool(val)}
+}
+
+impl Value {
+    /// Writes the value as JSON.
+    fn write_json(&self, w: &mut String) {
+        match self {
+            Value::Str(s) => write_json_string(w, s),
+            Value::Num(x) if x.is_finite() => write!(w, "{}", x).unwrap(),
+            Value::Num(_) => w.push_str("null"),
+            Value::Bool(b) => write!(w, "{}", b).unwrap(),
+        }
+    }
+}
+
+/// Writes a string as a quoted JSON string.
+fn write_json_string(w: &mut String, s: &str) {
+    w.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => w.push_str("\\\""),
+            '\\' => w.push_str("\\\\"),
+            '\n' => w.push_str("\\n"),
+            '\r' => w.push_str("\\r"),
+            '\t' => w.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(w, "\\u{:04x}", c as u32).unwrap(),
+            c => w.push(c),
+        }
+    }
+    w.push('"');
+}
+
+/// Converts a graph to the elements JSON format of Cytoscape.js.
+///
+/// Nodes get the id `n0, n1, ...` and edges get the id `e0, e1, ...` by index.
+/// The attributes of every node and edge are added to its data,
+/// computed from the index and the value by `node_attr` and `edge_attr`.
+///
+/// For example, `|_, node| vec![("label", node.to_string().into())]`.
+pub fn to_cytoscape<T, U, N, L>(graph: &Graph<T, U>, node_attr: N, edge_attr: L) -> String
+    where N: Fn(usize, &T) -> Vec<(&'static str, Value)>,
+          L: Fn(usize, &U) -> Vec<(&'static str, Value)>
+{
+    let write_attr = |res: &mut String, attr: Vec<(&'static str, Value)>| {
+        for (key, val) in attr {
+            res.push_str(", ");
+            write_json_string(res, key);
+            res.push_str(": ");
+            val.write_json(res);
+        }
+    };
+    let mut res = String::from("{\n  \"nodes\": [");
+    for (i, node) in graph.0.iter().enumerate() {
+        if i > 0 {res.push(',')};
+        write!(res, "\n    {{\"data\": {{\"id\": \"n{}\"", i).unwrap();
+        write_attr(&mut res, node_attr(i, node));
+        res.push_str("}}");
+    }
+    res.push_str("\n  ],\n  \"edges\": [");
+    for (k, ([a, b], edge)) in graph.1.iter().enumerate() {
+        if k > 0 {res.push(',')};
+        write!(res, "\n    {{\"data\": {{\"id\": \"e{}\", \"source\": \"n{}\", \"target\": \"n{}\"",
+            k, a, b).unwrap();
+        write_attr(&mut res, edge_attr(k, edge));
+        res.push_str("}}");
+    }
+    res.push_str("\n  ]\n}\n");
+    res
+}
+
+/// Converts a graph to the GEXF format used by Gephi.
+///
+/// Nodes get the id `0, 1, ...` and edges get the id `0, 1, ...` by index.
+/// Nodes and edges are labeled with `node_label` and `edge_label`.
+///
+/// The attributes of every node and edge are computed from the index and the value
+/// by `node_attr` and `edge_attr`.
+/// This is used to include data aligned with nodes, for example depth or component id.
+/// The type of an attribute is declared from the first value with its name.
+///
+/// Requires the `gexf` feature.
+#[cfg(feature = "gexf")]
+pub fn to_gexf<T, U, N, L, NA, LA>(
+    graph: &Graph<T, U>,
+    node_label: N,
+    edge_label: L,
+    node_attr: NA,
+    edge_attr: LA,
+) -> String
+    where N: Fn(&T) -> String,
+          L: Fn(&U) -> String,
+          NA: Fn(usize, &T) -> Vec<(&'static str, Value)>,
+          LA: Fn(usize, &U) -> Vec<(&'static str, Value)>
+{
+    type Attr = Vec<(&'static str, Value)>;
+
+    // Collects attribute names with their type, in the order they are first found.
+    fn declare(list: &[Attr]) -> Vec<(&'static str, &'static str)> {
+        let mut res: Vec<(&'static str, &'static str)> = vec![];
+        for (key, val) in list.iter().flatten() {
+            if res.iter().any(|(k, _)| k == key) {continue};
+            let ty = match val {
+                Value::Str(_) => "string",
+                Value::Num(_) => "double",
+                Value::Bool(_) => "boolean",
+            };
+            res.push((key, ty));
+        }
+        res
+    }
+
+    fn write_declare(res: &mut String, class: &str, decl: &[(&'static str, &'static str)]) {
+        if decl.is_empty() {return};
+        writeln!(res, "    <attributes class=\"{}\">", class).unwrap();
+        for (id, (key, ty)) in decl.iter().enumerate() {
+            writeln!(res, "      <attribute id=\"{}\" title=\"{}\" type=\"{}\"/>",
+                id, xml_escape(key), ty).unwrap();
+        }
+        res.push_str("    </attributes>\n");
+    }
+
+    fn write_values(
+        res: &mut String,
+        tag: &str,
+        decl: &[(&'static str, &'static str)],
+        attr: Attr
+    ) {
+        if attr.is_empty() {
+            res.push_str("/>\n");
+            return;
+        }
+        res.push_str(">\n        <attvalues>\n");
+        for (key, val) in attr {
+            let id = decl.iter().position(|(k, _)| *k == key).unwrap();
+            let val = match val {
+                Value::Str(s) => xml_escape(&s),
+                Value::Num(x) => x.to_string(),
+                Value::Bool(b) => b.to_string(),
+            };
+            writeln!(res, "          <attvalue for=\"{}\" value=\"{}\"/>", id, val).unwrap();
+        }
+        res.push_str("        </attvalues>\n");
+        writeln!(res, "      </{}>", tag).unwrap();
+    }
+
+    let node_attrs: Vec<Attr> = graph.0.iter().enumerate()
+        .map(|(i, t)| node_attr(i, t)).collect();
+    let edge_attrs: Vec<Attr> = graph.1.iter().enumerate()
+        .map(|(k, e)| edge_attr(k, &e.1)).collect();
+    let node_decl = declare(&node_attrs);
+    let edge_decl = declare(&edge_attrs);
+
+    let mut res = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    res.push_str("<gexf xmlns=\"http://gexf.net/1.3\" version=\"1.3\">\n");
+    res.push_str("  <graph defaultedgetype=\"directed\">\n");
+    write_declare(&mut res, "node", &node_decl);
+    write_declare(&mut res, "edge", &edge_decl);
+    res.push_str("    <nodes>\n");
+    for ((i, node), attr) in graph.0.iter().enumerate().zip(node_attrs) {
+        write!(res, "      <node id=\"{}\" label=\"{}\"",
+            i, xml_escape(&node_label(node))).unwrap();
+        write_values(&mut res, "node", &node_decl, attr);
+    }
+    res.push_str("    </nodes>\n    <edges>\n");
+    for ((k, ([a, b], edge)), attr) in graph.1.iter().enumerate().zip(edge_attrs) {
+        write!(res, "      <edge id=\"{}\" source=\"{}\" target=\"{}\" label=\"{}\"",
+            k, a, b, xml_escape(&edge_label(edge))).unwrap();
+        write_values(&mut res, "edge", &edge_decl, attr);
+    }
+    res.push_str("    </edges>\n  </graph>\n</gexf>\n");
+    res
+}
+
+/// Escapes special characters in XML text.
+#[cfg(feature = "gexf")]
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+        .replace('"', "&quot;").replace('\'', "&apos;")
+}
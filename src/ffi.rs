@@ -0,0 +1,267 @@
+//! C interface for generating graphs.
+//!
+//! Nodes and edges are `u64` values chosen by the caller,
+//! for example indices into a table of states or hashes of states.
+//! Two nodes are the same when their values are equal.
+//! The callbacks receive a context pointer `ctx` that is passed through unchanged.
+//!
+//! A generated graph is returned as an opaque handle,
+//! with flat arrays of nodes, edge endpoints and edge values.
+//! The handle must be released with `gb_graph_free`.
+//!
+//! To link from C or C++, build a static or dynamic library, e.g.
+//! `cargo rustc --release --features ffi --crate-type staticlib`.
+//! The declarations are in `include/graph_builder.h`.
+//!
+//! Requires the `ffi` feature.
+
+use std::os::raw::{c_int, c_void};
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::{gen, GenerateError, GenerateSettings, Graph};
+
+/// Error code when the maximum number of nodes was reached.
+pub const GB_MAX_NODES: c_int = -1;
+/// Error code when the maximum number of edges was reached.
+pub const GB_MAX_EDGES: c_int = -2;
+/// Error code when the maximum number of composed edges was reached.
+pub const GB_MAX_COMPOSITIONS: c_int = -3;
+
+/// Generates a new node with edge from `node` and operation `op`.
+///
+/// Writes the new node to `out_node` and the edge to `out_edge`, then returns `0`.
+/// Any other value is reported as an error, where positive values are recommended.
+/// To skip an operation that does not apply, return an error code and ignore it.
+pub type GbGen = extern "C" fn(
+    ctx: *mut c_void,
+    node: u64,
+    op: usize,
+    out_node: *mut u64,
+    out_edge: *mut u64,
+) -> c_int;
+
+/// Returns non-zero to keep `node` in post-processing.
+pub type GbFilter = extern "C" fn(ctx: *mut c_void, node: u64) -> c_int;
+
+/// Composes edge `a` followed by edge `b`.
+///
+/// Writes the composed edge to `out` and returns `0`.
+/// Returns a negative value to leave out the composed edge without reporting an error,
+/// or a positive value to report an error.
+pub type GbCompose = extern "C" fn(ctx: *mut c_void, a: u64, b: u64, out: *mut u64) -> c_int;
+
+/// Stores callbacks for generating a graph.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct GbCallbacks {
+    /// Generates new nodes, see `GbGen`. When null, `gb_gen` returns null.
+    pub f: Option<GbGen>,
+    /// Filters nodes, see `GbFilter`. When null, all nodes are kept.
+    pub g: Option<GbFilter>,
+    /// Composes edges, see `GbCompose`. When null, edges are never composed.
+    pub h: Option<GbCompose>,
+    /// Context pointer passed to every callback.
+    pub ctx: *mut c_void,
+}
+
+/// Stores settings for generating a graph.
+///
+/// This corresponds to `GenerateSettings` with `EdgeIndex::Auto`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GbSettings {
+    /// The maximum number of nodes before terminating.
+    pub max_nodes: usize,
+    /// The maximum number of edges before terminating.
+    pub max_edges: usize,
+    /// The maximum number of composed edges before terminating post-processing.
+    pub max_compositions: usize,
+}
+
+/// Opaque handle to a generated graph.
+pub struct GbGraph {
+    graph: Graph<u64, u64>,
+    endpoints: Vec<usize>,
+    ops: Vec<u64>,
+    error: c_int,
+}
+
+/// Stores an error code from a callback or a memory limit.
+struct Code(c_int);
+
+impl From<GenerateError> for Code {
+    fn from(err: GenerateError) -> Code {
+        Code(match err {
+            GenerateError::MaxNodes(_) => GB_MAX_NODES,
+            GenerateError::MaxEdges(_) => GB_MAX_EDGES,
+            GenerateError::MaxCompositions(_) => GB_MAX_COMPOSITIONS,
+        })
+    }
+}
+
+/// Creates new settings from node and edge limits.
+///
+/// There is no limit on the number of composed edges.
+#[no_mangle]
+pub extern "C" fn gb_settings(max_nodes: usize, max_edges: usize) -> GbSettings {
+    GbSettings {max_nodes, max_edges, max_compositions: usize::MAX}
+}
+
+/// Generates a graph from seed nodes, see `gen`.
+///
+/// The graph is returned even when generation hit a limit or a callback reported an error.
+/// Use `gb_graph_error` to get the first error.
+///
+/// # Safety
+///
+/// `seed` must point to `seed_len` nodes, or be null when `seed_len` is zero.
+/// The callbacks must write to the output pointers when they return `0`.
+///
+/// Returns null when the callback `f` is null.
+/// Panics are caught before reaching the caller, and also return null.
+#[no_mangle]
+pub unsafe extern "C" fn gb_gen(
+    seed: *const u64,
+    seed_len: usize,
+    n: usize,
+    callbacks: GbCallbacks,
+    settings: GbSettings,
+) -> *mut GbGraph {
+    let GbCallbacks {f, g, h, ctx} = callbacks;
+    let f = match f {
+        Some(f) => f,
+        None => return std::ptr::null_mut(),
+    };
+    let seed = if seed_len == 0 {vec![]}
+        else {std::slice::from_raw_parts(seed, seed_len).to_vec()};
+    let res = panic::catch_unwind(AssertUnwindSafe(|| gen_graph(seed, n, f, g, h, ctx, settings)));
+    match res {
+        Ok(graph) => Box::into_raw(Box::new(graph)),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Generates a graph with callbacks, see `gb_gen`.
+fn gen_graph(
+    seed: Vec<u64>,
+    n: usize,
+    f: GbGen,
+    g: Option<GbFilter>,
+    h: Option<GbCompose>,
+    ctx: *mut c_void,
+    settings: GbSettings,
+) -> GbGraph {
+    let settings = GenerateSettings {
+        max_nodes: settings.max_nodes,
+        max_edges: settings.max_edges,
+        max_compositions: settings.max_compositions,
+        ..GenerateSettings::new(0, 0)
+    };
+    let res = gen((seed, vec![]), n,
+        |&node, op| {
+            let (mut out_node, mut out_edge) = (0, 0);
+            match f(ctx, node, op, &mut out_node, &mut out_edge) {
+                0 => Ok((out_node, out_edge)),
+                code => Err(Code(code)),
+            }
+        },
+        |&node| g.map(|g| g(ctx, node) != 0).unwrap_or(true),
+        |&a, &b| {
+            let h = h.ok_or(None)?;
+            let mut out = 0;
+            match h(ctx, a, b, &mut out) {
+                0 => Ok(out),
+                code if code < 0 => Err(None),
+                code => Err(Some(Code(code))),
+            }
+        },
+        &settings);
+    let (graph, error) = match res {
+        Ok(graph) => (graph, 0),
+        Err((graph, Code(code))) => (graph, code),
+    };
+    let mut endpoints = Vec::with_capacity(2 * graph.1.len());
+    for &([a, b], _) in &graph.1 {
+        endpoints.push(a);
+        endpoints.push(b);
+    }
+    let ops = graph.1.iter().map(|&(_, op)| op).collect();
+    GbGraph {graph, endpoints, ops, error}
+}
+
+/// Returns the first error code from generating the graph, or `0` when there was no error.
+///
+/// # Safety
+///
+/// `graph` must be a handle returned from `gb_gen` that is not freed.
+#[no_mangle]
+pub unsafe extern "C" fn gb_graph_error(graph: *const GbGraph) -> c_int {
+    (*graph).error
+}
+
+/// Returns the number of nodes.
+///
+/// # Safety
+///
+/// `graph` must be a handle returned from `gb_gen` that is not freed.
+#[no_mangle]
+pub unsafe extern "C" fn gb_graph_node_count(graph: *const GbGraph) -> usize {
+    (*graph).graph.0.len()
+}
+
+/// Returns a pointer to the nodes, by index.
+///
+/// # Safety
+///
+/// `graph` must be a handle returned from `gb_gen` that is not freed.
+/// The pointer is valid until the graph is freed.
+#[no_mangle]
+pub unsafe extern "C" fn gb_graph_nodes(graph: *const GbGraph) -> *const u64 {
+    (*graph).graph.0.as_ptr()
+}
+
+/// Returns the number of edges.
+///
+/// # Safety
+///
+/// `graph` must be a handle returned from `gb_gen` that is not freed.
+#[no_mangle]
+pub unsafe extern "C" fn gb_graph_edge_count(graph: *const GbGraph) -> usize {
+    (*graph).graph.1.len()
+}
+
+/// Returns a pointer to the edge endpoints, as `2 * edge_count` node indices.
+///
+/// Edge `k` goes from node index `endpoints[2 * k]` to node index `endpoints[2 * k + 1]`.
+///
+/// # Safety
+///
+/// `graph` must be a handle returned from `gb_gen` that is not freed.
+/// The pointer is valid until the graph is freed.
+#[no_mangle]
+pub unsafe extern "C" fn gb_graph_edge_endpoints(graph: *const GbGraph) -> *const usize {
+    (*graph).endpoints.as_ptr()
+}
+
+/// Returns a pointer to the edge values, by index.
+///
+/// # Safety
+///
+/// `graph` must be a handle returned from `gb_gen` that is not freed.
+/// The pointer is valid until the graph is freed.
+#[no_mangle]
+pub unsafe extern "C" fn gb_graph_edges(graph: *const GbGraph) -> *const u64 {
+    (*graph).ops.as_ptr()
+}
+
+/// Frees a graph.
+///
+/// # Safety
+///
+/// `graph` must be a handle returned from `gb_gen` that is not freed, or null.
+#[no_mangle]
+pub unsafe extern "C" fn gb_graph_free(graph: *mut GbGraph) {
+    if !graph.is_null() {
+        drop(Box::from_raw(graph));
+    }
+}
@@ -0,0 +1,172 @@
+//! Words in free groups and balls in their Cayley graphs.
+//!
+//! A word is a product of generators and their inverses,
+//! reduced such that no generator is next to its inverse, i.e. `a·a⁻¹ = ε`.
+//! As text, generators are lowercase letters `a, b, c, ...`
+//! and inverses are the uppercase letters `A, B, C, ...`.
+
+use crate::{gen_from_iter, GenResult, GenerateError, GenerateSettings, Graph};
+
+/// Stores a generator or its inverse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Letter {
+    /// The index of the generator.
+    pub gen: usize,
+    /// Whether this is the inverse of the generator.
+    pub inv: bool,
+}
+
+impl Letter {
+    /// Returns the inverse letter.
+    pub fn inverse(self) -> Letter {
+        Letter {gen: self.gen, inv: !self.inv}
+    }
+
+    /// Returns the letters of `n` generators and their inverses,
+    /// as `a, A, b, B, ...`.
+    pub fn all(n: usize) -> Vec<Letter> {
+        (0..n).flat_map(|gen| vec![Letter {gen, inv: false}, Letter {gen, inv: true}]).collect()
+    }
+}
+
+/// Stores a reduced word in a free group.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Word(Vec<Letter>);
+
+impl Word {
+    /// Returns the empty word, which is the identity.
+    pub fn identity() -> Word {Word(vec![])}
+
+    /// Creates a word from letters, reducing it.
+    pub fn new(letters: &[Letter]) -> Word {
+        let mut res = Word::identity();
+        for &letter in letters {
+            res.push(letter);
+        }
+        res
+    }
+
+    /// Parses a word, where `a, b, c, ...` are generators and `A, B, C, ...` their inverses.
+    ///
+    /// Returns `None` if the text contains other characters.
+    pub fn parse(text: &str) -> Option<Word> {
+        let mut res = Word::identity();
+        for c in text.chars() {
+            let letter = match c {
+                'a'..='z' => Letter {gen: c as usize - 'a' as usize, inv: false},
+                'A'..='Z' => Letter {gen: c as usize - 'A' as usize, inv: true},
+                _ => return None,
+            };
+            res.push(letter);
+        }
+        Some(res)
+    }
+
+    /// Returns the letters of the reduced word.
+    pub fn letters(&self) -> &[Letter] {&self.0}
+
+    /// Returns the number of letters of the reduced word.
+    pub fn len(&self) -> usize {self.0.len()}
+
+    /// Returns `true` if the word is the identity.
+    pub fn is_empty(&self) -> bool {self.0.is_empty()}
+
+    /// Multiplies the word by a letter on the right, reducing it.
+    pub fn push(&mut self, letter: Letter) {
+        if self.0.last() == Some(&letter.inverse()) {
+            self.0.pop();
+        } else {
+            self.0.push(letter);
+        }
+    }
+
+    /// Returns the product of two words.
+    pub fn mul(&self, other: &Word) -> Word {
+        let mut res = self.clone();
+        for &letter in &other.0 {
+            res.push(letter);
+        }
+        res
+    }
+
+    /// Returns the inverse word.
+    pub fn inverse(&self) -> Word {
+        Word(self.0.iter().rev().map(|l| l.inverse()).collect())
+    }
+}
+
+impl std::fmt::Display for Word {
+    fn fmt(&self, w: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        if self.0.is_empty() {return write!(w, "ε")};
+        for letter in &self.0 {
+            let base = if letter.inv {b'A'} else {b'a'};
+            if letter.gen < 26 {
+                write!(w, "{}", (base + letter.gen as u8) as char)?;
+            } else {
+                write!(w, "{}{}", base as char, letter.gen)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Generates the ball of radius `radius` around the identity
+/// in the Cayley graph of the free group with `n` generators.
+///
+/// Nodes are the reduced words with at most `radius` letters.
+/// An edge from `x` to `x·l` is labeled by the letter `l`.
+///
+/// For `n >= 2`, the number of nodes is `1 + 2n((2n-1)^r - 1)/(2n-2)`.
+pub fn gen_ball(
+    n: usize,
+    radius: usize,
+    settings: &GenerateSettings,
+) -> GenResult<Graph<Word, Letter>, GenerateError> {
+    let letters = Letter::all(n);
+    let f = |word: &Word| letters.iter().filter_map(|&letter| {
+        let mut word = word.clone();
+        word.push(letter);
+        if word.len() > radius {None} else {Some(Ok((word, letter)))}
+    }).collect::<Vec<_>>();
+    gen_from_iter((vec![Word::identity()], vec![]), f, |_| true, |_, _| Err(None), settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ball_size(n: usize, r: usize) -> usize {
+        if n == 1 {return 1 + 2 * r};
+        1 + 2 * n * ((2 * n - 1).pow(r as u32) - 1) / (2 * n - 2)
+    }
+
+    #[test]
+    fn words() {
+        let w = Word::parse("abBc").unwrap();
+        assert_eq!(w.to_string(), "ac");
+        assert_eq!(w.inverse().to_string(), "CA");
+        assert!(w.mul(&w.inverse()).is_empty());
+        assert_eq!(Word::identity().to_string(), "ε");
+        assert_eq!(Word::parse("a1"), None);
+    }
+
+    #[test]
+    fn ball_sizes() {
+        let settings = GenerateSettings::new(100_000, 1_000_000);
+        for n in 1..4 {
+            for r in 0..5 {
+                let (nodes, edges) = gen_ball(n, r, &settings).unwrap();
+                assert_eq!(nodes.len(), ball_size(n, r));
+                assert!(nodes.iter().all(|w| w.len() <= r));
+                if r == 0 {
+                    assert!(edges.is_empty());
+                    continue;
+                }
+                // Inner words have an edge per letter, words on the sphere only the reducing one.
+                let inner = ball_size(n, r - 1);
+                assert_eq!(edges.len(), 2 * n * inner + nodes.len() - inner);
+            }
+        }
+        assert_eq!(ball_size(2, 2), 17);
+    }
+}
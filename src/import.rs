@@ -0,0 +1,359 @@
+//! Importing graphs from text formats used by other tools.
+//!
+//! Every importer has a variant that takes closures that parse labels of nodes and edges.
+
+use std::convert::Infallible;
+use std::collections::HashMap;
+
+use crate::Graph;
+
+/// Stores an error from importing a graph.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImportError<E> {
+    /// The text is not valid in the supported format.
+    Syntax {
+        /// The line number of the error, starting at 1.
+        line: usize,
+        /// Description of the error.
+        message: String,
+    },
+    /// A label could not be parsed.
+    Label {
+        /// The line number of the label, starting at 1.
+        line: usize,
+        /// The error from parsing the label.
+        error: E,
+    },
+}
+
+impl<E: std::fmt::Display> std::fmt::Display for ImportError<E> {
+    fn fmt(&self, w: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            ImportError::Syntax {line, message} => write!(w, "Line {}: {}", line, message),
+            ImportError::Label {line, error} =>
+                write!(w, "Line {}: Invalid label: {}", line, error),
+        }
+    }
+}
+
+impl<E: std::fmt::Debug + std::fmt::Display> std::error::Error for ImportError<E> {}
+
+/// Parses a directed graph in the DOT format of Graphviz.
+///
+/// Nodes are labeled with their `label` attribute, or their id when it is missing.
+/// Edges are labeled with their `label` attribute, or the empty string when it is missing.
+/// Nodes are added in the order they first appear, either by declaration or in an edge.
+///
+/// Only a restricted subset of DOT is supported:
+///
+/// - A single `digraph`, with edges `a -> b`, which may be chained `a -> b -> c`
+/// - Ids as identifiers, numbers or quoted strings
+/// - Node and edge attribute lists, where attributes other than `label` are ignored
+/// - Graph attributes and default attributes, e.g. `rankdir=LR` or `node [shape=box]`, ignored
+/// - Comments `//`, `/* */` and lines starting with `#`
+///
+/// Undirected graphs, subgraphs, ports and HTML strings are rejected.
+///
+/// This reads the output of `export::to_dot` back.
+pub fn from_dot(src: &str) -> Result<Graph<String, String>, ImportError<Infallible>> {
+    from_dot_with(src, |s| Ok(s.into()), |s| Ok(s.into()))
+}
+
+/// Parses a directed graph in the DOT format of Graphviz, using closures to parse labels.
+///
+/// The labels of nodes are parsed with `node` and the labels of edges with `edge`.
+///
+/// See `from_dot` for the supported subset of DOT.
+pub fn from_dot_with<T, U, E, N, L>(
+    src: &str,
+    mut node: N,
+    mut edge: L,
+) -> Result<Graph<T, U>, ImportError<E>>
+    where N: FnMut(&str) -> Result<T, E>,
+          L: FnMut(&str) -> Result<U, E>
+{
+    let tokens = tokenize(src)?;
+    let (nodes, edges) = Parser {tokens: &tokens, pos: 0}.parse()?;
+    let mut graph = (Vec::with_capacity(nodes.len()), Vec::with_capacity(edges.len()));
+    for (label, line) in nodes {
+        graph.0.push(node(&label).map_err(|error| ImportError::Label {line, error})?);
+    }
+    for (ab, label, line) in edges {
+        graph.1.push((ab, edge(&label).map_err(|error| ImportError::Label {line, error})?));
+    }
+    Ok(graph)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Id(String),
+    Arrow,
+    UndirectedArrow,
+    Open,
+    Close,
+    OpenAttr,
+    CloseAttr,
+    Equal,
+    Separator,
+}
+
+fn syntax<E>(line: usize, message: &str) -> ImportError<E> {
+    ImportError::Syntax {line, message: message.into()}
+}
+
+/// Splits DOT text into tokens with line numbers, skipping whitespace and comments.
+fn tokenize<E>(src: &str) -> Result<Vec<(Token, usize)>, ImportError<E>> {
+    let mut res = vec![];
+    let mut chars = src.chars().peekable();
+    let mut line = 1;
+    let mut line_start = true;
+    while let Some(c) = chars.next() {
+        let start = line_start;
+        line_start = false;
+        match c {
+            '\n' => {line += 1; line_start = true}
+            c if c.is_whitespace() => line_start = start,
+            '#' if start => {
+                while chars.peek().map(|&c| c != '\n').unwrap_or(false) {chars.next();}
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.peek().map(|&c| c != '\n').unwrap_or(false) {chars.next();}
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                loop {
+                    match chars.next() {
+                        None => return Err(syntax(line, "Unterminated comment")),
+                        Some('/') if prev == '*' => break,
+                        Some(c) => {
+                            if c == '\n' {line += 1};
+                            prev = c;
+                        }
+                    }
+                }
+            }
+            '{' => res.push((Token::Open, line)),
+            '}' => res.push((Token::Close, line)),
+            '[' => res.push((Token::OpenAttr, line)),
+            ']' => res.push((Token::CloseAttr, line)),
+            '=' => res.push((Token::Equal, line)),
+            ';' | ',' => res.push((Token::Separator, line)),
+            '-' if chars.peek() == Some(&'>') => {
+                chars.next();
+                res.push((Token::Arrow, line));
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                chars.next();
+                res.push((Token::UndirectedArrow, line));
+            }
+            '"' => {
+                let first = line;
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        None => return Err(syntax(first, "Unterminated string")),
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => s.push('\n'),
+                            Some('\n') => line += 1,
+                            Some(c @ '"') | Some(c @ '\\') => s.push(c),
+                            Some(c) => {s.push('\\'); s.push(c)}
+                            None => return Err(syntax(first, "Unterminated string")),
+                        },
+                        Some(c) => {
+                            if c == '\n' {line += 1};
+                            s.push(c);
+                        }
+                    }
+                }
+                res.push((Token::Id(s), first));
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' => {
+                let mut s = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_' || c == '.') {break};
+                    s.push(c);
+                    chars.next();
+                }
+                res.push((Token::Id(s), line));
+            }
+            '<' => return Err(syntax(line, "HTML strings are not supported")),
+            ':' => return Err(syntax(line, "Ports are not supported")),
+            c => return Err(syntax(line, &format!("Unexpected character `{}`", c))),
+        }
+    }
+    Ok(res)
+}
+
+/// Stores nodes as `(label, line)` and edges as `([a, b], label, line)`.
+type Parsed = (Vec<(String, usize)>, Vec<([usize; 2], String, usize)>);
+
+struct Parser<'a> {
+    tokens: &'a [(Token, usize)],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {self.tokens.get(self.pos).map(|t| &t.0)}
+
+    fn line(&self) -> usize {
+        self.tokens.get(self.pos).or_else(|| self.tokens.last()).map(|t| t.1).unwrap_or(1)
+    }
+
+    fn keyword(&self, name: &str) -> bool {
+        matches!(self.peek(), Some(Token::Id(s)) if s.eq_ignore_ascii_case(name))
+    }
+
+    fn expect<E>(&mut self, token: Token, message: &str) -> Result<(), ImportError<E>> {
+        if self.peek() == Some(&token) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(syntax(self.line(), message))
+        }
+    }
+
+    fn id<E>(&mut self) -> Result<&'a str, ImportError<E>> {
+        match self.peek() {
+            Some(Token::Id(s)) => {
+                self.pos += 1;
+                Ok(s)
+            }
+            _ => Err(syntax(self.line(), "Expected id")),
+        }
+    }
+
+    /// Parses an optional attribute list and returns the label, if any.
+    fn attributes<E>(&mut self) -> Result<Option<(String, usize)>, ImportError<E>> {
+        let mut label = None;
+        while self.peek() == Some(&Token::OpenAttr) {
+            self.pos += 1;
+            loop {
+                match self.peek() {
+                    Some(Token::CloseAttr) => {self.pos += 1; break}
+                    Some(Token::Separator) => self.pos += 1,
+                    _ => {
+                        let key = self.id()?;
+                        self.expect(Token::Equal, "Expected `=` in attribute")?;
+                        let line = self.line();
+                        let val = self.id()?;
+                        if key == "label" {label = Some((val.to_string(), line))};
+                    }
+                }
+            }
+        }
+        Ok(label)
+    }
+
+    fn parse<E>(mut self) -> Result<Parsed, ImportError<E>> {
+        let mut nodes: Vec<(String, usize)> = vec![];
+        let mut edges = vec![];
+        let mut ids: HashMap<&str, usize> = HashMap::new();
+
+        if self.keyword("strict") {self.pos += 1};
+        if self.keyword("graph") {
+            return Err(syntax(self.line(), "Undirected graphs are not supported"));
+        }
+        if !self.keyword("digraph") {
+            return Err(syntax(self.line(), "Expected `digraph`"));
+        }
+        self.pos += 1;
+        if let Some(Token::Id(_)) = self.peek() {self.pos += 1};
+        self.expect(Token::Open, "Expected `{`")?;
+        loop {
+            match self.peek() {
+                None => return Err(syntax(self.line(), "Expected `}`")),
+                Some(Token::Close) => {self.pos += 1; break}
+                Some(Token::Separator) => {self.pos += 1; continue}
+                Some(Token::Open) => return Err(syntax(self.line(), "Subgraphs are not supported")),
+                _ => {}
+            }
+            if self.keyword("subgraph") {
+                return Err(syntax(self.line(), "Subgraphs are not supported"));
+            }
+            if (self.keyword("graph") || self.keyword("node") || self.keyword("edge")) &&
+               self.tokens.get(self.pos + 1).map(|t| &t.0) == Some(&Token::OpenAttr)
+            {
+                self.pos += 1;
+                self.attributes()?;
+                continue;
+            }
+            let line = self.line();
+            let first = self.id()?;
+            if self.peek() == Some(&Token::Equal) {
+                self.pos += 1;
+                self.id()?;
+                continue;
+            }
+            let mut chain = vec![first];
+            loop {
+                match self.peek() {
+                    Some(Token::Arrow) => {
+                        self.pos += 1;
+                        chain.push(self.id()?);
+                    }
+                    Some(Token::UndirectedArrow) => return Err(syntax(self.line(),
+                        "Undirected edges are not supported")),
+                    _ => break,
+                }
+            }
+            let label = self.attributes()?;
+            let mut node_ids = Vec::with_capacity(chain.len());
+            for id in &chain {
+                let n = nodes.len();
+                let i = *ids.entry(id).or_insert(n);
+                if i == n {nodes.push((id.to_string(), line))};
+                node_ids.push(i);
+            }
+            if chain.len() == 1 {
+                if let Some(label) = label {nodes[node_ids[0]] = label};
+            } else {
+                let (label, line) = label.unwrap_or((String::new(), line));
+                for w in node_ids.windows(2) {
+                    edges.push(([w[0], w[1]], label.clone(), line));
+                }
+            }
+        }
+        if self.pos < self.tokens.len() {
+            return Err(syntax(self.line(), "Unexpected text after graph"));
+        }
+        Ok((nodes, edges))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::to_dot;
+
+    #[test]
+    fn dot_round_trip() {
+        let graph: Graph<String, String> = (
+            vec!["a".into(), "say \"hi\"".into(), "back\\slash\nnew line".into(), "".into()],
+            vec![([0, 1], "x".into()), ([1, 2], "".into()), ([2, 0], "-> {}".into()),
+                ([1, 1], "\"".into())],
+        );
+        let dot = to_dot(&graph, |t| t.clone(), |u| u.clone());
+        assert_eq!(from_dot(&dot), Ok(graph.clone()));
+
+        let dot = to_dot(&graph, |t| t.len().to_string(), |u| u.len().to_string());
+        let parsed = from_dot_with(&dot, |s| s.parse::<usize>(), |s| s.parse::<usize>()).unwrap();
+        assert_eq!(parsed, (vec![1, 8, 19, 0], vec![([0, 1], 1), ([1, 2], 0), ([2, 0], 5),
+            ([1, 1], 1)]));
+    }
+
+    #[test]
+    fn dot_subset() {
+        let src = "# comment\ndigraph G {\n  rankdir=LR; node [shape=box]\n  b [label=\"B\"]\n\
+            a -> b -> \"c d\" [label=x, color=red] // comment\n  /* multi\n line */ 1 -> a\n}\n";
+        let graph = from_dot(src).unwrap();
+        assert_eq!(graph.0, vec!["B", "a", "c d", "1"]);
+        assert_eq!(graph.1, vec![([1, 0], "x".into()), ([0, 2], "x".into()), ([3, 1], "".into())]);
+
+        assert!(matches!(from_dot("graph {\n a -- b\n}"), Err(ImportError::Syntax {line: 1, ..})));
+        assert!(matches!(from_dot("digraph {\n a -> \n}"), Err(ImportError::Syntax {line: 3, ..})));
+        let err = from_dot_with("digraph {\n\n x [label=\"y\"] }", |s| s.parse::<u8>(), |_| Ok(()));
+        assert!(matches!(err, Err(ImportError::Label {line: 3, ..})));
+    }
+}
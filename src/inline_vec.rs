@@ -0,0 +1,151 @@
+//! Short lists stored without heap allocation.
+//!
+//! This module is enabled by the `inline_vec` feature.
+//!
+//! Edge data is often a short list, e.g. the indices of swap operations in `eq`.
+//! Composing such edges allocates a new list for every composed edge,
+//! which dominates the time spent in post-processing.
+//! An `InlineVec` stores up to `N` items inline and only allocates when it grows larger.
+
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, DerefMut};
+
+/// A list that stores up to `N` items inline, and moves them to the heap when growing larger.
+///
+/// Dereferences to a slice, such that it can be used like a `Vec` for most purposes.
+#[derive(Clone)]
+pub struct InlineVec<T: Copy + Default, const N: usize> {
+    len: usize,
+    inline: [T; N],
+    heap: Vec<T>,
+}
+
+impl<T: Copy + Default, const N: usize> InlineVec<T, N> {
+    /// Creates a new empty list.
+    pub fn new() -> Self {
+        InlineVec {len: 0, inline: [T::default(); N], heap: vec![]}
+    }
+
+    /// Creates a list from a slice.
+    pub fn from_slice(items: &[T]) -> Self {
+        let mut res = InlineVec::new();
+        res.extend_from_slice(items);
+        res
+    }
+
+    /// Returns `true` if the items are stored on the heap.
+    pub fn spilled(&self) -> bool {self.len > N}
+
+    /// Adds an item at the end.
+    pub fn push(&mut self, item: T) {
+        if self.len < N {
+            self.inline[self.len] = item;
+        } else {
+            if self.len == N {self.heap.extend_from_slice(&self.inline)};
+            self.heap.push(item);
+        }
+        self.len += 1;
+    }
+
+    /// Removes the last item.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {return None};
+        self.len -= 1;
+        if self.len < N {
+            Some(self.inline[self.len])
+        } else {
+            let item = self.heap.pop();
+            if self.len == N {
+                self.inline.copy_from_slice(&self.heap);
+                self.heap.clear();
+            }
+            item
+        }
+    }
+
+    /// Adds items at the end.
+    pub fn extend_from_slice(&mut self, items: &[T]) {
+        for &item in items {self.push(item)}
+    }
+
+    /// Removes all items, keeping the heap memory.
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.heap.clear();
+    }
+
+    /// Returns a list with the items of two sorted lists in sorted order.
+    ///
+    /// This does not allocate when the result fits inline.
+    pub fn merge_sorted(&self, other: &Self) -> Self where T: Ord {
+        let mut res = InlineVec::new();
+        let (a, b) = (&**self, &**other);
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() && j < b.len() {
+            if a[i] <= b[j] {
+                res.push(a[i]);
+                i += 1;
+            } else {
+                res.push(b[j]);
+                j += 1;
+            }
+        }
+        res.extend_from_slice(&a[i..]);
+        res.extend_from_slice(&b[j..]);
+        res
+    }
+}
+
+impl<T: Copy + Default, const N: usize> Default for InlineVec<T, N> {
+    fn default() -> Self {InlineVec::new()}
+}
+
+impl<T: Copy + Default, const N: usize> Deref for InlineVec<T, N> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        if self.len > N {&self.heap} else {&self.inline[..self.len]}
+    }
+}
+
+impl<T: Copy + Default, const N: usize> DerefMut for InlineVec<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        if self.len > N {&mut self.heap} else {&mut self.inline[..self.len]}
+    }
+}
+
+impl<T: Copy + Default, const N: usize> From<&[T]> for InlineVec<T, N> {
+    fn from(items: &[T]) -> Self {InlineVec::from_slice(items)}
+}
+
+impl<T: Copy + Default, const N: usize> std::iter::FromIterator<T> for InlineVec<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut res = InlineVec::new();
+        for item in iter {res.push(item)}
+        res
+    }
+}
+
+impl<T: Copy + Default + std::fmt::Debug, const N: usize> std::fmt::Debug for InlineVec<T, N> {
+    fn fmt(&self, w: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        (**self).fmt(w)
+    }
+}
+
+impl<T: Copy + Default + PartialEq, const N: usize> PartialEq for InlineVec<T, N> {
+    fn eq(&self, other: &Self) -> bool {**self == **other}
+}
+
+impl<T: Copy + Default + Eq, const N: usize> Eq for InlineVec<T, N> {}
+
+impl<T: Copy + Default + PartialOrd, const N: usize> PartialOrd for InlineVec<T, N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {(**self).partial_cmp(&**other)}
+}
+
+impl<T: Copy + Default + Ord, const N: usize> Ord for InlineVec<T, N> {
+    fn cmp(&self, other: &Self) -> Ordering {(**self).cmp(&**other)}
+}
+
+impl<T: Copy + Default + Hash, const N: usize> Hash for InlineVec<T, N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {(**self).hash(state)}
+}
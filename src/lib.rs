@@ -17,10 +17,47 @@
 
 use std::hash::Hash;
 use std::error::Error;
+use std::collections::{HashMap, HashSet};
+
+pub mod algo;
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
+pub mod derivation;
+pub mod eq;
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod free_group;
+pub mod import;
+#[cfg(feature = "inline_vec")]
+pub mod inline_vec;
+pub mod memory;
+pub mod modular;
+pub mod monoid;
+pub mod perm;
+pub mod poset;
+pub mod puzzle;
+pub mod reach;
+pub mod rewrite;
+pub mod search;
+pub mod spec;
+pub mod trace;
+pub mod traverse;
+pub mod undirected;
 
 /// A graph is a tuple of nodes and edges between nodes.
 pub type Graph<T, U> = (Vec<T>, Vec<([usize; 2], U)>);
 
+/// The result of generating a graph.
+///
+/// When generation fails, the data generated so far is returned together with the error.
+pub type GenResult<X, E> = Result<X, (X, E)>;
+
+/// The index of the node and the step `j` that first generated a node.
+///
+/// This is `None` for nodes that were not generated from another node.
+pub type Provenance = Option<(usize, usize)>;
+
 /// Stores settings for generating graph.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct GenerateSettings {
@@ -28,30 +65,252 @@ pub struct GenerateSettings {
     pub max_nodes: usize,
     /// The maximum number of edges before terminating.
     pub max_edges: usize,
+    /// The maximum number of composed edges before terminating post-processing.
+    ///
+    /// A removed node with `k` incoming and `m` outgoing edges
+    /// might create `k * m` composed edges.
+    pub max_compositions: usize,
+    /// The data structure used to check whether an edge exists.
+    pub edge_index: EdgeIndex,
+    /// Whether to sort edges by source and target node after post-processing.
+    ///
+    /// By default, edges are in the order they were generated, followed by composed edges,
+    /// except that removing nodes changes the order of the remaining edges.
+    /// The sort is stable, so edges between the same nodes keep their order.
+    pub sort_edges: bool,
+    /// How to react to errors from `f` and `h`.
+    pub error_policy: ErrorPolicy,
+    /// Whether to keep nodes that do not pass the filter when removing them
+    /// would disconnect the remaining nodes.
+    ///
+    /// Composing edges keeps the remaining nodes connected through removed nodes,
+    /// except when `h` refuses to compose or a limit is reached.
+    /// When this is `true`, a removed node is kept if it is on a path from one remaining node
+    /// to another, and the second node can no longer be reached from the first after composing.
+    /// This checks paths from every remaining node, which is slow for large graphs.
+    pub keep_connected: bool,
+    /// The maximum number of edges from before post-processing that a composed edge spans.
+    ///
+    /// Edges are not composed when the composed edge would span more edges,
+    /// the same way as when `h` returns `Err(None)`.
+    /// For example, with `2`, an edge is only composed from two edges that were not composed.
+    pub max_composite_len: usize,
+}
+
+impl GenerateSettings {
+    /// Creates new settings from node and edge limits.
+    ///
+    /// There is no limit on the number of composed edges nor on their length.
+    /// Uses `EdgeIndex::Auto` to check whether an edge exists.
+    /// Edges are not sorted.
+    /// Uses `ErrorPolicy::ContinueKeepOne` for errors.
+    /// Nodes are removed even when this disconnects the graph.
+    pub fn new(max_nodes: usize, max_edges: usize) -> GenerateSettings {
+        GenerateSettings {
+            max_nodes,
+            max_edges,
+            max_compositions: usize::MAX,
+            edge_index: EdgeIndex::Auto,
+            sort_edges: false,
+            error_policy: ErrorPolicy::ContinueKeepOne,
+            keep_connected: false,
+            max_composite_len: usize::MAX,
+        }
+    }
+}
+
+/// Selects the data structure used to check whether an edge exists.
+///
+/// A hash set of node pairs uses memory proportional to the number of edges,
+/// but is slow and memory-hungry when the graph is dense.
+/// A bitset per source node uses one bit per possible target,
+/// which is faster and smaller when most nodes are connected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EdgeIndex {
+    /// Use a bitset when the maximum number of nodes is small enough.
+    ///
+    /// The bitset is used when `max_nodes` is less or equal to `DENSE_MAX_NODES`.
+    Auto,
+    /// Use a hash set of node pairs.
+    Sparse,
+    /// Use a bitset per source node.
+    Dense,
+}
+
+/// The maximum number of nodes for which `EdgeIndex::Auto` uses a bitset.
+///
+/// At this limit, a complete bitset uses 2 MiB of memory.
+pub const DENSE_MAX_NODES: usize = 4096;
+
+/// Stores which edges exist between nodes.
+enum EdgeSet {
+    Sparse(HashSet<[usize; 2]>),
+    Dense(Vec<Vec<u64>>),
+}
+
+impl EdgeSet {
+    fn new(index: EdgeIndex, max_nodes: usize) -> EdgeSet {
+        match index {
+            EdgeIndex::Auto if max_nodes <= DENSE_MAX_NODES => EdgeSet::Dense(vec![]),
+            EdgeIndex::Dense => EdgeSet::Dense(vec![]),
+            EdgeIndex::Auto | EdgeIndex::Sparse => EdgeSet::Sparse(HashSet::new()),
+        }
+    }
+
+    fn insert(&mut self, [a, b]: [usize; 2]) {
+        match self {
+            EdgeSet::Sparse(set) => {set.insert([a, b]);}
+            EdgeSet::Dense(rows) => {
+                if rows.len() <= a {rows.resize(a + 1, vec![])};
+                let row = &mut rows[a];
+                if row.len() <= b / 64 {row.resize(b / 64 + 1, 0)};
+                row[b / 64] |= 1 << (b % 64);
+            }
+        }
+    }
+
+    fn contains(&self, &[a, b]: &[usize; 2]) -> bool {
+        match self {
+            EdgeSet::Sparse(set) => set.contains(&[a, b]),
+            EdgeSet::Dense(rows) => rows.get(a)
+                .and_then(|row| row.get(b / 64))
+                .map(|&bits| bits & (1 << (b % 64)) != 0)
+                .unwrap_or(false),
+        }
+    }
+}
+
+/// Selects how to react to errors returned by `f` while generating
+/// and by `h` while composing edges.
+///
+/// Errors from reaching limits always stop generating, and are reported
+/// unless another error was reported first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ErrorPolicy {
+    /// Ignore errors and continue, such that only limits are reported.
+    ContinueSilently,
+    /// Continue after errors and keep one of them to report.
+    ///
+    /// Only one error is kept, and the rule differs between `f` and `h`:
+    /// every error from `f` replaces the kept error, such that the last error
+    /// from generating is reported, while an error from `h` is only kept when there is
+    /// no earlier error, such that the first error from composing is reported,
+    /// unless generating failed.
+    ContinueKeepOne,
+    /// Continue after errors and collect every error from `f` and `h` in order.
+    ///
+    /// All errors are returned by `gen_outcome`.
+    /// Functions that return one error report the first collected error,
+    /// or the limit that was reached when there is none.
+    ContinueCollecting,
+    /// Stop at the first error and report it.
+    ///
+    /// When `f` returns an error, no more nodes are expanded,
+    /// and when `h` returns an error, no more edges are composed.
+    /// The graph generated so far is still post-processed.
+    AbortOnFirst,
+}
+
+impl ErrorPolicy {
+    /// Stores an error according to the policy, returning `true` if generating should stop.
+    ///
+    /// When keeping one error, the error replaces an earlier error if `replace` is `true`.
+    fn record<E>(self, errors: &mut Errors<E>, err: E, replace: bool) -> bool {
+        match self {
+            ErrorPolicy::ContinueSilently => false,
+            ErrorPolicy::ContinueKeepOne => {
+                if replace || errors.error.is_none() {errors.error = Some(err)};
+                false
+            }
+            ErrorPolicy::ContinueCollecting => {
+                errors.collected.push(err);
+                false
+            }
+            ErrorPolicy::AbortOnFirst => {
+                if errors.error.is_none() {errors.error = Some(err)};
+                true
+            }
+        }
+    }
+}
+
+/// Stores the errors while generating a graph, see `ErrorPolicy`.
+struct Errors<E> {
+    /// The error to report, which is only a limit with `ErrorPolicy::ContinueCollecting`.
+    error: Option<E>,
+    /// Every error from `f` and `h` in order, with `ErrorPolicy::ContinueCollecting`.
+    collected: Vec<E>,
+}
+
+impl<E> Errors<E> {
+    fn new() -> Errors<E> {Errors {error: None, collected: vec![]}}
+
+    /// Stores an error from reaching a limit, unless an error was stored before.
+    fn limit(&mut self, err: GenerateError) where E: From<GenerateError> {
+        if self.error.is_none() {self.error = Some(err.into())};
+    }
+}
+
+impl<E> From<Errors<E>> for Option<E> {
+    fn from(errors: Errors<E>) -> Option<E> {
+        errors.collected.into_iter().next().or(errors.error)
+    }
+}
+
+/// Stores how far generation got when reaching a limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Progress {
+    /// The number of nodes before filtering.
+    pub nodes: usize,
+    /// The number of edges before filtering, including composed edges.
+    pub edges: usize,
+    /// The number of nodes taken for expansion, including the node being expanded,
+    /// or when composing edges, the number of edges visited.
+    pub position: usize,
+    /// The largest depth of a generated node, or zero when composing edges.
+    pub depth: usize,
 }
 
 /// Stores a graph generating error.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum GenerateError {
     /// Hit limit maximum number of nodes.
-    MaxNodes,
+    MaxNodes(Progress),
     /// Hit limit maximum number of edges.
-    MaxEdges,
+    MaxEdges(Progress),
+    /// Hit limit maximum number of composed edges.
+    MaxCompositions(Progress),
+}
+
+impl GenerateError {
+    /// Returns how far generation got when reaching the limit.
+    pub fn progress(&self) -> Progress {
+        match *self {
+            GenerateError::MaxNodes(p) |
+            GenerateError::MaxEdges(p) |
+            GenerateError::MaxCompositions(p) => p,
+        }
+    }
 }
 
 impl std::fmt::Display for GenerateError {
     fn fmt(&self, w: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
         match *self {
-            GenerateError::MaxNodes => write!(w, "Reached limit maximum number of nodes"),
-            GenerateError::MaxEdges => write!(w, "Reached limit maximum number of edges"),
+            GenerateError::MaxNodes(_) => write!(w, "Reached limit maximum number of nodes")?,
+            GenerateError::MaxEdges(_) => write!(w, "Reached limit maximum number of edges")?,
+            GenerateError::MaxCompositions(_) =>
+                write!(w, "Reached limit maximum number of composed edges")?,
         }
+        let p = self.progress();
+        write!(w, " (nodes: {}, edges: {}, position: {}, depth: {})",
+            p.nodes, p.edges, p.position, p.depth)
     }
 }
 
 impl Error for GenerateError {}
 
 impl From<GenerateError> for () {
-    fn from(_: GenerateError) -> () {()}
+    fn from(_: GenerateError) {}
 }
 
 /// Generates a graph from:
@@ -95,165 +354,2369 @@ impl From<GenerateError> for () {
 /// };
 /// ```
 ///
+/// To get the graph without matching on the result, use `gen_outcome`.
+///
 /// When an error happens during composing edges, one can choose whether to
 /// report the error with `Err(Some(err))`, or ignore it with `Err(None)`.
 /// This is useful because sometimes you want to filter edges without reporting errors.
-/// The algorithm assumes that one wishes to continue generating the graph
-/// when encountering an error. Only the first error will be reported.
+/// By default, the algorithm continues generating the graph when encountering an error,
+/// and reports one error. This is controlled by the error policy of the settings,
+/// see `ErrorPolicy`.
 pub fn gen<T, U, F, G, H, E>(
-    (mut nodes, mut edges): Graph<T, U>,
+    seed: Graph<T, U>,
     n: usize,
     f: F,
     g: G,
     h: H,
     settings: &GenerateSettings,
-) -> Result<Graph<T, U>, (Graph<T, U>, E)>
+) -> GenResult<Graph<T, U>, E>
     where T: Eq + Hash + Clone,
           F: Fn(&T, usize) -> Result<(T, U), E>,
           G: Fn(&T) -> bool,
           H: Fn(&U, &U) -> Result<U, Option<E>>,
           E: From<GenerateError>
 {
-    use std::collections::{HashMap, HashSet};
+    let mut exp = expand_unit(seed, n, f, settings);
+    let (graph, _) =
+        post_process(exp.graph, &mut exp.has_edge, |_, t| g(t), h, settings, &mut exp.error);
+    into_result(graph, exp.error)
+}
 
-    let mut error: Option<E> = None;
-    let mut has: HashMap<T, usize> = HashMap::new();
-    let mut has_edge: HashSet<[usize; 2]> = HashSet::new();
-    for n in &nodes {
-        has.insert(n.clone(), 0);
+/// Counts what happened while generating a graph, see `GenOutcome`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GenStats {
+    /// The number of nodes before filtering, including the seed.
+    pub nodes: usize,
+    /// The number of edges before post-processing, including the seed.
+    pub edges: usize,
+    /// The number of edges composed in post-processing.
+    pub composed: usize,
+    /// The number of nodes removed in post-processing.
+    pub removed_nodes: usize,
+    /// The number of edges removed in post-processing, including composed edges.
+    pub removed_edges: usize,
+    /// The largest depth of a generated node.
+    pub depth: usize,
+}
+
+/// The result of generating a graph, which always includes the graph.
+///
+/// When an error happens, the graph contains the nodes and edges generated so far,
+/// the same way as the partial graph returned by `gen`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenOutcome<T, U, E> {
+    /// The generated graph.
+    pub graph: Graph<T, U>,
+    /// The error that was reported, if any.
+    ///
+    /// With `ErrorPolicy::ContinueCollecting`, this is only the limit that was reached.
+    pub error: Option<E>,
+    /// Every error from `f` and `h` in order, with `ErrorPolicy::ContinueCollecting`.
+    pub errors: Vec<E>,
+    /// Counts of what happened while generating.
+    pub stats: GenStats,
+}
+
+impl<T, U, E> GenOutcome<T, U, E> {
+    /// Returns `true` if there was no error.
+    pub fn is_ok(&self) -> bool {self.error.is_none() && self.errors.is_empty()}
+
+    /// Converts into the result type of `gen`, with the first collected error if any.
+    pub fn into_result(self) -> GenResult<Graph<T, U>, E> {
+        into_result(self.graph, Errors {error: self.error, collected: self.errors})
     }
-    for edge in &edges {
-        has_edge.insert(edge.0);
+}
+
+/// Generates a graph, returning the graph, the error and statistics together.
+///
+/// This works the same way as `gen`,
+/// but one does not need to match on the result to get the graph.
+pub fn gen_outcome<T, U, F, G, H, E>(
+    seed: Graph<T, U>,
+    n: usize,
+    f: F,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenOutcome<T, U, E>
+    where T: Eq + Hash + Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let mut exp = expand_unit(seed, n, f, settings);
+    let nodes = exp.graph.0.len();
+    let edges = exp.graph.1.len();
+    let depth = exp.depth.iter().cloned().max().unwrap_or(0);
+    let (graph, _, map_edges) = post_process_observe(exp.graph, &mut exp.has_edge,
+        |_, t| g(t), |_, _, _, x, y| h(x, y), settings, &mut exp.error, |_, _, _| {});
+    let composed = map_edges.len() - edges;
+    let stats = GenStats {
+        nodes,
+        edges,
+        composed,
+        removed_nodes: nodes - graph.0.len(),
+        removed_edges: edges + composed - graph.1.len(),
+        depth,
+    };
+    let Errors {error, collected: errors} = exp.error;
+    GenOutcome {graph, error, errors, stats}
+}
+
+/// Generates a graph, returning only the error when anything fails.
+///
+/// This works the same way as `gen` with `ErrorPolicy::AbortOnFirst`,
+/// but stops at the first error from `f` or limit, without post-processing,
+/// and does not return the partial graph.
+/// Errors from `h` while post-processing also stop with an error,
+/// while `Err(None)` still skips composing edges.
+/// This is used for checks where a complete graph is expected.
+pub fn gen_strict<T, U, F, G, H, E>(
+    seed: Graph<T, U>,
+    n: usize,
+    f: F,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> Result<Graph<T, U>, E>
+    where T: Eq + Hash + Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let settings = &GenerateSettings {error_policy: ErrorPolicy::AbortOnFirst, ..settings.clone()};
+    let mut exp = expand_unit(seed, n, f, settings);
+    if let Some(err) = exp.error.error.take() {return Err(err)};
+    let (graph, _) =
+        post_process(exp.graph, &mut exp.has_edge, |_, t| g(t), h, settings, &mut exp.error);
+    if let Some(err) = exp.error.error {Err(err)} else {Ok(graph)}
+}
+
+/// The three functions of `gen`, as a trait.
+///
+/// This is used to store a generator in a struct, or to implement it on a type
+/// that keeps settings for the problem, instead of passing three closures.
+/// A tuple of closures `(n, f, g, h)` is also a generator, with the same meaning as in `gen`.
+pub trait Generator<T, U, E> {
+    /// Returns the number of times `successor` is called for every node.
+    fn steps(&self) -> usize;
+    /// Returns the node and edge of step `j` from a node.
+    fn successor(&self, node: &T, j: usize) -> Result<(T, U), E>;
+    /// Returns `true` if a node is kept in post-processing.
+    ///
+    /// Keeps all nodes by default.
+    fn keep(&self, _node: &T) -> bool {true}
+    /// Composes two edges, when the node between them is removed.
+    fn compose(&self, a: &U, b: &U) -> Result<U, Option<E>>;
+}
+
+impl<T, U, E, F, G, H> Generator<T, U, E> for (usize, F, G, H)
+    where F: Fn(&T, usize) -> Result<(T, U), E>,
+          G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>
+{
+    fn steps(&self) -> usize {self.0}
+    fn successor(&self, node: &T, j: usize) -> Result<(T, U), E> {(self.1)(node, j)}
+    fn keep(&self, node: &T) -> bool {(self.2)(node)}
+    fn compose(&self, a: &U, b: &U) -> Result<U, Option<E>> {(self.3)(a, b)}
+}
+
+/// Generates a graph using a `Generator`.
+///
+/// This works the same way as `gen`.
+pub fn gen_with<T, U, X, E>(
+    generator: &X,
+    seed: Graph<T, U>,
+    settings: &GenerateSettings,
+) -> GenResult<Graph<T, U>, E>
+    where T: Eq + Hash + Clone,
+          X: Generator<T, U, E> + ?Sized,
+          E: From<GenerateError>
+{
+    gen(seed, generator.steps(), |t, j| generator.successor(t, j), |t| generator.keep(t),
+        |a, b| generator.compose(a, b), settings)
+}
+
+/// The arguments of `gen`, bundled such that the functions are set by name.
+///
+/// Passing `g` and `h` in the wrong order to `gen` compiles when nodes and edges
+/// have the same type, which is avoided by setting them with `filter` and `compose`.
+/// By default, all nodes are kept and edges are never composed.
+///
+/// For example:
+///
+/// ```
+/// use graph_builder::{GenSpec, GenerateSettings};
+///
+/// let settings = GenerateSettings::new(100, 1000);
+/// let f = |&x: &u32, j: usize| Ok::<_, ()>(((x + 1 + j as u32) % 6, 1 + j as u32));
+/// let graph = GenSpec::new((vec![0], vec![]), 2, f, settings)
+///     .filter(|x| x % 2 == 0)
+///     .compose(|a, b| Ok(a + b))
+///     .run()
+///     .unwrap();
+/// assert_eq!(graph.0, vec![0, 2, 4]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct GenSpec<T, U, F, G, H> {
+    /// The initial graph.
+    pub seed: Graph<T, U>,
+    /// The number of times `f` is called for every node.
+    pub n: usize,
+    /// Generates the node and edge of step `j` from a node.
+    pub f: F,
+    /// Returns `true` if a node is kept in post-processing.
+    pub g: G,
+    /// Composes two edges, when the node between them is removed.
+    pub h: H,
+    /// The settings for generating the graph.
+    pub settings: GenerateSettings,
+}
+
+impl<T, U, F, E> GenSpec<T, U, F, fn(&T) -> bool, fn(&U, &U) -> Result<U, Option<E>>> {
+    /// Creates a new specification that keeps all nodes.
+    pub fn new(seed: Graph<T, U>, n: usize, f: F, settings: GenerateSettings) -> Self
+        where F: Fn(&T, usize) -> Result<(T, U), E>
+    {
+        GenSpec {seed, n, f, g: |_| true, h: |_, _| Err(None), settings}
     }
-    let mut i = 0;
-    'outer: while i < nodes.len() {
-        for j in 0..n {
-            match f(&nodes[i], j) {
-                Ok((new_node, new_edge)) => {
-                    let id = if let Some(&id) = has.get(&new_node) {id}
-                    else {
-                        let id = nodes.len();
-                        has.insert(new_node.clone(), id);
-                        nodes.push(new_node);
-                        id
-                    };
-                    has_edge.insert([i, id]);
-                    edges.push(([i, id], new_edge));
+}
 
-                    if nodes.len() >= settings.max_nodes {
-                        if error.is_none() {
-                            error = Some(GenerateError::MaxNodes.into());
-                        }
-                        break 'outer;
-                    } else if edges.len() >= settings.max_edges {
-                        if error.is_none() {
-                            error = Some(GenerateError::MaxEdges.into());
-                        }
-                        break 'outer;
-                    }
-                }
-                Err(err) => {
-                    error = Some(err);
-                }
-            }
-        }
-        i += 1;
+impl<T, U, F, G, H> GenSpec<T, U, F, G, H> {
+    /// Sets the filter `g`, which returns `true` for the nodes to keep.
+    pub fn filter<G2>(self, g: G2) -> GenSpec<T, U, F, G2, H>
+        where G2: Fn(&T) -> bool
+    {
+        let GenSpec {seed, n, f, h, settings, ..} = self;
+        GenSpec {seed, n, f, g, h, settings}
     }
-    let mut removed: HashSet<usize> = HashSet::new();
-    // Hash nodes that do not passes filter.
-    for i in 0..nodes.len() {if !g(&nodes[i]) {removed.insert(i);}}
-    let edges_count = edges.len();
-    let mut removed_edges: Vec<usize> = vec![];
-    let mut j = 0;
-    // Generate new edges by composing them if they got removed.
-    while j < edges.len() {
-        let [a, b] = edges[j].0;
-        if removed.contains(&b) {
-            removed_edges.push(j);
-            // Look for all edges that starts with removed node.
-            for k in 0..edges_count {
-                let [c, d] = edges[k].0;
-                if c == b && !has_edge.contains(&[a, d]) {
-                    // Compose the two edges into a new one that
-                    // no longer refers to the removed node.
-                    match h(&edges[j].1, &edges[k].1) {
-                        Ok(new_edge) => {
-                            edges.push(([a, d], new_edge));
-                            has_edge.insert([a, d]);
-                        }
-                        Err(None) => {}
-                        Err(Some(err)) => {
-                            if error.is_none() {
-                                error = Some(err);
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        j += 1;
+
+    /// Sets the composer `h`, which composes edges through removed nodes.
+    pub fn compose<H2, E>(self, h: H2) -> GenSpec<T, U, F, G, H2>
+        where H2: Fn(&U, &U) -> Result<U, Option<E>>
+    {
+        let GenSpec {seed, n, f, g, settings, ..} = self;
+        GenSpec {seed, n, f, g, h, settings}
     }
 
-    let mut new_nodes = vec![];
-    let mut map_nodes: Vec<Option<usize>> = vec![];
-    for (i, node) in nodes.into_iter().enumerate() {
-        if removed.contains(&i) {
-            map_nodes.push(None);
-        } else {
-            let id = new_nodes.len();
-            map_nodes.push(Some(id));
-            new_nodes.push(node);
-        }
+    /// Generates the graph, see `gen`.
+    pub fn run<E>(self) -> GenResult<Graph<T, U>, E>
+        where T: Eq + Hash + Clone,
+              F: Fn(&T, usize) -> Result<(T, U), E>,
+              G: Fn(&T) -> bool,
+              H: Fn(&U, &U) -> Result<U, Option<E>>,
+              E: From<GenerateError>
+    {
+        gen(self.seed, self.n, self.f, self.g, self.h, &self.settings)
     }
-    for j in (0..edges.len()).rev() {
-        let [a, b] = edges[j].0;
-        if let (Some(a), Some(b)) = (map_nodes[a], map_nodes[b]) {
-            edges[j].0 = [a, b];
-        } else {
-            edges.swap_remove(j);
-        }
+}
+
+/// Generates a graph where the composer also receives the nodes of the composed edges.
+///
+/// This works the same way as `gen`, but `h` is called with the source node,
+/// the removed node in the middle and the target node, followed by the two edges.
+/// This is used when composition rules depend on the states involved,
+/// not only on the edges.
+pub fn gen_with_nodes<T, U, F, G, H, E>(
+    seed: Graph<T, U>,
+    n: usize,
+    f: F,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<Graph<T, U>, E>
+    where T: Eq + Hash + Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          G: Fn(&T) -> bool,
+          H: Fn(&T, &T, &T, &U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let mut exp = expand_unit(seed, n, f, settings);
+    let (graph, _, _) = post_process_observe(exp.graph, &mut exp.has_edge, |_, t| g(t), h,
+        settings, &mut exp.error, |_, _, _| {});
+    into_result(graph, exp.error)
+}
+
+/// The structure around a node in the graph before filtering, see `gen_with_context`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NodeCtx<'a> {
+    /// The index of the node before filtering.
+    pub index: usize,
+    /// The number of steps from the seed when the node was generated.
+    pub depth: usize,
+    /// Outgoing edges, as pairs of target node and edge index.
+    pub out: &'a [(usize, usize)],
+    /// Incoming edges, as pairs of source node and edge index.
+    pub inc: &'a [(usize, usize)],
+}
+
+impl<'a> NodeCtx<'a> {
+    /// Returns the number of outgoing edges.
+    pub fn out_degree(&self) -> usize {self.out.len()}
+
+    /// Returns the number of incoming edges.
+    pub fn in_degree(&self) -> usize {self.inc.len()}
+
+    /// Returns `true` if the node has no outgoing edges, except to itself.
+    pub fn is_leaf(&self) -> bool {self.out.iter().all(|&(j, _)| j == self.index)}
+}
+
+/// Generates a graph where the filter also receives the structure around every node.
+///
+/// This works the same way as `gen`, but `g` is called with a `NodeCtx`,
+/// which stores the depth and the edges of the node in the graph before filtering.
+/// This is used for structural filters, e.g. to remove leaves.
+pub fn gen_with_context<T, U, F, G, H, E>(
+    seed: Graph<T, U>,
+    n: usize,
+    f: F,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<Graph<T, U>, E>
+    where T: Eq + Hash + Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          G: Fn(&T, NodeCtx) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let mut exp = expand_unit(seed, n, f, settings);
+    let inc = Incidence::new(&exp.graph);
+    let depth = &exp.depth;
+    let g = |i, t: &T| {
+        g(t, NodeCtx {index: i, depth: depth[i], out: &inc.out[i], inc: &inc.inc[i]})
+    };
+    let (graph, _) = post_process(exp.graph, &mut exp.has_edge, g, h, settings, &mut exp.error);
+    into_result(graph, exp.error)
+}
+
+/// Generates a graph with a second filter that runs after composing edges.
+///
+/// This works the same way as `gen`, followed by a second pass that removes the nodes
+/// that do not pass the filter `g2`, composing edges through them with `h` again.
+/// The second filter receives a `NodeCtx` with the edges after the first pass,
+/// where indices refer to the graph after the first pass,
+/// such that it can react to the composed structure, e.g. to remove isolated nodes.
+///
+/// The limit of composed edges applies to each pass separately.
+pub fn gen_two_phase<T, U, F, G, G2, H, E>(
+    seed: Graph<T, U>,
+    n: usize,
+    f: F,
+    g: G,
+    g2: G2,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<Graph<T, U>, E>
+    where T: Eq + Hash + Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          G: Fn(&T) -> bool,
+          G2: Fn(&T, NodeCtx) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let mut exp = expand_unit(seed, n, f, settings);
+    let (graph, map_nodes) =
+        post_process(exp.graph, &mut exp.has_edge, |_, t| g(t), &h, settings, &mut exp.error);
+    let mut depth = vec![0; graph.0.len()];
+    for (i, j) in map_nodes.into_iter().enumerate() {
+        if let Some(j) = j {depth[j] = exp.depth[i]};
     }
+    let inc = Incidence::new(&graph);
+    let mut has_edge = EdgeSet::new(settings.edge_index, settings.max_nodes);
+    for edge in &graph.1 {
+        has_edge.insert(edge.0);
+    }
+    let g2 = |i, t: &T| {
+        g2(t, NodeCtx {index: i, depth: depth[i], out: &inc.out[i], inc: &inc.inc[i]})
+    };
+    let (graph, _) = post_process(graph, &mut has_edge, g2, h, settings, &mut exp.error);
+    into_result(graph, exp.error)
+}
 
-    if let Some(err) = error {
-        Err(((new_nodes, edges), err))
-    } else {
-        Ok((new_nodes, edges))
+/// Wraps a function `f` such that calls returning an error are retried.
+///
+/// Every failing call is repeated up to `retries` times,
+/// waiting `delay` before the first retry and doubling the delay for every further retry.
+/// When all retries fail, the last error is returned.
+///
+/// This is used when `f` has transient failures, e.g. when calling an external process,
+/// such that a single failure does not remove a branch from the graph.
+/// Since errors are retried, do not use this when `f` returns errors
+/// for operations that do not apply.
+pub fn retry<T, U, F, E>(
+    f: F,
+    retries: usize,
+    delay: std::time::Duration,
+) -> impl Fn(&T, usize) -> Result<(T, U), E>
+    where F: Fn(&T, usize) -> Result<(T, U), E>
+{
+    move |t, j| {
+        let mut res = f(t, j);
+        let mut wait = delay;
+        for _ in 0..retries {
+            if res.is_ok() {break};
+            if wait > std::time::Duration::from_secs(0) {std::thread::sleep(wait)};
+            wait *= 2;
+            res = f(t, j);
+        }
+        res
     }
 }
 
-/// Filters edges such that only those who are equal in both directions remains.
+/// Wraps a composer `h` for edges that carry a cost `C` next to the data `U`.
 ///
-/// Removes redundant edges and edges which only exist in one direction.
+/// The data is composed with `h` and the costs are added,
+/// such that `h` does not need to keep track of costs.
+/// This can be used with any function that composes edges,
+/// e.g. `filter_compose` or `search::gen_uniform_cost` with the cost `|e| e.1`.
+#[allow(clippy::type_complexity)]
+pub fn compose_cost<U, C, H, E>(h: H) -> impl Fn(&(U, C), &(U, C)) -> Result<(U, C), Option<E>>
+    where C: Copy + std::ops::Add<Output = C>,
+          H: Fn(&U, &U) -> Result<U, Option<E>>
+{
+    move |(a, x), (b, y)| h(a, b).map(|u| (u, *x + *y))
+}
+
+/// Returns a filter that keeps all nodes.
 ///
-/// Does not preserve the order of edges.
-/// The order of the edges is unsorted afterwards.
+/// With this filter, no edges are composed.
+pub fn keep_all<T>() -> impl Fn(&T) -> bool {
+    |_| true
+}
+
+/// Returns a composer that never composes edges.
 ///
-/// Assumes that there are maximum two edges between nodes.
-pub fn bidir<T: PartialEq + std::fmt::Debug>(edges: &mut Vec<([usize; 2], T)>) {
-    if edges.len() == 0 {return};
+/// This is used when the filter keeps all nodes,
+/// or to remove nodes together with their edges.
+pub fn never_compose<U, E>() -> impl Fn(&U, &U) -> Result<U, Option<E>> {
+    |_, _| Err(None)
+}
 
-    // Fix indices such that they pair up.
-    for j in 0..edges.len() {
-        let [a, b] = edges[j].0;
-        edges[j].0 = [a.min(b), a.max(b)];
+/// Returns a composer for edges that store a list, e.g. of operations,
+/// which concatenates the lists in path order.
+pub fn concat_compose<X: Clone, E>() -> impl Fn(&Vec<X>, &Vec<X>) -> Result<Vec<X>, Option<E>> {
+    |a, b| {
+        let mut res = Vec::with_capacity(a.len() + b.len());
+        res.extend_from_slice(a);
+        res.extend_from_slice(b);
+        Ok(res)
     }
-    edges.sort_by_key(|s| s.0);
-    let mut pair = false;
-    for j in (0..edges.len()).rev() {
-        let k = j + 1;
-        if pair {
-            if k >= edges.len() {
-                edges.swap_remove(j);
-            } else {
-                if edges[j] == edges[k] {
-                    edges.swap_remove(k);
-                } else {
-                    edges.swap_remove(j);
-                }
-                pair = false;
-            }
-        } else {
-            pair = true;
-        }
+}
+
+/// Returns a composer for edges that store a cost, which adds the costs.
+///
+/// A composed path costs the sum of its edges.
+/// Since an edge is only composed when there is no edge between the same nodes,
+/// the composed cost is the cost of one path, which is not always the cheapest.
+/// For edges that also store data, see `compose_cost`.
+pub fn add_cost_compose<C, E>() -> impl Fn(&C, &C) -> Result<C, Option<E>>
+    where C: Copy + std::ops::Add<Output = C>
+{
+    |&a, &b| Ok(a + b)
+}
+
+/// Generates a graph where every edge carries a cost next to its data.
+///
+/// This works the same way as `gen`, but `f` also returns the cost of the new edge.
+/// Composed edges cost the sum of the edges they were composed from,
+/// see `compose_cost`.
+pub fn gen_cost<T, U, C, F, G, H, E>(
+    seed: Graph<T, (U, C)>,
+    n: usize,
+    f: F,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<Graph<T, (U, C)>, E>
+    where T: Eq + Hash + Clone,
+          C: Copy + std::ops::Add<Output = C>,
+          F: Fn(&T, usize) -> Result<(T, U, C), E>,
+          G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    gen(seed, n, |t, j| f(t, j).map(|(t, u, c)| (t, (u, c))), g, compose_cost(h), settings)
+}
+
+/// Generates a graph and the depth of every node.
+///
+/// This works the same way as `gen`, but also returns a list of depths aligned with the nodes.
+/// The depth of a node is the number of steps from the seed to where the node was first generated.
+/// Seed nodes have depth zero.
+///
+/// Since nodes are expanded in the order they are generated,
+/// the depth is the shortest distance from the seed before filtering.
+/// Filtering nodes does not change the depth of the remaining nodes.
+pub fn gen_depth<T, U, F, G, H, E>(
+    seed: Graph<T, U>,
+    n: usize,
+    f: F,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<(Graph<T, U>, Vec<usize>), E>
+    where T: Eq + Hash + Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let mut exp = expand_unit(seed, n, f, settings);
+    let (graph, map_nodes) =
+        post_process(exp.graph, &mut exp.has_edge, |_, t| g(t), h, settings, &mut exp.error);
+    let depth = retain_mapped(exp.depth, &map_nodes);
+    into_result((graph, depth), exp.error)
+}
+
+/// Generates a graph and the provenance of every node.
+///
+/// This works the same way as `gen`, but also returns a list aligned with the nodes,
+/// containing the index of the node that first produced it and the step `j` passed to `f`.
+///
+/// The provenance forms a spanning tree of the generated nodes,
+/// which tells how every node was first reached from the seed.
+///
+/// Seed nodes have no provenance.
+/// When the parent of a node is removed by the filter `g`,
+/// the node has no provenance, since it can not refer to the removed node.
+pub fn gen_provenance<T, U, F, G, H, E>(
+    seed: Graph<T, U>,
+    n: usize,
+    f: F,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<(Graph<T, U>, Vec<Provenance>), E>
+    where T: Eq + Hash + Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let mut exp = expand_unit(seed, n, f, settings);
+    let (graph, map_nodes) =
+        post_process(exp.graph, &mut exp.has_edge, |_, t| g(t), h, settings, &mut exp.error);
+    let parent = retain_mapped(exp.parent, &map_nodes).into_iter()
+        .map(|p| p.and_then(|(i, j)| map_nodes[i].map(|i| (i, j))))
+        .collect();
+    into_result((graph, parent), exp.error)
+}
+
+/// Stores which edges every composed edge was composed from, see `gen_edge_provenance`.
+///
+/// Edges are identified by their index before removing nodes,
+/// where generated edges come first, followed by composed edges in the order they were added.
+/// A composed edge might be composed from other composed edges.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EdgeProvenance {
+    /// The id of every edge in the result.
+    pub ids: Vec<usize>,
+    /// The ids of the two edges that every edge was composed from,
+    /// or `None` for edges that were not composed.
+    pub sources: Vec<Option<[usize; 2]>>,
+}
+
+impl EdgeProvenance {
+    /// Returns the ids of the two edges that edge `k` in the result was composed from.
+    pub fn sources_of(&self, k: usize) -> Option<[usize; 2]> {self.sources[self.ids[k]]}
+
+    /// Returns the ids of the edges that were not composed, which edge `id` is composed of,
+    /// in the order along its path.
+    pub fn primitives(&self, id: usize) -> Vec<usize> {
+        let mut res = vec![];
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            match self.sources[id] {
+                Some([a, b]) => {
+                    stack.push(b);
+                    stack.push(a);
+                }
+                None => res.push(id),
+            }
+        }
+        res
+    }
+}
+
+/// Generates a graph and records which edges every composed edge was composed from.
+///
+/// This works the same way as `gen`, but also returns an `EdgeProvenance`,
+/// which tells the derivation of every composed edge.
+pub fn gen_edge_provenance<T, U, F, G, H, E>(
+    seed: Graph<T, U>,
+    n: usize,
+    f: F,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<(Graph<T, U>, EdgeProvenance), E>
+    where T: Eq + Hash + Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let mut exp = expand_unit(seed, n, f, settings);
+    let mut sources: Vec<Option<[usize; 2]>> = vec![None; exp.graph.1.len()];
+    let (graph, _, map_edges) = post_process_observe(exp.graph, &mut exp.has_edge,
+        |_, t| g(t), |_, _, _, x, y| h(x, y), settings, &mut exp.error,
+        |_, from, _| sources.push(Some(from)));
+    let mut ids = vec![0; graph.1.len()];
+    for (id, k) in map_edges.into_iter().enumerate() {
+        if let Some(k) = k {ids[k] = id};
+    }
+    into_result((graph, EdgeProvenance {ids, sources}), exp.error)
+}
+
+/// Generates a graph and the number of times every node was returned by `f`.
+///
+/// This works the same way as `gen`, but also returns a list of counts aligned with the nodes.
+/// Every time `f` returns a node, its count increases by one, including the first time,
+/// so a node that was generated through `k` different steps has count `k`.
+/// Seed nodes start at zero.
+///
+/// Since every call to `f` adds an edge, the count is the number of generated
+/// edges that point to the node, before filtering.
+pub fn gen_hits<T, U, F, G, H, E>(
+    seed: Graph<T, U>,
+    n: usize,
+    f: F,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<(Graph<T, U>, Vec<usize>), E>
+    where T: Eq + Hash + Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let seed_attr = vec![(); seed.0.len()];
+    let f = |t: &T, j| if j < n {Some(f(t, j).map(|(t, u)| (Emit::NewNode(t, u), ())))}
+        else {None};
+    let mut frontier = Hits(Fifo(0), vec![0; seed.0.len()]);
+    let mut exp = expand(seed, seed_attr, f, &mut frontier, settings);
+    let (graph, map_nodes) =
+        post_process(exp.graph, &mut exp.has_edge, |_, t| g(t), h, settings, &mut exp.error);
+    let hits = retain_mapped(frontier.1, &map_nodes);
+    into_result((graph, hits), exp.error)
+}
+
+/// Receives events while generating a graph.
+///
+/// All methods do nothing by default,
+/// such that one only implements the events of interest.
+/// A pair of closures `(on_node, on_edge)` is also an observer.
+pub trait Observer<T, U> {
+    /// Called when a node is added, with its index before filtering.
+    fn on_node(&mut self, _i: usize, _node: &T) {}
+    /// Called when an edge is added, with its index before filtering.
+    fn on_edge(&mut self, _k: usize, _edge: &([usize; 2], U)) {}
+}
+
+impl<T, U, N, M> Observer<T, U> for (N, M)
+    where N: FnMut(usize, &T), M: FnMut(usize, &([usize; 2], U))
+{
+    fn on_node(&mut self, i: usize, node: &T) {(self.0)(i, node)}
+    fn on_edge(&mut self, k: usize, edge: &([usize; 2], U)) {(self.1)(k, edge)}
+}
+
+/// Generates a graph while reporting every added node and edge to an observer.
+///
+/// This works the same way as `gen`, but calls `observer` for the seed,
+/// for every new node and edge while generating,
+/// and for every composed edge in post-processing.
+/// This is used to mirror the growing graph into an external store.
+///
+/// Indices refer to nodes and edges before filtering.
+/// Post-processing removes the nodes that do not pass the filter `g`
+/// and the edges that refer to them, which is not reported.
+pub fn gen_observe<T, U, F, G, H, O, E>(
+    seed: Graph<T, U>,
+    n: usize,
+    f: F,
+    g: G,
+    h: H,
+    observer: &mut O,
+    settings: &GenerateSettings,
+) -> GenResult<Graph<T, U>, E>
+    where T: Eq + Hash + Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          O: Observer<T, U>,
+          E: From<GenerateError>
+{
+    for (i, node) in seed.0.iter().enumerate() {observer.on_node(i, node)}
+    for (k, edge) in seed.1.iter().enumerate() {observer.on_edge(k, edge)}
+    let seed_attr = vec![(); seed.0.len()];
+    let f = |t: &T, j| if j < n {Some(f(t, j).map(|(t, u)| (Emit::NewNode(t, u), ())))}
+        else {None};
+    let edges = seed.1.len();
+    let mut frontier = Observed {fifo: Fifo(0), observer: &mut *observer, new_node: None, edges};
+    let mut exp = expand(seed, seed_attr, f, &mut frontier, settings);
+    let (graph, _, _) = post_process_observe(exp.graph, &mut exp.has_edge, |_, t| g(t),
+        |_, _, _, x, y| h(x, y), settings, &mut exp.error, |k, _, edge| observer.on_edge(k, edge));
+    into_result(graph, exp.error)
+}
+
+/// Generates a graph with an attribute for every node.
+///
+/// This works the same way as `gen`, but `f` also returns an attribute for the new node.
+/// The attributes are collected into a list aligned with the nodes,
+/// which is filtered and remapped together with the nodes.
+///
+/// An attribute is only kept when the node is generated the first time.
+/// Attributes of seed nodes are passed in `seed_attr`, which must have the same length
+/// as the list of seed nodes.
+///
+/// Use this instead of storing attributes inside nodes,
+/// since nodes are hashed and compared to detect duplicates.
+pub fn gen_attr<T, U, A, F, G, H, E>(
+    seed: Graph<T, U>,
+    seed_attr: Vec<A>,
+    n: usize,
+    f: F,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<(Graph<T, U>, Vec<A>), E>
+    where T: Eq + Hash + Clone,
+          F: Fn(&T, usize) -> Result<(T, U, A), E>,
+          G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    assert_eq!(seed.0.len(), seed_attr.len(), "Expected one attribute per seed node");
+    let f = |t: &T, j| if j < n {Some(f(t, j).map(|(t, u, a)| (Emit::NewNode(t, u), a)))}
+        else {None};
+    let mut exp = expand(seed, seed_attr, f, &mut Fifo(0), settings);
+    let (graph, map_nodes) =
+        post_process(exp.graph, &mut exp.has_edge, |_, t| g(t), h, settings, &mut exp.error);
+    let attr = retain_mapped(exp.attr, &map_nodes);
+    into_result((graph, attr), exp.error)
+}
+
+/// Generates a graph where nodes are replaced by a canonical representative.
+///
+/// This works the same way as `gen`, but every node is passed through `canon`
+/// before checking whether it already exists.
+/// Symmetric variants of a node are joined into one node,
+/// and `f` always receives the canonical representative.
+///
+/// This is the cheapest way to exploit symmetry of a problem
+/// without computing the full orbit of every node.
+///
+/// Seed nodes are also replaced by their canonical representative.
+pub fn gen_canon<T, U, F, C, G, H, E>(
+    (nodes, edges): Graph<T, U>,
+    n: usize,
+    f: F,
+    canon: C,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<Graph<T, U>, E>
+    where T: Eq + Hash + Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          C: Fn(&T) -> T,
+          G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let seed = (nodes.iter().map(&canon).collect(), edges);
+    let f = |t: &T, j| f(t, j).map(|(t, u)| (canon(&t), u));
+    gen(seed, n, f, g, h, settings)
+}
+
+/// Generates the derivation tree without joining equal nodes.
+///
+/// This works the same way as `gen`, but every node returned by `f` is added as a new node,
+/// even when an equal node already exists.
+/// The result is a forest rooted at the seed nodes, where every generated node
+/// has exactly one incoming edge from the node it was generated from.
+/// Since nodes are never compared, they do not need to implement `Eq` or `Hash`.
+///
+/// The number of nodes grows exponentially with depth,
+/// so generation usually ends by reaching the maximum number of nodes or edges.
+pub fn gen_tree<T, U, F, G, H, E>(
+    seed: Graph<T, U>,
+    n: usize,
+    f: F,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<Graph<T, U>, E>
+    where F: Fn(&T, usize) -> Result<(T, U), E>,
+          G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let seed_attr = vec![(); seed.0.len()];
+    let f = |t: &T, j| if j < n {Some(f(t, j).map(|(t, u)| (Emit::NewNode(t, u), ())))}
+        else {None};
+    let mut exp = expand_with(seed, seed_attr, f, &mut Fifo(0), NoIndex, settings);
+    let (graph, _) =
+        post_process(exp.graph, &mut exp.has_edge, |_, t| g(t), h, settings, &mut exp.error);
+    into_result(graph, exp.error)
+}
+
+/// Generates a graph where edges between the same nodes are combined.
+///
+/// This works the same way as `gen`, but when `f` returns an edge between two nodes
+/// that already have an edge in the same direction, the edges are combined with `combine`
+/// instead of adding another edge.
+/// For example, to keep the shorter of two proofs.
+///
+/// Combined edges do not count toward the maximum number of edges,
+/// so the limit applies to the number of distinct pairs of nodes.
+pub fn gen_combine<T, U, F, C, G, H, E>(
+    seed: Graph<T, U>,
+    n: usize,
+    f: F,
+    combine: C,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<Graph<T, U>, E>
+    where T: Eq + Hash + Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          C: Fn(&U, &U) -> U,
+          G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let mut frontier = Combined {fifo: Fifo(0), combine, edge_pos: HashMap::new()};
+    for (k, edge) in seed.1.iter().enumerate() {
+        frontier.edge_pos.entry(edge.0).or_insert(k);
+    }
+    let seed_attr = vec![(); seed.0.len()];
+    let f = |t: &T, j| if j < n {Some(f(t, j).map(|(t, u)| (Emit::NewNode(t, u), ())))}
+        else {None};
+    let mut exp = expand(seed, seed_attr, f, &mut frontier, settings);
+    let (graph, _) =
+        post_process(exp.graph, &mut exp.has_edge, |_, t| g(t), h, settings, &mut exp.error);
+    into_result(graph, exp.error)
+}
+
+/// Combines edges between the same nodes while expanding nodes
+/// in the order they were generated.
+struct Combined<C> {
+    fifo: Fifo,
+    combine: C,
+    /// The position of the first edge between every pair of nodes.
+    edge_pos: HashMap<[usize; 2], usize>,
+}
+
+impl<T, U, C: Fn(&U, &U) -> U> Frontier<T, U> for Combined<C> {
+    fn next(&mut self, nodes: &[T], depth: &[usize]) -> Option<usize> {
+        <Fifo as Frontier<T, U>>::next(&mut self.fifo, nodes, depth)
+    }
+
+    fn add_edge(
+        &mut self,
+        edges: &mut Vec<([usize; 2], U)>,
+        _has_edge: &EdgeSet,
+        (key, u): ([usize; 2], U),
+    ) -> bool {
+        if let Some(&k) = self.edge_pos.get(&key) {
+            edges[k].1 = (self.combine)(&edges[k].1, &u);
+            false
+        } else {
+            self.edge_pos.insert(key, edges.len());
+            edges.push((key, u));
+            true
+        }
+    }
+}
+
+/// Generates a graph from a function that returns the successors of a node.
+///
+/// This works the same way as `gen`, but instead of calling `f` with `j = 0..n`,
+/// `f` is called once per node and returns an iterator of new nodes with edges.
+/// This is useful when nodes have a variable number of successors.
+///
+/// The step `j` of a new node is its position in the iterator.
+/// Since the iterator can not borrow the node, copy the data it needs before returning.
+pub fn gen_from_iter<T, U, F, I, G, H, E>(
+    seed: Graph<T, U>,
+    f: F,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<Graph<T, U>, E>
+    where T: Eq + Hash + Clone,
+          F: Fn(&T) -> I,
+          I: IntoIterator<Item = Result<(T, U), E>>,
+          G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let seed_attr = vec![(); seed.0.len()];
+    let mut iter: Option<I::IntoIter> = None;
+    let f = |t: &T, j| {
+        if j == 0 {iter = Some(f(t).into_iter())};
+        iter.as_mut()?.next().map(|res| res.map(|(t, u)| (Emit::NewNode(t, u), ())))
+    };
+    let mut exp = expand(seed, seed_attr, f, &mut Fifo(0), settings);
+    let (graph, _) =
+        post_process(exp.graph, &mut exp.has_edge, |_, t| g(t), h, settings, &mut exp.error);
+    into_result(graph, exp.error)
+}
+
+/// Generates a graph from a function that returns all successors of a node in one call.
+///
+/// This works the same way as `gen`, but `f` is called once per node
+/// and returns a list of new nodes with edges.
+/// Use this when computing successors shares expensive setup between operations.
+///
+/// When `f` returns an error, the node has no successors.
+pub fn gen_batch<T, U, F, G, H, E>(
+    seed: Graph<T, U>,
+    f: F,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<Graph<T, U>, E>
+    where T: Eq + Hash + Clone,
+          F: Fn(&T) -> Result<Vec<(T, U)>, E>,
+          G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let f = |t: &T| {
+        let (list, err) = match f(t) {
+            Ok(list) => (list, None),
+            Err(err) => (vec![], Some(Err(err))),
+        };
+        list.into_iter().map(Ok).chain(err)
+    };
+    gen_from_iter(seed, f, g, h, settings)
+}
+
+/// Generates a graph and passes it in chunks to a callback while generating.
+///
+/// This works the same way as `gen` without post-processing,
+/// since filtering nodes requires the whole graph.
+/// Whenever `chunk_size` new nodes or edges are generated, `on_chunk` is called with
+/// the index of the first new node, the new nodes, the index of the first new edge
+/// and the new edges. The seed is passed in the first chunk.
+/// Every edge refers to nodes that were passed in the same chunk or earlier.
+///
+/// When `truncate` is `true`, edges are dropped after being passed to `on_chunk`,
+/// and the returned graph is empty.
+/// This is used to stream large graphs to a file or a database.
+/// Nodes are still kept until generation ends, to detect duplicates and expand them,
+/// but edges are not kept in memory.
+pub fn gen_chunked<T, U, F, C, E>(
+    seed: Graph<T, U>,
+    n: usize,
+    f: F,
+    chunk_size: usize,
+    truncate: bool,
+    on_chunk: C,
+    settings: &GenerateSettings,
+) -> GenResult<Graph<T, U>, E>
+    where T: Eq + Hash + Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          C: FnMut(usize, &[T], usize, &[([usize; 2], U)]),
+          E: From<GenerateError>
+{
+    let mut frontier = Chunks {
+        fifo: Fifo(0),
+        on_chunk,
+        chunk_size,
+        truncate,
+        new_nodes: seed.0.len(),
+        new_edges: seed.1.len(),
+        edge_offset: 0,
+    };
+    let f = |t: &T, j| if j < n {Some(f(t, j).map(|(t, u)| (Emit::NewNode(t, u), ())))}
+        else {None};
+    let seed_attr = vec![(); seed.0.len()];
+    let exp = expand(seed, seed_attr, f, &mut frontier, settings);
+    let (mut nodes, mut edges) = exp.graph;
+    if frontier.new_nodes > 0 || frontier.new_edges > 0 {frontier.flush(&nodes, &mut edges)};
+    if truncate {nodes.clear()};
+    into_result((nodes, edges), exp.error)
+}
+
+/// Passes new nodes and edges in chunks to a callback while expanding nodes
+/// in the order they were generated.
+struct Chunks<C> {
+    fifo: Fifo,
+    on_chunk: C,
+    chunk_size: usize,
+    truncate: bool,
+    /// The number of nodes not yet passed to `on_chunk`.
+    new_nodes: usize,
+    /// The number of edges not yet passed to `on_chunk`.
+    new_edges: usize,
+    /// The number of edges passed to `on_chunk` and dropped.
+    edge_offset: usize,
+}
+
+impl<C> Chunks<C> {
+    /// Passes the new nodes and edges to `on_chunk`.
+    fn flush<T, U>(&mut self, nodes: &[T], edges: &mut Vec<([usize; 2], U)>)
+        where C: FnMut(usize, &[T], usize, &[([usize; 2], U)])
+    {
+        let (a, b) = (nodes.len() - self.new_nodes, edges.len() - self.new_edges);
+        (self.on_chunk)(a, &nodes[a..], self.edge_offset + b, &edges[b..]);
+        if self.truncate {
+            self.edge_offset += edges.len();
+            edges.clear();
+        }
+        self.new_nodes = 0;
+        self.new_edges = 0;
+    }
+}
+
+impl<T, U, C> Frontier<T, U> for Chunks<C>
+    where C: FnMut(usize, &[T], usize, &[([usize; 2], U)])
+{
+    fn next(&mut self, nodes: &[T], depth: &[usize]) -> Option<usize> {
+        <Fifo as Frontier<T, U>>::next(&mut self.fifo, nodes, depth)
+    }
+
+    fn edge(&mut self, nodes: &[T], edges: &mut Vec<([usize; 2], U)>) {
+        self.new_edges += 1;
+        if self.new_nodes >= self.chunk_size || self.new_edges >= self.chunk_size {
+            self.flush(nodes, edges);
+        }
+    }
+
+    fn trace(&mut self, entry: trace::TraceEntry) {
+        if let trace::TraceResult::NewNode(_) = entry.result {self.new_nodes += 1};
+    }
+}
+
+/// Stores a node or edge emitted by a generator.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Emit<T, U> {
+    /// A node with an edge from the expanded node.
+    ///
+    /// The node is added when it does not exist.
+    NewNode(T, U),
+    /// An edge from the expanded node to an existing node.
+    ///
+    /// The edge is ignored when the node does not exist.
+    EdgeTo(T, U),
+    /// An edge between two existing nodes.
+    ///
+    /// The edge is ignored when any of the nodes does not exist.
+    EdgeBetween(T, T, U),
+}
+
+/// Generates a graph from a function that can emit edges between existing nodes.
+///
+/// This works the same way as `gen`, but `f` returns `Emit`,
+/// which makes it possible to record relations between nodes that are already known,
+/// instead of only edges from the expanded node to new nodes.
+///
+/// Edges between existing nodes count toward the maximum number of edges.
+pub fn gen_emit<T, U, F, G, H, E>(
+    seed: Graph<T, U>,
+    n: usize,
+    f: F,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<Graph<T, U>, E>
+    where T: Eq + Hash + Clone,
+          F: Fn(&T, usize) -> Result<Emit<T, U>, E>,
+          G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let seed_attr = vec![(); seed.0.len()];
+    let f = |t: &T, j| if j < n {Some(f(t, j).map(|emit| (emit, ())))} else {None};
+    let mut exp = expand(seed, seed_attr, f, &mut Fifo(0), settings);
+    let (graph, _) =
+        post_process(exp.graph, &mut exp.has_edge, |_, t| g(t), h, settings, &mut exp.error);
+    into_result(graph, exp.error)
+}
+
+/// Decides the order in which nodes are expanded.
+trait Frontier<T, U> {
+    /// Returns the next node to expand, or `None` to stop expanding.
+    fn next(&mut self, nodes: &[T], depth: &[usize]) -> Option<usize>;
+    /// Adds an edge while expanding, returning whether it counts as a new edge.
+    ///
+    /// Returns `false` when the edge is combined with an existing edge or dropped.
+    fn add_edge(
+        &mut self,
+        edges: &mut Vec<([usize; 2], U)>,
+        _has_edge: &EdgeSet,
+        edge: ([usize; 2], U),
+    ) -> bool {
+        edges.push(edge);
+        true
+    }
+    /// Called after a new edge is added while expanding, which is the last edge.
+    ///
+    /// Edges can be removed, e.g. after passing them on,
+    /// since only the number of edges is used to check the limits.
+    fn edge(&mut self, _nodes: &[T], _edges: &mut Vec<([usize; 2], U)>) {}
+    /// Called for every new node, duplicate node or error returned while expanding.
+    fn trace(&mut self, _entry: trace::TraceEntry) {}
+}
+
+/// Expands every node once, in the order they were generated.
+struct Fifo(usize);
+
+impl<T, U> Frontier<T, U> for Fifo {
+    fn next(&mut self, nodes: &[T], _depth: &[usize]) -> Option<usize> {
+        if self.0 < nodes.len() {
+            self.0 += 1;
+            Some(self.0 - 1)
+        } else {None}
+    }
+}
+
+/// Counts the number of times every node is returned while expanding nodes
+/// in the order they were generated.
+struct Hits(Fifo, Vec<usize>);
+
+impl<T, U> Frontier<T, U> for Hits {
+    fn next(&mut self, nodes: &[T], depth: &[usize]) -> Option<usize> {
+        <Fifo as Frontier<T, U>>::next(&mut self.0, nodes, depth)
+    }
+
+    fn trace(&mut self, entry: trace::TraceEntry) {
+        match entry.result {
+            trace::TraceResult::NewNode(_) => self.1.push(1),
+            trace::TraceResult::Duplicate(id) => self.1[id] += 1,
+            trace::TraceResult::Error => {}
+        }
+    }
+}
+
+/// Reports new nodes and edges to an observer while expanding nodes
+/// in the order they were generated.
+struct Observed<'a, O> {
+    fifo: Fifo,
+    observer: &'a mut O,
+    /// A new node that is reported together with the edge to it.
+    new_node: Option<usize>,
+    /// The number of edges.
+    edges: usize,
+}
+
+impl<'a, T, U, O: Observer<T, U>> Frontier<T, U> for Observed<'a, O> {
+    fn next(&mut self, nodes: &[T], depth: &[usize]) -> Option<usize> {
+        <Fifo as Frontier<T, U>>::next(&mut self.fifo, nodes, depth)
+    }
+
+    fn edge(&mut self, nodes: &[T], edges: &mut Vec<([usize; 2], U)>) {
+        if let Some(id) = self.new_node.take() {self.observer.on_node(id, &nodes[id])};
+        self.observer.on_edge(self.edges, &edges[edges.len() - 1]);
+        self.edges += 1;
+    }
+
+    fn trace(&mut self, entry: trace::TraceEntry) {
+        if let trace::TraceResult::NewNode(id) = entry.result {self.new_node = Some(id)};
+    }
+}
+
+/// Stores the state of graph generation before post-processing.
+struct Expansion<T, U, A, E> {
+    graph: Graph<T, U>,
+    attr: Vec<A>,
+    has_edge: EdgeSet,
+    depth: Vec<usize>,
+    parent: Vec<Provenance>,
+    /// The index of the edge from the parent of every generated node.
+    parent_edge: Vec<Option<usize>>,
+    error: Errors<E>,
+}
+
+/// Generates new nodes and edges without attributes.
+fn expand_unit<T, U, F, E>(
+    seed: Graph<T, U>,
+    n: usize,
+    f: F,
+    settings: &GenerateSettings,
+) -> Expansion<T, U, (), E>
+    where T: Eq + Hash + Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          E: From<GenerateError>
+{
+    let seed_attr = vec![(); seed.0.len()];
+    let f = |t: &T, j| if j < n {Some(f(t, j).map(|(t, u)| (Emit::NewNode(t, u), ())))}
+        else {None};
+    expand(seed, seed_attr, f, &mut Fifo(0), settings)
+}
+
+/// Generates new nodes and edges until there are no more nodes to expand,
+/// or until hitting memory limits.
+///
+/// The function `f` is called with `j = 0, 1, 2, ...` for every node,
+/// until it returns `None`.
+fn expand<T, U, A, F, R, E>(
+    seed: Graph<T, U>,
+    attr: Vec<A>,
+    f: F,
+    frontier: &mut R,
+    settings: &GenerateSettings,
+) -> Expansion<T, U, A, E>
+    where T: Eq + Hash + Clone,
+          F: FnMut(&T, usize) -> Option<Result<(Emit<T, U>, A), E>>,
+          R: Frontier<T, U>,
+          E: From<GenerateError>
+{
+    let has: HashMap<T, usize> = HashMap::new();
+    expand_with(seed, attr, f, frontier, has, settings)
+}
+
+/// Finds nodes that were generated before, see `expand_with`.
+trait NodeIndex<T> {
+    /// Returns the index of a node equal to `node`.
+    fn find(&self, node: &T) -> Option<usize>;
+    /// Adds a node, keeping the index of an equal node that was added before.
+    fn add(&mut self, node: &T, id: usize);
+}
+
+impl<T: Eq + Hash + Clone> NodeIndex<T> for HashMap<T, usize> {
+    fn find(&self, node: &T) -> Option<usize> {self.get(node).cloned()}
+
+    fn add(&mut self, node: &T, id: usize) {
+        self.entry(node.clone()).or_insert(id);
+    }
+}
+
+/// Treats every node as new, such that nodes are never compared.
+struct NoIndex;
+
+impl<T> NodeIndex<T> for NoIndex {
+    fn find(&self, _node: &T) -> Option<usize> {None}
+
+    fn add(&mut self, _node: &T, _id: usize) {}
+}
+
+/// Expands nodes the same way as `expand`, using `has` to find existing nodes.
+fn expand_with<T, U, A, F, R, X, E>(
+    (mut nodes, mut edges): Graph<T, U>,
+    mut attr: Vec<A>,
+    mut f: F,
+    frontier: &mut R,
+    mut has: X,
+    settings: &GenerateSettings,
+) -> Expansion<T, U, A, E>
+    where F: FnMut(&T, usize) -> Option<Result<(Emit<T, U>, A), E>>,
+          R: Frontier<T, U>,
+          X: NodeIndex<T>,
+          E: From<GenerateError>
+{
+    use trace::{TraceEntry, TraceResult};
+
+    let mut error: Errors<E> = Errors::new();
+    let mut has_edge = EdgeSet::new(settings.edge_index, settings.max_nodes);
+    let mut depth: Vec<usize> = vec![0; nodes.len()];
+    let mut parent: Vec<Provenance> = vec![None; nodes.len()];
+    let mut parent_edge: Vec<Option<usize>> = vec![None; nodes.len()];
+    for (i, n) in nodes.iter().enumerate() {
+        has.add(n, i);
+    }
+    for edge in &edges {
+        has_edge.insert(edge.0);
+    }
+    let (mut expanded, mut max_depth, mut edge_count) = (0, 0, edges.len());
+    'outer: while let Some(i) = frontier.next(&nodes, &depth) {
+        expanded += 1;
+        let mut j = 0;
+        while let Some(res) = f(&nodes[i], j) {
+            match res {
+                Ok((emit, new_attr)) => {
+                    let mut new_id = None;
+                    let edge = match emit {
+                        Emit::NewNode(new_node, new_edge) => {
+                            let id = if let Some(id) = has.find(&new_node) {
+                                frontier.trace(TraceEntry {
+                                    node: i, step: j, result: TraceResult::Duplicate(id)
+                                });
+                                id
+                            } else {
+                                let id = nodes.len();
+                                has.add(&new_node, id);
+                                nodes.push(new_node);
+                                attr.push(new_attr);
+                                depth.push(depth[i] + 1);
+                                max_depth = max_depth.max(depth[i] + 1);
+                                parent.push(Some((i, j)));
+                                parent_edge.push(None);
+                                frontier.trace(TraceEntry {
+                                    node: i, step: j, result: TraceResult::NewNode(id)
+                                });
+                                new_id = Some(id);
+                                id
+                            };
+                            Some(([i, id], new_edge))
+                        }
+                        Emit::EdgeTo(b, new_edge) => has.find(&b).map(|b| ([i, b], new_edge)),
+                        Emit::EdgeBetween(a, b, new_edge) => match (has.find(&a), has.find(&b)) {
+                            (Some(a), Some(b)) => Some(([a, b], new_edge)),
+                            _ => None,
+                        },
+                    };
+                    if let Some(edge) = edge {
+                        let key = edge.0;
+                        if frontier.add_edge(&mut edges, &has_edge, edge) {
+                            if let Some(id) = new_id {parent_edge[id] = Some(edges.len() - 1)};
+                            has_edge.insert(key);
+                            edge_count += 1;
+                            frontier.edge(&nodes, &mut edges);
+                        }
+                    }
+
+                    let progress = Progress {
+                        nodes: nodes.len(),
+                        edges: edge_count,
+                        position: expanded,
+                        depth: max_depth,
+                    };
+                    if nodes.len() >= settings.max_nodes {
+                        error.limit(GenerateError::MaxNodes(progress));
+                        break 'outer;
+                    } else if edge_count >= settings.max_edges {
+                        error.limit(GenerateError::MaxEdges(progress));
+                        break 'outer;
+                    }
+                }
+                Err(err) => {
+                    frontier.trace(TraceEntry {node: i, step: j, result: TraceResult::Error});
+                    if settings.error_policy.record(&mut error, err, true) {break 'outer};
+                }
+            }
+            j += 1;
+        }
+    }
+    Expansion {graph: (nodes, edges), attr, has_edge, depth, parent, parent_edge, error}
+}
+
+/// Removes nodes that do not pass the filter `g`, which receives the index and the node,
+/// composing edges with `h` such that they no longer refer to removed nodes.
+///
+/// Returns the new graph and a map from old node indices to new ones.
+fn post_process<T, U, G, H, E>(
+    graph: Graph<T, U>,
+    has_edge: &mut EdgeSet,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+    error: &mut Errors<E>,
+) -> (Graph<T, U>, Vec<Option<usize>>)
+    where G: Fn(usize, &T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let (graph, map_nodes, _) = post_process_observe(graph, has_edge, g,
+        |_, _, _, x, y| h(x, y), settings, error, |_, _, _| {});
+    (graph, map_nodes)
+}
+
+/// A post-processed graph with maps from old node and edge indices to new ones.
+type Processed<T, U> = (Graph<T, U>, Vec<Option<usize>>, Vec<Option<usize>>);
+
+/// Post-processes a graph the same way as `post_process`,
+/// calling `on_compose` with the index of every composed edge when it is added,
+/// the indices of the two edges it was composed from, and the composed edge.
+///
+/// The composer `h` also receives the source, removed and target nodes.
+/// Also returns a map from edge indices before removing nodes, including composed edges,
+/// to new edge indices.
+///
+/// The index refers to the list of edges before removing nodes.
+fn post_process_observe<T, U, G, H, O, E>(
+    graph: Graph<T, U>,
+    has_edge: &mut EdgeSet,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+    error: &mut Errors<E>,
+    on_compose: O,
+) -> Processed<T, U>
+    where G: Fn(usize, &T) -> bool,
+          H: Fn(&T, &T, &T, &U, &U) -> Result<U, Option<E>>,
+          O: FnMut(usize, [usize; 2], &([usize; 2], U)),
+          E: From<GenerateError>
+{
+    post_process_walk(graph, has_edge, g, h, settings, error, on_compose, Walk::Directed)
+}
+
+/// How edges are walked when composing them in post-processing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Walk {
+    /// An edge `[a, b]` goes from `a` to `b`.
+    Directed,
+    /// An edge `[a, b]` goes both ways, and composed edges have the smaller index first.
+    Undirected,
+}
+
+impl Walk {
+    /// Returns the ways `[from, to]` to walk an edge.
+    fn dirs(self, [a, b]: [usize; 2]) -> [Option<[usize; 2]>; 2] {
+        match self {
+            Walk::Directed => [Some([a, b]), None],
+            Walk::Undirected => [Some([a, b]), if a == b {None} else {Some([b, a])}],
+        }
+    }
+
+    /// Returns the indices of an edge composed from a walk from `a` to `d`.
+    fn key(self, [a, d]: [usize; 2]) -> [usize; 2] {
+        match self {
+            Walk::Directed => [a, d],
+            Walk::Undirected => [a.min(d), a.max(d)],
+        }
+    }
+}
+
+/// Post-processes a graph the same way as `post_process_observe`,
+/// walking edges as selected by `walk`.
+#[allow(clippy::too_many_arguments, clippy::needless_range_loop)]
+fn post_process_walk<T, U, G, H, O, E>(
+    (nodes, mut edges): Graph<T, U>,
+    has_edge: &mut EdgeSet,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+    error: &mut Errors<E>,
+    mut on_compose: O,
+    walk: Walk,
+) -> Processed<T, U>
+    where G: Fn(usize, &T) -> bool,
+          H: Fn(&T, &T, &T, &U, &U) -> Result<U, Option<E>>,
+          O: FnMut(usize, [usize; 2], &([usize; 2], U)),
+          E: From<GenerateError>
+{
+    let mut removed: HashSet<usize> = HashSet::new();
+    // Hash nodes that do not passes filter.
+    for i in 0..nodes.len() {if !g(i, &nodes[i]) {removed.insert(i);}}
+    let edges_count = edges.len();
+    // The number of edges from before post-processing that every edge spans.
+    let mut lengths: Vec<usize> = vec![1; edges_count];
+    let mut j = 0;
+    // Generate new edges by composing them if they got removed.
+    'outer: while j < edges.len() {
+        let from = walk.dirs(edges[j].0);
+        for &[a, b] in from.iter().flatten() {
+            if !removed.contains(&b) {continue};
+            // Look for all edges that starts with removed node.
+            for k in 0..edges_count {
+                if k == j {continue};
+                let to = walk.dirs(edges[k].0);
+                for &[c, d] in to.iter().flatten() {
+                    let e = walk.key([a, d]);
+                    if c != b || has_edge.contains(&e) ||
+                       lengths[j] + lengths[k] > settings.max_composite_len {continue};
+                    // Compose the two edges into a new one that
+                    // no longer refers to the removed node.
+                    match h(&nodes[a], &nodes[b], &nodes[d], &edges[j].1, &edges[k].1) {
+                        Ok(new_edge) => {
+                            if edges.len() - edges_count >= settings.max_compositions {
+                                let progress = Progress {
+                                    nodes: nodes.len(),
+                                    edges: edges.len(),
+                                    position: j + 1,
+                                    depth: 0,
+                                };
+                                error.limit(GenerateError::MaxCompositions(progress));
+                                break 'outer;
+                            }
+                            edges.push((e, new_edge));
+                            lengths.push(lengths[j] + lengths[k]);
+                            on_compose(edges.len() - 1, [j, k], &edges[edges.len() - 1]);
+                            has_edge.insert(e);
+                        }
+                        Err(None) => {}
+                        Err(Some(err)) => {
+                            if settings.error_policy.record(error, err, false) {break 'outer};
+                        }
+                    }
+                }
+            }
+        }
+        j += 1;
+    }
+    if settings.keep_connected {
+        keep_connected(nodes.len(), &edges, edges_count, walk, &mut removed);
+    }
+
+    let mut new_nodes = vec![];
+    let mut map_nodes: Vec<Option<usize>> = vec![];
+    for (i, node) in nodes.into_iter().enumerate() {
+        if removed.contains(&i) {
+            map_nodes.push(None);
+        } else {
+            let id = new_nodes.len();
+            map_nodes.push(Some(id));
+            new_nodes.push(node);
+        }
+    }
+    // The index of every edge before removing nodes.
+    let mut ids: Vec<usize> = (0..edges.len()).collect();
+    let mut map_edges: Vec<Option<usize>> = vec![None; edges.len()];
+    for j in (0..edges.len()).rev() {
+        let [a, b] = edges[j].0;
+        if let (Some(a), Some(b)) = (map_nodes[a], map_nodes[b]) {
+            edges[j].0 = [a, b];
+        } else {
+            edges.swap_remove(j);
+            ids.swap_remove(j);
+        }
+    }
+    if settings.sort_edges {
+        let mut sorted: Vec<_> = edges.into_iter().zip(ids).collect();
+        sorted.sort_by_key(|(e, _)| e.0);
+        let (e, i) = sorted.into_iter().unzip();
+        edges = e;
+        ids = i;
+    }
+    for (j, &id) in ids.iter().enumerate() {map_edges[id] = Some(j)}
+    ((new_nodes, edges), map_nodes, map_edges)
+}
+
+/// Stops removing nodes that are needed to keep the remaining nodes connected,
+/// see `GenerateSettings::keep_connected`.
+///
+/// The first `edges_count` edges are the edges before composing.
+fn keep_connected<U>(
+    n: usize,
+    edges: &[([usize; 2], U)],
+    edges_count: usize,
+    walk: Walk,
+    removed: &mut HashSet<usize>,
+) {
+    let mut out: Vec<Vec<usize>> = vec![vec![]; n];
+    let mut inc: Vec<Vec<usize>> = vec![vec![]; n];
+    for (e, _) in &edges[..edges_count] {
+        for &[a, b] in walk.dirs(*e).iter().flatten() {
+            out[a].push(b);
+            inc[b].push(a);
+        }
+    }
+    let mut new_out: Vec<Vec<usize>> = vec![vec![]; n];
+    for (e, _) in edges {
+        for &[a, b] in walk.dirs(*e).iter().flatten() {
+            if !removed.contains(&a) && !removed.contains(&b) {new_out[a].push(b)};
+        }
+    }
+    let mut keep: HashSet<usize> = HashSet::new();
+    for a in 0..n {
+        if removed.contains(&a) || !out[a].iter().any(|b| removed.contains(b)) {continue};
+        // Removed nodes reachable through removed nodes, and the remaining nodes they reach.
+        let mut through: HashSet<usize> = HashSet::new();
+        let mut targets: HashSet<usize> = HashSet::new();
+        let mut stack: Vec<usize> = out[a].iter().cloned().filter(|b| removed.contains(b))
+            .collect();
+        through.extend(stack.iter().cloned());
+        while let Some(i) = stack.pop() {
+            for &j in &out[i] {
+                if !removed.contains(&j) {
+                    if j != a {targets.insert(j);}
+                } else if through.insert(j) {
+                    stack.push(j);
+                }
+            }
+        }
+        let mut reached = vec![false; n];
+        reached[a] = true;
+        let mut stack = vec![a];
+        while let Some(i) = stack.pop() {
+            for &j in &new_out[i] {
+                if !reached[j] {
+                    reached[j] = true;
+                    stack.push(j);
+                }
+            }
+        }
+        // Keep the removed nodes on paths to targets that are no longer reached.
+        for d in targets {
+            if reached[d] {continue};
+            let mut stack = vec![d];
+            let mut visited: HashSet<usize> = HashSet::new();
+            while let Some(i) = stack.pop() {
+                for &j in &inc[i] {
+                    if through.contains(&j) && visited.insert(j) {
+                        keep.insert(j);
+                        stack.push(j);
+                    }
+                }
+            }
+        }
+    }
+    for i in keep {removed.remove(&i);}
+}
+
+/// Removes nodes that do not pass the filter `g` from an existing graph,
+/// composing edges with `h` such that they no longer refer to removed nodes.
+///
+/// This is the post-processing step of `gen`, which can be used on any graph,
+/// e.g. imported graphs or graphs that are filtered in several passes.
+/// The limit of composed edges and the edge index are taken from the settings.
+pub fn filter_compose<T, U, G, H, E>(
+    graph: Graph<T, U>,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<Graph<T, U>, E>
+    where G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let mut has_edge = EdgeSet::new(settings.edge_index, settings.max_nodes);
+    for edge in &graph.1 {
+        has_edge.insert(edge.0);
+    }
+    let mut error = Errors::new();
+    let (graph, _) = post_process(graph, &mut has_edge, |_, t| g(t), h, settings, &mut error);
+    into_result(graph, error)
+}
+
+/// A graph with a map from old indices to new ones, see `filter_compose_mapped`.
+pub type Mapped<T, U> = (Graph<T, U>, Vec<Option<usize>>);
+
+/// Removes nodes that do not pass the filter `g` from an existing graph,
+/// the same way as `filter_compose`, and returns a map from old edge indices to new ones.
+///
+/// Removing nodes moves the remaining edges, which changes their indices.
+/// The map is `None` for removed edges.
+/// Composed edges are not in the map, since they did not exist before.
+/// Use `remap` to move a list of edge annotations to the new indices.
+pub fn filter_compose_mapped<T, U, G, H, E>(
+    graph: Graph<T, U>,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<Mapped<T, U>, E>
+    where G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let mut has_edge = EdgeSet::new(settings.edge_index, settings.max_nodes);
+    for edge in &graph.1 {
+        has_edge.insert(edge.0);
+    }
+    let edges = graph.1.len();
+    let mut error = Errors::new();
+    let (graph, _, mut map_edges) = post_process_observe(graph, &mut has_edge, |_, t| g(t),
+        |_, _, _, x, y| h(x, y), settings, &mut error, |_, _, _| {});
+    map_edges.truncate(edges);
+    into_result((graph, map_edges), error)
+}
+
+/// Moves items to new indices, where `map` contains the new index of every item.
+///
+/// Returns a list of length `len`, where indices without an item are `None`.
+/// This is used to keep annotations of nodes or edges aligned after they were moved,
+/// e.g. with the map from `filter_compose_mapped` or `bidir_mapped`.
+pub fn remap<X>(list: Vec<X>, map: &[Option<usize>], len: usize) -> Vec<Option<X>> {
+    let mut res: Vec<Option<X>> = (0..len).map(|_| None).collect();
+    for (x, &id) in list.into_iter().zip(map) {
+        if let Some(id) = id {res[id] = Some(x)};
+    }
+    res
+}
+
+/// Removes node `i` from a graph, composing edges through it with `h`.
+///
+/// For every edge `a -> i` and `i -> b`, a composed edge `a -> b` is added
+/// when there is no edge from `a` to `b`, the same way as in post-processing.
+/// Returns a map from old node indices to new ones, which is `None` for the removed node.
+///
+/// When `h` returns an error, the remaining edges are still composed,
+/// and the first error is returned with the map.
+pub fn remove_node<T, U, H, E>(
+    graph: &mut Graph<T, U>,
+    i: usize,
+    h: H,
+) -> GenResult<Vec<Option<usize>>, E>
+    where H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let settings = GenerateSettings {edge_index: EdgeIndex::Sparse, ..GenerateSettings::new(0, 0)};
+    let mut has_edge = EdgeSet::new(settings.edge_index, settings.max_nodes);
+    for edge in &graph.1 {
+        has_edge.insert(edge.0);
+    }
+    let mut error = Errors::new();
+    let (new_graph, map_nodes) = post_process(std::mem::take(graph), &mut has_edge,
+        |j, _| j != i, h, &settings, &mut error);
+    *graph = new_graph;
+    into_result(map_nodes, error)
+}
+
+/// A compressed graph with the absorbed nodes of every edge, see `compress_chains`.
+pub type Compressed<T, U> = (Graph<T, U>, Vec<Vec<T>>);
+
+/// Replaces chains of nodes with one incoming and one outgoing edge by composed edges.
+///
+/// A node is absorbed when it has exactly one incoming edge and one outgoing edge,
+/// which is not a self-loop.
+/// Every maximal chain of absorbed nodes is replaced by one edge,
+/// composing the edges along the chain with `h` from the start.
+/// Cycles of absorbed nodes keep the first node of the cycle.
+///
+/// Returns the compressed graph and, aligned with its edges, the absorbed nodes
+/// of every edge in the order they appear along the chain.
+///
+/// When `h` fails to compose, the node where it failed is kept,
+/// and the first error reported by `h` is returned.
+pub fn compress_chains<T, U, H, E>(
+    (nodes, edges): Graph<T, U>,
+    h: H,
+) -> GenResult<Compressed<T, U>, E>
+    where H: Fn(&U, &U) -> Result<U, Option<E>>
+{
+    let n = nodes.len();
+    let mut out: Vec<Vec<usize>> = vec![vec![]; n];
+    let mut in_degree = vec![0; n];
+    let mut ends = Vec::with_capacity(edges.len());
+    let mut data = Vec::with_capacity(edges.len());
+    for (k, ([a, b], u)) in edges.into_iter().enumerate() {
+        out[a].push(k);
+        in_degree[b] += 1;
+        ends.push([a, b]);
+        data.push(Some(u));
+    }
+    let absorb = |i: usize| in_degree[i] == 1 && out[i].len() == 1 && ends[out[i][0]][1] != i;
+    let mut keep: Vec<bool> = (0..n).map(|i| !absorb(i)).collect();
+    let mut stack: Vec<usize> = (0..n).rev().filter(|&i| keep[i]).collect();
+    let mut error: Option<E> = None;
+    let mut new_edges: Vec<([usize; 2], U, Vec<usize>)> = vec![];
+    let mut visited = vec![false; n];
+    let mut next_cycle = 0;
+    loop {
+        let a = if let Some(a) = stack.pop() {a}
+            else {
+                // Nodes that are not reached from kept nodes are on cycles of absorbed nodes.
+                while next_cycle < n && (keep[next_cycle] || visited[next_cycle]) {
+                    next_cycle += 1;
+                }
+                if next_cycle == n {break};
+                keep[next_cycle] = true;
+                next_cycle
+            };
+        for &k in &out[a] {
+            let mut acc = data[k].take().unwrap();
+            let mut path = vec![];
+            let mut b = ends[k][1];
+            while !keep[b] {
+                let k2 = out[b][0];
+                match h(&acc, data[k2].as_ref().unwrap()) {
+                    Ok(x) => {
+                        acc = x;
+                        data[k2] = None;
+                        visited[b] = true;
+                        path.push(b);
+                        b = ends[k2][1];
+                    }
+                    Err(err) => {
+                        if let (None, Some(err)) = (&error, err) {error = Some(err)};
+                        keep[b] = true;
+                        stack.push(b);
+                    }
+                }
+            }
+            new_edges.push(([a, b], acc, path));
+        }
+    }
+
+    let mut map_nodes: Vec<Option<usize>> = vec![None; n];
+    let mut absorbed_nodes: Vec<Option<T>> = vec![];
+    let mut new_nodes = vec![];
+    for (i, node) in nodes.into_iter().enumerate() {
+        if keep[i] {
+            map_nodes[i] = Some(new_nodes.len());
+            new_nodes.push(node);
+            absorbed_nodes.push(None);
+        } else {
+            absorbed_nodes.push(Some(node));
+        }
+    }
+    let mut res_edges = Vec::with_capacity(new_edges.len());
+    let mut absorbed = Vec::with_capacity(new_edges.len());
+    for ([a, b], u, path) in new_edges {
+        res_edges.push(([map_nodes[a].unwrap(), map_nodes[b].unwrap()], u));
+        absorbed.push(path.into_iter().map(|i| absorbed_nodes[i].take().unwrap()).collect());
+    }
+    into_result(((new_nodes, res_edges), absorbed), error)
+}
+
+/// Keeps the items of a list aligned with nodes that were not removed.
+fn retain_mapped<X>(list: Vec<X>, map_nodes: &[Option<usize>]) -> Vec<X> {
+    list.into_iter().zip(map_nodes).filter(|(_, id)| id.is_some()).map(|(x, _)| x).collect()
+}
+
+/// Removes a comment starting with `#`, ignoring `#` inside quoted strings.
+///
+/// When `escapes` is `true`, a quote after a backslash does not end a string.
+/// This is shared by the text formats of `rewrite` and `spec`.
+fn strip_comment(line: &str, escapes: bool) -> &str {
+    let mut in_string = false;
+    let mut escape = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escape => escape = false,
+            '\\' if escapes && in_string => escape = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Converts a generated result and the first error into a result.
+fn into_result<X, E, R: Into<Option<E>>>(x: X, error: R) -> GenResult<X, E> {
+    if let Some(err) = error.into() {Err((x, err))} else {Ok(x)}
+}
+
+/// Common queries on graphs.
+///
+/// Every query scans the list of nodes or edges.
+/// For many queries on the same graph, build an `Incidence` index instead.
+pub trait GraphExt<T, U> {
+    /// Returns the targets of edges from node `i`, in the order of the edges.
+    fn successors(&self, i: usize) -> Vec<usize>;
+    /// Returns the sources of edges to node `i`, in the order of the edges.
+    fn predecessors(&self, i: usize) -> Vec<usize>;
+    /// Returns the data of the first edge from `a` to `b`, if any.
+    fn edge_between(&self, a: usize, b: usize) -> Option<&U>;
+    /// Returns the index of the first node equal to `node`, if any.
+    fn node_index(&self, node: &T) -> Option<usize> where T: PartialEq;
+    /// Returns `true` if the graph contains a node equal to `node`.
+    fn contains_node(&self, node: &T) -> bool where T: PartialEq {
+        self.node_index(node).is_some()
+    }
+}
+
+impl<T, U> GraphExt<T, U> for Graph<T, U> {
+    fn successors(&self, i: usize) -> Vec<usize> {
+        self.1.iter().filter(|(e, _)| e[0] == i).map(|(e, _)| e[1]).collect()
+    }
+
+    fn predecessors(&self, i: usize) -> Vec<usize> {
+        self.1.iter().filter(|(e, _)| e[1] == i).map(|(e, _)| e[0]).collect()
+    }
+
+    fn edge_between(&self, a: usize, b: usize) -> Option<&U> {
+        self.1.iter().find(|(e, _)| *e == [a, b]).map(|(_, u)| u)
+    }
+
+    fn node_index(&self, node: &T) -> Option<usize> where T: PartialEq {
+        self.0.iter().position(|t| t == node)
+    }
+}
+
+/// Stores the incoming and outgoing edges of every node in a graph.
+///
+/// Since a graph only stores a list of edges,
+/// algorithms that visit neighbours of nodes use this index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Incidence {
+    /// Outgoing edges of every node, as pairs of target node and edge index.
+    pub out: Vec<Vec<(usize, usize)>>,
+    /// Incoming edges of every node, as pairs of source node and edge index.
+    pub inc: Vec<Vec<(usize, usize)>>,
+}
+
+impl Incidence {
+    /// Creates a new incidence index of a graph.
+    ///
+    /// The edges of every node are stored in the same order as in the graph.
+    pub fn new<T, U>((nodes, edges): &Graph<T, U>) -> Incidence {
+        let mut out = vec![vec![]; nodes.len()];
+        let mut inc = vec![vec![]; nodes.len()];
+        for (k, &([a, b], _)) in edges.iter().enumerate() {
+            out[a].push((b, k));
+            inc[b].push((a, k));
+        }
+        Incidence {out, inc}
+    }
+
+    /// Returns the number of nodes.
+    pub fn len(&self) -> usize {self.out.len()}
+
+    /// Returns `true` if there are no nodes.
+    pub fn is_empty(&self) -> bool {self.out.is_empty()}
+
+    /// Returns the neighbours of every node, ignoring the direction of edges.
+    ///
+    /// The neighbours are sorted and contain no duplicates nor the node itself.
+    pub fn undirected(&self) -> Vec<Vec<usize>> {
+        (0..self.len()).map(|i| {
+            let mut list: Vec<usize> = self.out[i].iter().chain(&self.inc[i])
+                .map(|&(j, _)| j).filter(|&j| j != i).collect();
+            list.sort_unstable();
+            list.dedup();
+            list
+        }).collect()
+    }
+
+    /// Returns the indices of edges from `a` to `b`.
+    pub fn edges_between(&self, a: usize, b: usize) -> impl Iterator<Item = usize> + '_ {
+        self.out[a].iter().filter(move |&&(j, _)| j == b).map(|&(_, k)| k)
+    }
+}
+
+/// Splits a seed into a number of parts that can be generated independently.
+///
+/// Nodes are distributed round-robin between the parts.
+/// An edge is put in the part of its source node.
+/// When the target node belongs to another part, a copy of it is added.
+///
+/// This is used to distribute generation across processes or machines.
+/// Each part is passed as seed to `gen` and the results are joined with `merge`.
+///
+/// Returns no parts when `parts` is zero.
+pub fn partition<T: Clone, U>(
+    (nodes, edges): Graph<T, U>,
+    parts: usize
+) -> Vec<Graph<T, U>> {
+    if parts == 0 {return vec![]};
+
+    let mut res: Vec<Graph<T, U>> = (0..parts).map(|_| (vec![], vec![])).collect();
+    // Maps each node to its index in every part where it occurs.
+    let mut local: Vec<Vec<Option<usize>>> = vec![vec![None; parts]; nodes.len()];
+    for (i, node) in nodes.iter().enumerate() {
+        let part = &mut res[i % parts].0;
+        local[i][i % parts] = Some(part.len());
+        part.push(node.clone());
+    }
+    for ([a, b], edge) in edges {
+        let k = a % parts;
+        let b_local = if let Some(id) = local[b][k] {id}
+        else {
+            let id = res[k].0.len();
+            res[k].0.push(nodes[b].clone());
+            local[b][k] = Some(id);
+            id
+        };
+        let a_local = local[a][k].unwrap();
+        res[k].1.push(([a_local, b_local], edge));
+    }
+    res
+}
+
+/// Merges partial graphs into one graph.
+///
+/// Equal nodes are joined into one node and edges are remapped to the new indices.
+/// An edge is removed when there is an equal edge between the same nodes.
+///
+/// The order of nodes and edges is preserved,
+/// starting with the first graph.
+///
+/// Post-processing is not repeated on the merged graph.
+/// When the partial graphs were generated with the same filter,
+/// the merged graph has no nodes that fails the filter.
+pub fn merge<T, U>(graphs: Vec<Graph<T, U>>) -> Graph<T, U>
+    where T: Eq + Hash + Clone, U: PartialEq
+{
+    let mut nodes: Vec<T> = vec![];
+    let mut edges: Vec<([usize; 2], U)> = vec![];
+    let mut has: HashMap<T, usize> = HashMap::new();
+    let mut has_edge: HashMap<[usize; 2], Vec<usize>> = HashMap::new();
+    for (part_nodes, part_edges) in graphs {
+        let mut map_nodes: Vec<usize> = Vec::with_capacity(part_nodes.len());
+        for node in part_nodes {
+            let id = if let Some(&id) = has.get(&node) {id}
+            else {
+                let id = nodes.len();
+                has.insert(node.clone(), id);
+                nodes.push(node);
+                id
+            };
+            map_nodes.push(id);
+        }
+        for ([a, b], edge) in part_edges {
+            let key = [map_nodes[a], map_nodes[b]];
+            let same = has_edge.entry(key).or_default();
+            if same.iter().any(|&k| edges[k].1 == edge) {continue};
+            same.push(edges.len());
+            edges.push((key, edge));
+        }
+    }
+    (nodes, edges)
+}
+
+/// Normalizes the data of every edge with `f`,
+/// removing edges that become equal to another edge between the same nodes.
+///
+/// This is used to canonicalize composed edges, e.g. by sorting or simplifying them,
+/// such that equal edges can be detected.
+///
+/// The order of the remaining edges is preserved, keeping the first of equal edges.
+pub fn normalize_edges<T, U, F>(graph: &mut Graph<T, U>, f: F)
+    where U: PartialEq, F: Fn(&mut U)
+{
+    let mut has_edge: HashMap<[usize; 2], Vec<usize>> = HashMap::new();
+    let mut edges: Vec<([usize; 2], U)> = Vec::with_capacity(graph.1.len());
+    for (key, mut edge) in graph.1.drain(..) {
+        f(&mut edge);
+        let same = has_edge.entry(key).or_default();
+        if same.iter().any(|&k| edges[k].1 == edge) {continue};
+        same.push(edges.len());
+        edges.push((key, edge));
+    }
+    graph.1 = edges;
+}
+
+/// Filters edges such that only those who are equal in both directions remains.
+///
+/// Removes redundant edges and edges which only exist in one direction.
+///
+/// Does not preserve the order of edges.
+/// The order of the edges is unsorted afterwards.
+///
+/// Assumes that there are maximum two edges between nodes.
+pub fn bidir<T: PartialEq + std::fmt::Debug>(edges: &mut Vec<([usize; 2], T)>) {
+    bidir_mapped(edges);
+}
+
+/// Filters edges the same way as `bidir`, and returns a map from old edge indices to new ones.
+///
+/// The map is `None` for removed edges.
+/// Use `remap` to move a list of edge annotations to the new indices.
+pub fn bidir_mapped<T: PartialEq + std::fmt::Debug>(
+    edges: &mut Vec<([usize; 2], T)>,
+) -> Vec<Option<usize>> {
+    bidir_impl(edges, |a, b| a == b, false, &mut vec![])
+}
+
+/// Filters edges the same way as `bidir`, but pairs edges in opposite directions
+/// when their data is related by `eq`, instead of being equal.
+///
+/// This is useful when an edge and its reverse store inverse operations,
+/// e.g. rotating left and rotating right, while `bidir` only works for
+/// operations that are their own inverse.
+///
+/// `eq` is called with the data of the edge from the smaller node index first,
+/// and this edge is kept.
+/// Every edge without a related edge in the other direction is removed,
+/// so it does not need every edge to have a partner.
+pub fn bidir_by<T, F>(edges: &mut Vec<([usize; 2], T)>, eq: F)
+    where F: Fn(&T, &T) -> bool
+{
+    bidir_impl(edges, eq, true, &mut vec![]);
+}
+
+/// Filters edges the same way as `bidir`, and returns the removed edges
+/// that did not pair up with an equal edge.
+///
+/// The removed edges keep their original direction and order.
+/// Redundant edges that are equal to a kept edge are not returned.
+/// These are useful to inspect, since they might come from operations that are not invertible.
+pub fn bidir_dropped<T: PartialEq + std::fmt::Debug>(
+    edges: &mut Vec<([usize; 2], T)>,
+) -> Vec<([usize; 2], T)> {
+    let mut dropped = vec![];
+    bidir_impl(edges, |a, b| a == b, false, &mut dropped);
+    dropped.sort_by_key(|&(id, _)| id);
+    dropped.into_iter().map(|(_, e)| e).collect()
+}
+
+/// Filters edges the same way as `bidir`, pairing edges when their data is related by `eq`.
+///
+/// When `unpaired` is `true`, every edge without a related edge is removed.
+/// Otherwise, neighbouring edges in sorted order are paired up from the end,
+/// assuming that every edge has a partner, and one edge of a pair is always kept.
+///
+/// Returns a map from old edge indices to new ones,
+/// and pushes removed edges that did not pair up to `dropped`, with their old index.
+#[allow(clippy::needless_range_loop)]
+fn bidir_impl<T, F>(
+    edges: &mut Vec<([usize; 2], T)>,
+    eq: F,
+    unpaired: bool,
+    dropped: &mut Vec<(usize, ([usize; 2], T))>,
+) -> Vec<Option<usize>>
+    where F: Fn(&T, &T) -> bool
+{
+    let mut map_edges: Vec<Option<usize>> = vec![None; edges.len()];
+    if edges.is_empty() {return map_edges};
+
+    let keys: Vec<[usize; 2]> = edges.iter().map(|e| e.0).collect();
+    // Fix indices such that they pair up.
+    for j in 0..edges.len() {
+        let [a, b] = edges[j].0;
+        edges[j].0 = [a.min(b), a.max(b)];
+    }
+    // Sort the index of every edge together with the edge.
+    let mut sorted: Vec<_> = std::mem::take(edges).into_iter().zip(0..).collect();
+    sorted.sort_by_key(|(s, _)| s.0);
+    let (sorted, mut ids): (Vec<_>, Vec<usize>) = sorted.into_iter().unzip();
+    *edges = sorted;
+    let mut drop = |edges: &mut Vec<([usize; 2], T)>, ids: &mut Vec<usize>, j: usize| {
+        let (_, u) = edges.swap_remove(j);
+        let id = ids.swap_remove(j);
+        dropped.push((id, (keys[id], u)));
+    };
+    let mut pair = false;
+    if unpaired {
+        // Every edge is a candidate until paired with the edge before it.
+        for j in (0..edges.len()).rev() {
+            if pair {
+                let k = j + 1;
+                let (x, y) = if keys[ids[j]][0] <= keys[ids[j]][1] {(j, k)} else {(k, j)};
+                if edges[j].0 == edges[k].0 && eq(&edges[x].1, &edges[y].1) {
+                    // Keep the edge from the smaller node index, matching the fixed indices.
+                    edges.swap_remove(y);
+                    ids.swap_remove(y);
+                    pair = false;
+                    continue;
+                }
+                drop(edges, &mut ids, k);
+            }
+            pair = true;
+        }
+        if pair {drop(edges, &mut ids, 0)};
+    } else {
+        for j in (0..edges.len()).rev() {
+            let k = j + 1;
+            if pair {
+                if k >= edges.len() {
+                    drop(edges, &mut ids, j);
+                } else {
+                    let (x, y) = if keys[ids[j]][0] <= keys[ids[j]][1] {(j, k)} else {(k, j)};
+                    if edges[j].0 == edges[k].0 && eq(&edges[x].1, &edges[y].1) {
+                        // Keep the edge from the smaller node index, matching the fixed indices.
+                        edges.swap_remove(y);
+                        ids.swap_remove(y);
+                    } else {
+                        drop(edges, &mut ids, j);
+                    }
+                    pair = false;
+                }
+            } else {
+                pair = true;
+            }
+        }
+    }
+    for (j, &id) in ids.iter().enumerate() {map_edges[id] = Some(j)}
+    map_edges
+}
+
+/// The directions of an undirected edge `[a, b]`, where `a <= b`, see `collapse_bidir`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Orientation {
+    /// The edge only existed from `a` to `b`.
+    Forward,
+    /// The edge only existed from `b` to `a`.
+    Backward,
+    /// The edge existed in both directions.
+    Both,
+}
+
+/// Collapses pairs of edges in opposite directions into single undirected edges.
+///
+/// Every undirected edge has the indices `[a, b]` with `a <= b`,
+/// and stores an `Orientation` telling which directions it came from.
+/// The data of a pair is merged with `merge(forward, backward)`.
+/// Edges without a partner in the other direction are kept as `Forward` or `Backward`,
+/// and self-loops are always `Forward`.
+/// When there are parallel edges, they are paired in their original order.
+///
+/// The edges are sorted by indices afterwards.
+/// Unlike `bidir`, this keeps the data of both directions,
+/// and the edges that only exist in one direction.
+pub fn collapse_bidir<T, F>(
+    edges: Vec<([usize; 2], T)>,
+    merge: F,
+) -> Vec<([usize; 2], (T, Orientation))>
+    where F: Fn(T, T) -> T
+{
+    let mut sorted: Vec<_> = edges.into_iter()
+        .map(|([a, b], u)| ([a.min(b), a.max(b)], a > b, u)).collect();
+    sorted.sort_by_key(|e| e.0);
+    let mut res = Vec::with_capacity(sorted.len());
+    let mut forward = vec![];
+    let mut backward = vec![];
+    let mut iter = sorted.into_iter().peekable();
+    while let Some((key, rev, u)) = iter.next() {
+        if rev {backward.push(u)} else {forward.push(u)}
+        if iter.peek().map(|e| e.0) == Some(key) {continue};
+
+        let m = forward.len().min(backward.len());
+        for (x, y) in forward.drain(..m).zip(backward.drain(..m)) {
+            res.push((key, (merge(x, y), Orientation::Both)));
+        }
+        res.extend(forward.drain(..).map(|x| (key, (x, Orientation::Forward))));
+        res.extend(backward.drain(..).map(|y| (key, (y, Orientation::Backward))));
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bidir_pairs_equal_edges() {
+        let mut edges = vec![([0, 1], 'a'), ([1, 2], 'b'), ([1, 0], 'a'), ([2, 1], 'b')];
+        assert_eq!(bidir_mapped(&mut edges), vec![Some(0), Some(1), None, None]);
+        assert_eq!(edges, vec![([0, 1], 'a'), ([1, 2], 'b')]);
+    }
+
+    #[test]
+    fn bidir_by_removes_unpaired_edges() {
+        // `[0, 1]` has no partner, and the data of `[1, 2]` and `[2, 1]` is not related.
+        let mut edges =
+            vec![([0, 1], 1), ([1, 2], 2), ([2, 1], 2), ([2, 3], 3), ([3, 2], -3)];
+        bidir_by(&mut edges, |a, b| *a == -*b);
+        assert_eq!(edges, vec![([2, 3], 3)]);
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum TestError {
+        Limit,
+        F(u32),
+        H(u32),
+    }
+
+    impl From<GenerateError> for TestError {
+        fn from(_: GenerateError) -> TestError {TestError::Limit}
+    }
+
+    fn gen_errors(
+        policy: ErrorPolicy,
+        f_errors: bool,
+    ) -> (GenResult<Graph<u32, u32>, TestError>, usize) {
+        let (outcome, calls) = gen_outcome_errors(policy, f_errors);
+        (outcome.into_result(), calls)
+    }
+
+    /// Generates `0 -> 1 -> 2 -> 3 -> 4`, where the second step of `1` and `3` fails,
+    /// and removes odd nodes, where composing the `k`-th pair of edges fails with `H(k)`.
+    fn gen_outcome_errors(
+        policy: ErrorPolicy,
+        f_errors: bool,
+    ) -> (GenOutcome<u32, u32, TestError>, usize) {
+        let mut settings = GenerateSettings::new(100, 100);
+        settings.error_policy = policy;
+        let calls = std::cell::Cell::new(0);
+        let f = |&x: &u32, j: usize| {
+            if j == 0 && x < 4 {Ok((x + 1, 1))}
+            else if f_errors && x % 2 == 1 {Err(TestError::F(x))}
+            else {Ok((x, 0))}
+        };
+        let h = |_: &u32, _: &u32| {
+            calls.set(calls.get() + 1);
+            Err(Some(TestError::H(calls.get() as u32 - 1)))
+        };
+        let outcome = gen_outcome((vec![0], vec![]), 2, f, |x| x % 2 == 0, h, &settings);
+        (outcome, calls.get())
+    }
+
+    #[test]
+    fn continue_silently() {
+        let (res, calls) = gen_errors(ErrorPolicy::ContinueSilently, true);
+        assert_eq!(res.unwrap().0, vec![0, 2, 4]);
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn continue_keep_one() {
+        // The last error from `f` wins over errors from `h`.
+        let (res, calls) = gen_errors(ErrorPolicy::ContinueKeepOne, true);
+        let (graph, err) = res.unwrap_err();
+        assert_eq!(graph.0, vec![0, 2, 4]);
+        assert_eq!(err, TestError::F(3));
+        assert_eq!(calls, 2);
+
+        // The first error from `h` wins over later ones.
+        let (res, calls) = gen_errors(ErrorPolicy::ContinueKeepOne, false);
+        assert_eq!(res.unwrap_err().1, TestError::H(0));
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn continue_collecting() {
+        let (outcome, calls) = gen_outcome_errors(ErrorPolicy::ContinueCollecting, true);
+        assert_eq!(outcome.graph.0, vec![0, 2, 4]);
+        assert_eq!(outcome.error, None);
+        assert_eq!(outcome.errors,
+            vec![TestError::F(1), TestError::F(3), TestError::H(0), TestError::H(1)]);
+        assert_eq!(calls, 2);
+        assert!(!outcome.is_ok());
+        assert_eq!(outcome.into_result().unwrap_err().1, TestError::F(1));
+
+        // A limit is kept apart from the collected errors.
+        let mut settings = GenerateSettings::new(3, 100);
+        settings.error_policy = ErrorPolicy::ContinueCollecting;
+        let f = |&x: &u32, j: usize| if j == 0 {Err(TestError::F(x))} else {Ok((x + 1, 0))};
+        let outcome: GenOutcome<_, u32, _> =
+            gen_outcome((vec![0], vec![]), 2, f, |_| true, |_, _| Err(None), &settings);
+        assert_eq!(outcome.error, Some(TestError::Limit));
+        assert_eq!(outcome.errors, vec![TestError::F(0), TestError::F(1)]);
+    }
+
+    #[test]
+    fn abort_on_first() {
+        // Node `1` fails before node `3` is generated, and no edges are composed after that.
+        let (res, calls) = gen_errors(ErrorPolicy::AbortOnFirst, true);
+        let (graph, err) = res.unwrap_err();
+        assert_eq!(graph.0, vec![0, 2]);
+        assert_eq!(err, TestError::F(1));
+        assert_eq!(calls, 1);
+
+        let (res, calls) = gen_errors(ErrorPolicy::AbortOnFirst, false);
+        assert_eq!(res.unwrap_err().1, TestError::H(0));
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn compress_chains_absorbs_corridors() {
+        // `a -> b -> c -> d` with a branch `b -> e`, and a separate cycle `x -> y -> z -> x`.
+        let graph = (vec!['a', 'b', 'c', 'd', 'e', 'x', 'y', 'z'], vec![
+            ([0, 1], "1"), ([1, 2], "2"), ([2, 3], "3"), ([1, 4], "4"),
+            ([5, 6], "5"), ([6, 7], "6"), ([7, 5], "7"),
+        ]);
+        let h = |a: &String, b: &String| Ok::<_, Option<()>>(format!("{}{}", a, b));
+        let graph = (graph.0, graph.1.into_iter().map(|(e, u)| (e, u.to_string())).collect());
+        let ((nodes, edges), absorbed) = compress_chains(graph, h).unwrap();
+        assert_eq!(nodes, vec!['a', 'b', 'd', 'e', 'x']);
+        assert_eq!(edges, vec![([0, 1], "1".into()), ([1, 2], "23".into()),
+            ([1, 3], "4".into()), ([4, 4], "567".into())]);
+        assert_eq!(absorbed, vec![vec![], vec!['c'], vec![], vec!['y', 'z']]);
+
+        // Nodes where `h` fails are kept.
+        let graph = (vec![0, 1, 2, 3], vec![([0, 1], 1), ([1, 2], 2), ([2, 3], 3)]);
+        let h = |a: &u32, b: &u32| if *b == 3 {Err(Some("fail"))} else {Ok(a + b)};
+        let (((nodes, edges), absorbed), err) = compress_chains(graph, h).unwrap_err();
+        assert_eq!(nodes, vec![0, 2, 3]);
+        assert_eq!(edges, vec![([0, 1], 3), ([1, 2], 3)]);
+        assert_eq!(absorbed, vec![vec![1], vec![]]);
+        assert_eq!(err, "fail");
+    }
+
+    #[test]
+    fn keep_connected_keeps_needed_nodes() {
+        // `0 -> 1 -> 2` and `0 -> 3 -> 2`, where only odd nodes pass the filter.
+        let graph = (vec![0, 1, 2, 3], vec![([0, 1], 1), ([1, 2], 2), ([0, 3], 3), ([3, 2], 4)]);
+        let mut settings = GenerateSettings::new(100, 100);
+        let refuse = |_: &u32, _: &u32| Err::<u32, Option<()>>(None);
+        let odd = |x: &u32| x % 2 == 1;
+        let res = filter_compose(graph.clone(), odd, refuse, &settings).unwrap();
+        assert_eq!(res, (vec![1, 3], vec![]));
+
+        settings.keep_connected = true;
+        let res = filter_compose(graph.clone(), |x| *x != 2, refuse, &settings).unwrap();
+        assert_eq!(res.0, vec![0, 1, 3]);
+        let res = filter_compose(graph.clone(), |x| *x == 0 || *x == 2, refuse, &settings)
+            .unwrap();
+        assert_eq!(res, graph);
+
+        // Only nodes on paths that are no longer connected are kept.
+        let via_one = |a: &u32, b: &u32| if *a == 1 {Ok(a + b)} else {Err(None::<()>)};
+        let res = filter_compose(graph.clone(), |x| *x == 0 || *x == 2, via_one, &settings)
+            .unwrap();
+        assert_eq!(res, (vec![0, 2], vec![([0, 1], 3)]));
+        let via_three = |a: &u32, b: &u32| if *a == 3 {Ok(a + b)} else {Err(None::<()>)};
+        let res = filter_compose(graph, |x| *x == 0 || *x == 2, via_three, &settings).unwrap();
+        assert_eq!(res, (vec![0, 2], vec![([0, 1], 7)]));
     }
 }
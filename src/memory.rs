@@ -0,0 +1,142 @@
+//! Estimating memory usage of graphs and graph generation.
+//!
+//! Estimates count the memory of the lists of nodes and edges,
+//! and of the indices used while generating to detect duplicate nodes and edges.
+//! Heap memory owned by nodes is measured by a user function,
+//! since it depends on the node type.
+//!
+//! After generating, `compact` frees unused capacity
+//! and `intern_edges` shares equal edge data.
+
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::mem::size_of;
+use std::rc::Rc;
+
+use crate::{Graph, Observer, DENSE_MAX_NODES};
+
+/// Stores an estimate of memory usage in bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Footprint {
+    /// The memory of the list of nodes, including heap memory owned by nodes.
+    pub nodes: usize,
+    /// The memory of the list of edges, without heap memory owned by edge data.
+    pub edges: usize,
+    /// The memory of the indices used while generating.
+    pub index: usize,
+}
+
+impl Footprint {
+    /// Returns the total number of bytes.
+    pub fn total(&self) -> usize {self.nodes + self.edges + self.index}
+}
+
+/// Estimates the memory of a hash table with `len` entries of `entry` bytes.
+///
+/// Hash tables keep at least one eighth of their slots free,
+/// and use one control byte per slot.
+fn hash_table(len: usize, entry: usize) -> usize {
+    len * 8 / 7 * (entry + 1)
+}
+
+/// Estimates the memory used to generate a graph.
+///
+/// The function `node_size` returns the heap memory owned by a node,
+/// e.g. `|s: &String| s.capacity()`, or zero for nodes without heap memory.
+/// Nodes are stored twice while generating, once in the list of nodes and once
+/// in the index that detects duplicates.
+///
+/// The edge index is assumed to be a bitset when there are at most `DENSE_MAX_NODES` nodes,
+/// and a hash set otherwise, the same way as `EdgeIndex::Auto`.
+pub fn memory_footprint<T, U, S>(graph: &Graph<T, U>, node_size: S) -> Footprint
+    where S: Fn(&T) -> usize
+{
+    let heap = graph.0.iter().map(&node_size).sum();
+    estimate::<T, U>(graph.0.len(), graph.1.len(), heap)
+}
+
+/// Estimates the memory of `n` nodes owning `heap` bytes and `m` edges.
+fn estimate<T, U>(n: usize, m: usize, heap: usize) -> Footprint {
+    let edge_index = if n <= DENSE_MAX_NODES {n * n.div_ceil(64) * size_of::<u64>()}
+        else {hash_table(m, size_of::<[usize; 2]>())};
+    Footprint {
+        nodes: n * size_of::<T>() + heap,
+        edges: m * size_of::<([usize; 2], U)>(),
+        index: hash_table(n, size_of::<(T, usize)>()) + heap + edge_index,
+    }
+}
+
+/// Keeps an estimate of memory usage up to date while generating a graph.
+///
+/// This is an observer for `gen_observe`, which can be read at any time,
+/// e.g. from another observer or after generating.
+/// The estimate is the same as from `memory_footprint` on the graph generated so far.
+pub struct MemoryObserver<S> {
+    /// Returns the heap memory owned by a node.
+    pub node_size: S,
+    /// The number of nodes.
+    pub node_count: usize,
+    /// The number of edges.
+    pub edge_count: usize,
+    /// The heap memory owned by nodes.
+    pub heap: usize,
+}
+
+impl<S> MemoryObserver<S> {
+    /// Creates a new memory observer.
+    pub fn new(node_size: S) -> MemoryObserver<S> {
+        MemoryObserver {node_size, node_count: 0, edge_count: 0, heap: 0}
+    }
+
+    /// Returns the current estimate, for nodes of type `T` and edges of type `U`.
+    pub fn footprint<T, U>(&self) -> Footprint {
+        estimate::<T, U>(self.node_count, self.edge_count, self.heap)
+    }
+}
+
+impl<T, U, S> Observer<T, U> for MemoryObserver<S>
+    where S: Fn(&T) -> usize
+{
+    fn on_node(&mut self, _i: usize, node: &T) {
+        self.node_count += 1;
+        self.heap += (self.node_size)(node);
+    }
+
+    fn on_edge(&mut self, _k: usize, _edge: &([usize; 2], U)) {
+        self.edge_count += 1;
+    }
+}
+
+/// Shrinks the capacity of the lists of nodes and edges to their length.
+///
+/// Returns the number of bytes that were freed.
+/// Heap memory owned by nodes and edge data is not changed.
+pub fn compact<T, U>(graph: &mut Graph<T, U>) -> usize {
+    let before = graph.0.capacity() * size_of::<T>() +
+        graph.1.capacity() * size_of::<([usize; 2], U)>();
+    graph.0.shrink_to_fit();
+    graph.1.shrink_to_fit();
+    let after = graph.0.capacity() * size_of::<T>() +
+        graph.1.capacity() * size_of::<([usize; 2], U)>();
+    before - after
+}
+
+/// Replaces edge data by shared references, such that equal edge data is stored once.
+///
+/// This saves memory when many edges have equal data with heap memory,
+/// e.g. composed sequences of operations.
+pub fn intern_edges<T, U>((nodes, edges): Graph<T, U>) -> Graph<T, Rc<U>>
+    where U: Eq + Hash
+{
+    let mut shared: HashSet<Rc<U>> = HashSet::new();
+    let edges = edges.into_iter().map(|(e, u)| {
+        let u = if let Some(x) = shared.get(&u) {x.clone()}
+            else {
+                let x = Rc::new(u);
+                shared.insert(x.clone());
+                x
+            };
+        (e, u)
+    }).collect();
+    (nodes, edges)
+}
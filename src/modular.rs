@@ -0,0 +1,113 @@
+//! Operation graphs of modular arithmetic groups `Z/nZ` and products of them.
+//!
+//! Since these groups are well understood, the number of nodes and edges is known,
+//! which makes them useful to check generation algorithms.
+//! Every node has exactly one outgoing edge per generator,
+//! so the number of edges is the number of nodes times the number of generators.
+
+use crate::{gen, GenResult, GenerateError, GenerateSettings, Graph};
+
+/// Generates the operation graph of `Z/nZ` starting from `0`,
+/// where an edge from `x` to `(x + gens[k]) % n` is labeled by the generator index `k`.
+///
+/// The number of nodes is `n / gcd(n, gens[0], gens[1], ...)`, see `cyclic_order`.
+///
+/// Panics if `n` is zero.
+pub fn gen_cyclic(
+    n: usize,
+    gens: &[usize],
+    settings: &GenerateSettings,
+) -> GenResult<Graph<usize, usize>, GenerateError> {
+    assert!(n > 0);
+    gen((vec![0], vec![]), gens.len(),
+        |&x, k| Ok(((x + gens[k] % n) % n, k)),
+        |_| true,
+        |_, _| Err(None),
+        settings)
+}
+
+/// Generates the operation graph of `Z/n_0Z × Z/n_1Z × ...` starting from zero,
+/// where `moduli` is `[n_0, n_1, ...]`.
+///
+/// Every generator is a list of increments, one per factor.
+/// An edge from `x` to `x + gens[k]` is labeled by the generator index `k`.
+///
+/// Panics if a modulus is zero or a generator has a different length than `moduli`.
+pub fn gen_product(
+    moduli: &[usize],
+    gens: &[Vec<usize>],
+    settings: &GenerateSettings,
+) -> GenResult<Graph<Vec<usize>, usize>, GenerateError> {
+    assert!(moduli.iter().all(|&n| n > 0));
+    assert!(gens.iter().all(|g| g.len() == moduli.len()));
+    gen((vec![vec![0; moduli.len()]], vec![]), gens.len(),
+        |x, k| {
+            let y = x.iter().zip(&gens[k]).zip(moduli)
+                .map(|((&a, &b), &n)| (a + b % n) % n).collect();
+            Ok((y, k))
+        },
+        |_| true,
+        |_, _| Err(None),
+        settings)
+}
+
+/// Returns the standard generators of a product, one unit increment per factor.
+///
+/// The operation graph of the standard generators contains every element,
+/// which is the product of the moduli.
+pub fn standard_generators(moduli: &[usize]) -> Vec<Vec<usize>> {
+    (0..moduli.len()).map(|i| {
+        let mut g = vec![0; moduli.len()];
+        g[i] = 1;
+        g
+    }).collect()
+}
+
+/// Returns the number of elements in the subgroup of `Z/nZ` generated by `gens`.
+///
+/// This is `n / gcd(n, gens[0], gens[1], ...)`, the number of nodes from `gen_cyclic`.
+pub fn cyclic_order(n: usize, gens: &[usize]) -> usize {
+    fn gcd(a: usize, b: usize) -> usize {if b == 0 {a} else {gcd(b, a % b)}}
+    n / gens.iter().fold(n, |acc, &g| gcd(acc, g % n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cyclic_counts() {
+        let settings = GenerateSettings::new(1000, 1000);
+        for n in 1..13 {
+            for gens in &[vec![1], vec![2], vec![3, 6], vec![4, 6], vec![0], vec![n + 2, 5]] {
+                let (nodes, edges) = gen_cyclic(n, gens, &settings).unwrap();
+                let order = cyclic_order(n, gens);
+                assert_eq!(nodes.len(), order);
+                assert_eq!(edges.len(), order * gens.len());
+                let mut sorted = nodes.clone();
+                sorted.sort();
+                sorted.dedup();
+                assert_eq!(sorted.len(), nodes.len());
+                assert!(edges.iter().all(|&([a, b], k)| nodes[b] == (nodes[a] + gens[k]) % n));
+            }
+        }
+        assert_eq!(cyclic_order(12, &[8]), 3);
+        assert_eq!(cyclic_order(12, &[8, 6]), 6);
+        assert_eq!(cyclic_order(7, &[0]), 1);
+    }
+
+    #[test]
+    fn product_counts() {
+        let settings = GenerateSettings::new(1000, 1000);
+        for moduli in &[vec![1], vec![5], vec![2, 3], vec![4, 6], vec![2, 3, 4]] {
+            let gens = standard_generators(moduli);
+            let (nodes, edges) = gen_product(moduli, &gens, &settings).unwrap();
+            let order: usize = moduli.iter().product();
+            assert_eq!(nodes.len(), order);
+            assert_eq!(edges.len(), order * moduli.len());
+        }
+        let (nodes, edges) = gen_product(&[4, 6], &[vec![1, 1]], &settings).unwrap();
+        assert_eq!(nodes.len(), 12);
+        assert_eq!(edges.len(), 12);
+    }
+}
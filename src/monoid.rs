@@ -0,0 +1,95 @@
+//! Element graphs of monoids given by generators and relations.
+//!
+//! A word is a list of generator indices, where the empty word is the identity.
+//! Elements are nodes and applying a generator on the right is an edge.
+//! Equality of elements is decided by a normalizer,
+//! which maps every word to a canonical word for its element.
+
+use crate::{gen, GenResult, GenerateError, GenerateSettings, Graph};
+
+/// Stores a monoid presentation with generators `0..generators`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Presentation {
+    /// The number of generators.
+    pub generators: usize,
+    /// Relations `lhs = rhs` between words.
+    pub relations: Vec<(Vec<usize>, Vec<usize>)>,
+}
+
+/// Returns `true` if `a` is smaller than `b` in shortlex order.
+fn shortlex_less(a: &[usize], b: &[usize]) -> bool {
+    (a.len(), a) < (b.len(), b)
+}
+
+impl Presentation {
+    /// Creates a new presentation.
+    pub fn new(generators: usize, relations: Vec<(Vec<usize>, Vec<usize>)>) -> Presentation {
+        Presentation {generators, relations}
+    }
+
+    /// Returns the relations as rewrite rules that make words smaller in shortlex order.
+    pub fn rules(&self) -> Vec<(Vec<usize>, Vec<usize>)> {
+        self.relations.iter().filter(|(a, b)| a != b).map(|(a, b)| {
+            if shortlex_less(a, b) {(b.clone(), a.clone())} else {(a.clone(), b.clone())}
+        }).collect()
+    }
+
+    /// Normalizes a word by rewriting with the rules from `rules` until no rule applies.
+    ///
+    /// Since every rule makes the word smaller in shortlex order, this terminates.
+    /// The result is a canonical word for its element when the rules are confluent,
+    /// e.g. after Knuth-Bendix completion.
+    /// Otherwise, equal elements might be generated as different nodes,
+    /// which is solved by using a custom normalizer with `gen_elements`.
+    pub fn normalize(&self, word: &[usize]) -> Vec<usize> {
+        rewrite(&self.rules(), word)
+    }
+
+    /// Generates the element graph, normalizing with `normalize`.
+    pub fn gen(
+        &self,
+        settings: &GenerateSettings,
+    ) -> GenResult<Graph<Vec<usize>, usize>, GenerateError> {
+        let rules = self.rules();
+        gen_elements(self.generators, |w| rewrite(&rules, w), settings)
+    }
+}
+
+/// Rewrites a word with rules until no rule applies.
+fn rewrite(rules: &[(Vec<usize>, Vec<usize>)], word: &[usize]) -> Vec<usize> {
+    let mut word = word.to_vec();
+    'outer: loop {
+        for (lhs, rhs) in rules {
+            if lhs.is_empty() {continue};
+            if let Some(pos) = word.windows(lhs.len()).position(|w| w == &lhs[..]) {
+                word.splice(pos..pos + lhs.len(), rhs.iter().cloned());
+                continue 'outer;
+            }
+        }
+        return word;
+    }
+}
+
+/// Generates the element graph of a monoid with generators `0..generators`,
+/// where `normalize` maps a word to the canonical word for its element.
+///
+/// Nodes are canonical words, starting with the identity.
+/// An edge from `x` to `normalize(x ++ [k])` is labeled by the generator index `k`.
+pub fn gen_elements<N>(
+    generators: usize,
+    normalize: N,
+    settings: &GenerateSettings,
+) -> GenResult<Graph<Vec<usize>, usize>, GenerateError>
+    where N: Fn(&[usize]) -> Vec<usize>
+{
+    let seed = (vec![normalize(&[])], vec![]);
+    gen(seed, generators,
+        |x, k| {
+            let mut word = x.clone();
+            word.push(k);
+            Ok((normalize(&word), k))
+        },
+        |_| true,
+        |_, _| Err(None),
+        settings)
+}
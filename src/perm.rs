@@ -0,0 +1,166 @@
+//! Permutations and the state graphs of permutation groups and puzzles.
+//!
+//! A permutation `p` moves the item at position `i` to position `p.apply(i)`.
+//! Permutations compose left to right, such that `a.then(&b)` moves items by `a` first.
+
+use crate::{gen, GenResult, GenerateError, GenerateSettings, Graph};
+
+/// Stores a permutation of `0..n` as the image of every position.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Perm(Vec<usize>);
+
+impl Perm {
+    /// Creates a permutation from the image of every position.
+    ///
+    /// Returns `None` if the list is not a permutation of `0..n`.
+    pub fn new(image: Vec<usize>) -> Option<Perm> {
+        let mut seen = vec![false; image.len()];
+        for &i in &image {
+            if i >= image.len() || seen[i] {return None};
+            seen[i] = true;
+        }
+        Some(Perm(image))
+    }
+
+    /// Creates the identity permutation of `0..n`.
+    pub fn identity(n: usize) -> Perm {
+        Perm((0..n).collect())
+    }
+
+    /// Creates a permutation of `0..n` from disjoint cycles,
+    /// where a cycle `[a, b, c]` moves `a` to `b`, `b` to `c` and `c` to `a`.
+    ///
+    /// Returns `None` if a position is out of range or appears more than once.
+    pub fn from_cycles(n: usize, cycles: &[&[usize]]) -> Option<Perm> {
+        let mut image: Vec<usize> = (0..n).collect();
+        let mut seen = vec![false; n];
+        for cycle in cycles {
+            for (k, &i) in cycle.iter().enumerate() {
+                if i >= n || seen[i] {return None};
+                seen[i] = true;
+                image[i] = cycle[(k + 1) % cycle.len()];
+            }
+        }
+        Some(Perm(image))
+    }
+
+    /// Returns the number of positions.
+    pub fn len(&self) -> usize {self.0.len()}
+
+    /// Returns `true` if the permutation has no positions.
+    pub fn is_empty(&self) -> bool {self.0.is_empty()}
+
+    /// Returns the image of every position.
+    pub fn image(&self) -> &[usize] {&self.0}
+
+    /// Returns where position `i` is moved.
+    pub fn apply(&self, i: usize) -> usize {self.0[i]}
+
+    /// Returns `true` if no position is moved.
+    pub fn is_identity(&self) -> bool {
+        self.0.iter().enumerate().all(|(i, &j)| i == j)
+    }
+
+    /// Returns the permutation that applies `self` first, then `other`.
+    ///
+    /// Panics if the permutations have different lengths.
+    pub fn then(&self, other: &Perm) -> Perm {
+        assert_eq!(self.len(), other.len());
+        Perm(self.0.iter().map(|&i| other.0[i]).collect())
+    }
+
+    /// Returns the inverse permutation.
+    pub fn inverse(&self) -> Perm {
+        let mut image = vec![0; self.len()];
+        for (i, &j) in self.0.iter().enumerate() {
+            image[j] = i;
+        }
+        Perm(image)
+    }
+
+    /// Returns the cycles of length two or more, each starting with its smallest position.
+    pub fn cycles(&self) -> Vec<Vec<usize>> {
+        let mut seen = vec![false; self.len()];
+        let mut res = vec![];
+        for i in 0..self.len() {
+            if seen[i] || self.0[i] == i {continue};
+            let mut cycle = vec![];
+            let mut j = i;
+            while !seen[j] {
+                seen[j] = true;
+                cycle.push(j);
+                j = self.0[j];
+            }
+            res.push(cycle);
+        }
+        res
+    }
+
+    /// Returns the order, which is the smallest `k > 0` such that applying `k` times is identity.
+    pub fn order(&self) -> usize {
+        fn gcd(a: usize, b: usize) -> usize {if b == 0 {a} else {gcd(b, a % b)}}
+        self.cycles().iter().fold(1, |acc, c| acc / gcd(acc, c.len()) * c.len())
+    }
+
+    /// Returns `true` if the permutation is a product of an even number of transpositions.
+    pub fn is_even(&self) -> bool {
+        self.cycles().iter().map(|c| c.len() - 1).sum::<usize>() % 2 == 0
+    }
+
+    /// Moves the items of a state, such that item `i` ends up at `self.apply(i)`.
+    ///
+    /// Panics if the state has a different length than the permutation.
+    pub fn apply_to<T: Clone>(&self, state: &[T]) -> Vec<T> {
+        assert_eq!(self.len(), state.len());
+        let mut res = state.to_vec();
+        for (i, item) in state.iter().enumerate() {
+            res[self.0[i]] = item.clone();
+        }
+        res
+    }
+}
+
+/// Generates the Cayley graph of the group generated by permutations.
+///
+/// Nodes are group elements, starting with the identity.
+/// An edge from `x` to `x.then(&gens[k])` is labeled by the generator index `k`.
+///
+/// Panics if `gens` is empty or the generators have different lengths.
+pub fn gen_cayley(
+    gens: &[Perm],
+    settings: &GenerateSettings,
+) -> GenResult<Graph<Perm, usize>, GenerateError> {
+    gen_orbit(Perm::identity(gens[0].len()), gens, |x, p| x.then(p), settings)
+}
+
+/// Generates the state graph of a permutation puzzle.
+///
+/// Nodes are states reachable from `start` by moving items with the generators.
+/// An edge is labeled by the generator index.
+///
+/// Panics if a generator has a different length than the state.
+pub fn gen_puzzle<T>(
+    start: Vec<T>,
+    gens: &[Perm],
+    settings: &GenerateSettings,
+) -> GenResult<Graph<Vec<T>, usize>, GenerateError>
+    where T: Eq + std::hash::Hash + Clone
+{
+    gen_orbit(start, gens, |x, p| p.apply_to(x), settings)
+}
+
+fn gen_orbit<T, A>(
+    start: T,
+    gens: &[Perm],
+    act: A,
+    settings: &GenerateSettings,
+) -> GenResult<Graph<T, usize>, GenerateError>
+    where T: Eq + std::hash::Hash + Clone,
+          A: Fn(&T, &Perm) -> T
+{
+    gen((vec![start], vec![]), gens.len(),
+        |x, k| Ok((act(x, &gens[k]), k)),
+        |_| true,
+        |_, _| Err(None),
+        settings)
+}
@@ -0,0 +1,122 @@
+//! Partially ordered sets and lattices.
+//!
+//! An order relation is a graph where an edge from `a` to `b` means `a ≤ b`.
+//! Generating an order relation often produces edges implied by transitivity,
+//! which are removed by Hasse reduction to get the covering relation.
+
+use std::hash::Hash;
+
+use crate::algo::{layers, transitive_reduction};
+use crate::{gen, GenResult, GenerateError, GenerateSettings, Graph};
+
+/// Reduces an order relation to its Hasse diagram.
+///
+/// Removes edges implied by transitivity, self-loops and parallel edges,
+/// see `algo::transitive_reduction`.
+/// Returns `None` if the relation has a cycle other than self-loops,
+/// which means it is not antisymmetric.
+pub fn hasse<T: Clone, U: Clone>(graph: &Graph<T, U>) -> Option<Graph<T, U>> {
+    let keep = transitive_reduction(graph)?;
+    Some((graph.0.clone(), keep.into_iter().map(|k| graph.1[k].clone()).collect()))
+}
+
+/// Generates an order relation and reduces it to its Hasse diagram.
+///
+/// The function `f` returns a node that is greater than or equal to the given node,
+/// with the same conventions as in `gen`.
+/// Since all nodes are kept, no edges are composed.
+///
+/// Returns `None` inside the result if the relation has a cycle other than self-loops.
+pub fn gen_hasse<T, U, F, E>(
+    seed: Graph<T, U>,
+    n: usize,
+    f: F,
+    settings: &GenerateSettings,
+) -> GenResult<Option<Graph<T, U>>, E>
+    where T: Eq + Hash + Clone,
+          U: Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          E: From<GenerateError>
+{
+    match gen(seed, n, f, |_| true, |_, _| Err(None), settings) {
+        Ok(graph) => Ok(hasse(&graph)),
+        Err((graph, err)) => Err((hasse(&graph), err)),
+    }
+}
+
+/// Stores the order relation of an acyclic graph, for comparing nodes.
+///
+/// The relation is reflexive, such that every node is less or equal to itself.
+/// Stores the set of greater or equal nodes for every node as a bitset,
+/// which uses `n * n / 8` bytes for `n` nodes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Order {
+    up: Vec<Vec<u64>>,
+}
+
+impl Order {
+    /// Creates the order relation of a graph,
+    /// or returns `None` if the graph has a cycle other than self-loops.
+    pub fn new<T, U>(graph: &Graph<T, U>) -> Option<Order> {
+        let n = graph.0.len();
+        let loopless: Graph<(), ()> = (vec![(); n],
+            graph.1.iter().filter(|(e, _)| e[0] != e[1]).map(|&(e, _)| (e, ())).collect());
+        let (_, groups) = layers(&loopless)?;
+        let mut out: Vec<Vec<usize>> = vec![vec![]; n];
+        for &([a, b], _) in &loopless.1 {out[a].push(b)}
+        let mut up: Vec<Vec<u64>> = vec![vec![]; n];
+        for group in groups.iter().rev() {
+            for &a in group {
+                let mut r = vec![0u64; n.div_ceil(64)];
+                r[a / 64] |= 1 << (a % 64);
+                for &b in &out[a] {
+                    for (x, y) in r.iter_mut().zip(&up[b]) {*x |= y}
+                }
+                up[a] = r;
+            }
+        }
+        Some(Order {up})
+    }
+
+    /// Returns the number of nodes.
+    pub fn len(&self) -> usize {self.up.len()}
+
+    /// Returns `true` if there are no nodes.
+    pub fn is_empty(&self) -> bool {self.up.is_empty()}
+
+    /// Returns `true` if `a ≤ b`.
+    pub fn leq(&self, a: usize, b: usize) -> bool {
+        (self.up[a][b / 64] >> (b % 64)) & 1 == 1
+    }
+
+    /// Returns the nodes that are greater or equal to both `a` and `b`.
+    pub fn upper_bounds(&self, a: usize, b: usize) -> Vec<usize> {
+        (0..self.len()).filter(|&c| self.leq(a, c) && self.leq(b, c)).collect()
+    }
+
+    /// Returns the nodes that are less or equal to both `a` and `b`.
+    pub fn lower_bounds(&self, a: usize, b: usize) -> Vec<usize> {
+        (0..self.len()).filter(|&c| self.leq(c, a) && self.leq(c, b)).collect()
+    }
+
+    /// Returns the least upper bound of `a` and `b`, if any.
+    pub fn join(&self, a: usize, b: usize) -> Option<usize> {
+        let bounds = self.upper_bounds(a, b);
+        bounds.iter().cloned().find(|&c| bounds.iter().all(|&d| self.leq(c, d)))
+    }
+
+    /// Returns the greatest lower bound of `a` and `b`, if any.
+    pub fn meet(&self, a: usize, b: usize) -> Option<usize> {
+        let bounds = self.lower_bounds(a, b);
+        bounds.iter().cloned().find(|&c| bounds.iter().all(|&d| self.leq(d, c)))
+    }
+
+    /// Returns `true` if every pair of nodes has a join and a meet.
+    ///
+    /// This checks all pairs of nodes, so it is slow for large orders.
+    pub fn is_lattice(&self) -> bool {
+        (0..self.len()).all(|a| (a + 1..self.len()).all(|b| {
+            self.join(a, b).is_some() && self.meet(a, b).is_some()
+        }))
+    }
+}
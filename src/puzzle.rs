@@ -0,0 +1,148 @@
+//! Puzzles with states and reversible moves.
+//!
+//! A puzzle has a fixed list of moves, indexed `0..moves()`,
+//! where every move has an inverse move that undoes it.
+//! Edges are sequences of moves, so composing edges joins the sequences.
+//!
+//! For example, sliding puzzles and Rubik-style puzzles,
+//! where the state graph is symmetric and compositional.
+
+use std::hash::Hash;
+
+use crate::search::gen_until;
+use crate::{gen_from_iter, GenResult, GenerateError, GenerateSettings, Graph};
+
+/// A sequence of moves, used as edges.
+pub type Moves = Vec<usize>;
+
+/// Implemented by puzzles with reversible moves.
+pub trait Puzzle {
+    /// The state of the puzzle.
+    type State: Eq + Hash + Clone;
+
+    /// Returns the number of moves.
+    fn moves(&self) -> usize;
+
+    /// Applies move `mv` to a state, or returns `None` if the move is not possible.
+    fn apply(&self, state: &Self::State, mv: usize) -> Option<Self::State>;
+
+    /// Returns the move that undoes move `mv`.
+    fn inverse(&self, mv: usize) -> usize;
+}
+
+/// Returns the function `f` for `gen` and the search functions,
+/// with `puzzle.moves()` operations.
+///
+/// Returns `Err(())` when a move is not possible.
+pub fn f<P: Puzzle>(
+    puzzle: &P,
+) -> impl Fn(&P::State, usize) -> Result<(P::State, Moves), ()> + '_ {
+    move |state, mv| puzzle.apply(state, mv).map(|s| (s, vec![mv])).ok_or(())
+}
+
+/// Returns the composer `h` for `gen`, which joins sequences of moves.
+pub fn h<E>() -> impl Fn(&Moves, &Moves) -> Result<Moves, Option<E>> {
+    |a, b| Ok(a.iter().chain(b).cloned().collect())
+}
+
+/// Returns the sequence of moves that undoes a sequence of moves.
+pub fn invert<P: Puzzle>(puzzle: &P, moves: &[usize]) -> Moves {
+    moves.iter().rev().map(|&mv| puzzle.inverse(mv)).collect()
+}
+
+/// Applies a sequence of moves to a state, or returns `None` if a move is not possible.
+pub fn replay<P: Puzzle>(puzzle: &P, state: &P::State, moves: &[usize]) -> Option<P::State> {
+    let mut state = state.clone();
+    for &mv in moves {
+        state = puzzle.apply(&state, mv)?;
+    }
+    Some(state)
+}
+
+/// Generates the states reachable from `start`,
+/// keeping states that satisfy `g` and composing moves through removed states.
+///
+/// Unlike `gen` with `f`, moves that are not possible are not reported as errors.
+pub fn gen_states<P, G>(
+    puzzle: &P,
+    start: P::State,
+    g: G,
+    settings: &GenerateSettings,
+) -> GenResult<Graph<P::State, Moves>, GenerateError>
+    where P: Puzzle, G: Fn(&P::State) -> bool
+{
+    let f = |state: &P::State| (0..puzzle.moves())
+        .filter_map(|mv| puzzle.apply(state, mv).map(|s| Ok((s, vec![mv]))))
+        .collect::<Vec<_>>();
+    gen_from_iter((vec![start], vec![]), f, g, h(), settings)
+}
+
+/// Finds a shortest sequence of moves from `start` to a state satisfying `goal`.
+///
+/// Returns `None` if no such state was found within the limits of the settings.
+pub fn solve<P, G>(
+    puzzle: &P,
+    start: P::State,
+    goal: G,
+    settings: &GenerateSettings,
+) -> Option<Moves>
+    where P: Puzzle, G: Fn(&P::State) -> bool
+{
+    let path = match gen_until((vec![start], vec![]), puzzle.moves(), f(puzzle), goal, settings) {
+        Ok(path) => path,
+        Err((path, ())) => path,
+    };
+    path.map(|(_, edges)| edges.into_iter().flatten().collect())
+}
+
+/// A sliding puzzle on a grid, with one blank tile.
+///
+/// The state lists the tiles row by row, where `0` is the blank tile.
+/// The moves slide the blank tile up, down, left or right.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SlidingPuzzle {
+    /// The number of columns.
+    pub width: usize,
+    /// The number of rows.
+    pub height: usize,
+}
+
+impl SlidingPuzzle {
+    /// Move the blank tile up.
+    pub const UP: usize = 0;
+    /// Move the blank tile down.
+    pub const DOWN: usize = 1;
+    /// Move the blank tile left.
+    pub const LEFT: usize = 2;
+    /// Move the blank tile right.
+    pub const RIGHT: usize = 3;
+
+    /// Returns the solved state, with tiles in order and the blank tile last.
+    pub fn solved(&self) -> Vec<u8> {
+        let n = self.width * self.height;
+        (1..n).map(|i| i as u8).chain(Some(0)).collect()
+    }
+}
+
+impl Puzzle for SlidingPuzzle {
+    type State = Vec<u8>;
+
+    fn moves(&self) -> usize {4}
+
+    fn apply(&self, state: &Vec<u8>, mv: usize) -> Option<Vec<u8>> {
+        let i = state.iter().position(|&t| t == 0)?;
+        let (x, y) = (i % self.width, i / self.width);
+        let j = match mv {
+            SlidingPuzzle::UP if y > 0 => i - self.width,
+            SlidingPuzzle::DOWN if y + 1 < self.height => i + self.width,
+            SlidingPuzzle::LEFT if x > 0 => i - 1,
+            SlidingPuzzle::RIGHT if x + 1 < self.width => i + 1,
+            _ => return None,
+        };
+        let mut state = state.clone();
+        state.swap(i, j);
+        Some(state)
+    }
+
+    fn inverse(&self, mv: usize) -> usize {mv ^ 1}
+}
@@ -0,0 +1,356 @@
+//! Reachability queries on generated graphs.
+//!
+//! An index is built once, such that many queries of whether one node
+//! can be transformed into another are fast.
+//! Strongly connected components are collapsed first,
+//! since all nodes in a component reach the same nodes.
+//!
+//! For distances, `DistanceOracle` estimates the number of steps between nodes.
+
+use std::collections::HashSet;
+
+use crate::algo::condense;
+use crate::{Graph, Incidence};
+
+/// The maximum number of strongly connected components for a transitive closure
+/// when using `ReachIndex::Auto`.
+///
+/// The closure uses one bit per pair of components, which is 8 MB at this size.
+pub const CLOSURE_MAX_COMPONENTS: usize = 8192;
+
+/// Selects the index used to answer reachability queries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ReachIndex {
+    /// Use a transitive closure when the number of components is small enough,
+    /// otherwise 2-hop labels.
+    ///
+    /// The closure is used when there are at most `CLOSURE_MAX_COMPONENTS` components.
+    Auto,
+    /// Store the set of reachable components for every component as a bitset.
+    ///
+    /// Queries take constant time, but memory grows quadratically.
+    Closure,
+    /// Store intervals from depth-first traversal of the acyclic graph of components.
+    ///
+    /// Memory grows linearly. Most queries are answered by comparing intervals,
+    /// otherwise by a search that skips components the intervals rule out.
+    Intervals,
+    /// Store 2-hop labels, built by pruned landmark labeling.
+    ///
+    /// Every component stores hubs it reaches and hubs that reach it,
+    /// such that a component reaches another when they share a hub.
+    /// Hubs are picked in order of degree, and a hub is not stored
+    /// where earlier hubs already answer the query, which keeps labels small.
+    /// Queries take time proportional to the size of the labels.
+    TwoHop,
+}
+
+/// Answers whether a node can reach another node.
+#[derive(Clone, Debug)]
+pub struct Reachability {
+    component: Vec<usize>,
+    index: Index,
+}
+
+#[derive(Clone, Debug)]
+enum Index {
+    Closure(Vec<Vec<u64>>),
+    Intervals {
+        out: Vec<Vec<usize>>,
+        // Pre-order number and the largest pre-order number in the subtree of a spanning forest.
+        // When one interval contains another, the component is reachable.
+        tree: Vec<[usize; 2]>,
+        // The smallest post-order number of reachable components and the post-order number.
+        // When one interval does not contain another, the component is not reachable.
+        post: Vec<[usize; 2]>,
+    },
+    TwoHop {
+        // Sorted ranks of hubs reachable from every component.
+        hubs_out: Vec<Vec<usize>>,
+        // Sorted ranks of hubs that reach every component.
+        hubs_in: Vec<Vec<usize>>,
+    },
+}
+
+/// Returns `true` if two sorted lists share an item.
+fn intersects(a: &[usize], b: &[usize]) -> bool {
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {return true};
+        if a[i] < b[j] {i += 1} else {j += 1}
+    }
+    false
+}
+
+impl Reachability {
+    /// Builds a reachability index using `ReachIndex::Auto`.
+    pub fn new<T, U>(graph: &Graph<T, U>) -> Reachability {
+        Reachability::with_index(graph, ReachIndex::Auto)
+    }
+
+    /// Builds a reachability index of the chosen kind.
+    pub fn with_index<T, U>(graph: &Graph<T, U>, index: ReachIndex) -> Reachability {
+        let cond = condense(graph);
+        let m = cond.graph.0.len();
+        let mut out: Vec<Vec<usize>> = vec![vec![]; m];
+        for &([a, b], _) in &cond.graph.1 {out[a].push(b)}
+        let index = match index {
+            ReachIndex::Auto if m <= CLOSURE_MAX_COMPONENTS => ReachIndex::Closure,
+            ReachIndex::Auto => ReachIndex::TwoHop,
+            x => x,
+        };
+        let index = if index == ReachIndex::TwoHop {
+            let mut inc: Vec<Vec<usize>> = vec![vec![]; m];
+            for &([a, b], _) in &cond.graph.1 {inc[b].push(a)}
+            let mut order: Vec<usize> = (0..m).collect();
+            order.sort_by_key(|&c| std::cmp::Reverse((out[c].len() + 1) * (inc[c].len() + 1)));
+            let mut hubs_out: Vec<Vec<usize>> = vec![vec![]; m];
+            let mut hubs_in: Vec<Vec<usize>> = vec![vec![]; m];
+            // Stores the last rank that visited a component, to avoid clearing between searches.
+            let mut visited = vec![usize::MAX; m];
+            let mut queue = std::collections::VecDeque::new();
+            for (rank, &h) in order.iter().enumerate() {
+                queue.push_back(h);
+                visited[h] = rank;
+                while let Some(c) = queue.pop_front() {
+                    if c != h && intersects(&hubs_out[h], &hubs_in[c]) {continue};
+                    hubs_in[c].push(rank);
+                    for &d in &out[c] {
+                        if visited[d] != rank {
+                            visited[d] = rank;
+                            queue.push_back(d);
+                        }
+                    }
+                }
+                // Use a different mark for the backward search.
+                let mark = rank + m;
+                queue.push_back(h);
+                visited[h] = mark;
+                while let Some(c) = queue.pop_front() {
+                    if c != h && intersects(&hubs_out[c], &hubs_in[h]) {continue};
+                    hubs_out[c].push(rank);
+                    for &d in &inc[c] {
+                        if visited[d] != mark {
+                            visited[d] = mark;
+                            queue.push_back(d);
+                        }
+                    }
+                }
+            }
+            Index::TwoHop {hubs_out, hubs_in}
+        } else if index == ReachIndex::Closure {
+            // Components are in topological order, so successors have higher index.
+            let mut reach: Vec<Vec<u64>> = vec![vec![]; m];
+            for c in (0..m).rev() {
+                let mut r = vec![0u64; m.div_ceil(64)];
+                r[c / 64] |= 1 << (c % 64);
+                for &d in &out[c] {
+                    for (x, y) in r.iter_mut().zip(&reach[d]) {*x |= y}
+                }
+                reach[c] = r;
+            }
+            Index::Closure(reach)
+        } else {
+            let mut tree = vec![[0, 0]; m];
+            let mut post = vec![[0, 0]; m];
+            let mut visited = vec![false; m];
+            let (mut pre_counter, mut post_counter) = (0, 0);
+            let mut call: Vec<(usize, usize)> = vec![];
+            for root in 0..m {
+                if visited[root] {continue};
+                visited[root] = true;
+                tree[root][0] = pre_counter;
+                pre_counter += 1;
+                call.push((root, 0));
+                while let Some(&(c, pos)) = call.last() {
+                    if let Some(&d) = out[c].get(pos) {
+                        call.last_mut().unwrap().1 += 1;
+                        if !visited[d] {
+                            visited[d] = true;
+                            tree[d][0] = pre_counter;
+                            pre_counter += 1;
+                            call.push((d, 0));
+                        }
+                        continue;
+                    }
+                    call.pop();
+                    tree[c][1] = pre_counter - 1;
+                    post[c][1] = post_counter;
+                    post_counter += 1;
+                }
+            }
+            // Successors have higher index, so they are finished first.
+            for c in (0..m).rev() {
+                post[c][0] = out[c].iter().map(|&d| post[d][0]).fold(post[c][1], usize::min);
+            }
+            Index::Intervals {out, tree, post}
+        };
+        Reachability {component: cond.component, index}
+    }
+
+    /// Returns the number of nodes.
+    pub fn len(&self) -> usize {self.component.len()}
+
+    /// Returns `true` if there are no nodes.
+    pub fn is_empty(&self) -> bool {self.component.is_empty()}
+
+    /// Returns `true` if there is a path from `a` to `b`.
+    ///
+    /// Every node can reach itself.
+    pub fn can_reach(&self, a: usize, b: usize) -> bool {
+        let (c, d) = (self.component[a], self.component[b]);
+        if c == d {return true};
+        if c > d {return false};
+        match &self.index {
+            Index::Closure(reach) => (reach[c][d / 64] >> (d % 64)) & 1 == 1,
+            Index::TwoHop {hubs_out, hubs_in} => intersects(&hubs_out[c], &hubs_in[d]),
+            Index::Intervals {out, tree, post} => {
+                let contains = |x: &[usize; 2], y: &[usize; 2]| x[0] <= y[0] && y[1] <= x[1];
+                let contains_tree = |x: &[usize; 2], y: &[usize; 2]| x[0] <= y[0] && y[0] <= x[1];
+                if !contains(&post[c], &post[d]) {return false};
+                let mut stack = vec![c];
+                let mut visited = HashSet::new();
+                while let Some(x) = stack.pop() {
+                    if contains_tree(&tree[x], &tree[d]) {return true};
+                    for &y in &out[x] {
+                        if y <= d && contains(&post[y], &post[d]) && visited.insert(y) {
+                            stack.push(y);
+                        }
+                    }
+                }
+                false
+            }
+        }
+    }
+}
+
+/// Estimates the number of steps between nodes from distances to and from landmark nodes.
+///
+/// For every landmark `l`, the distance from `a` to `b` is at most `d(a, l) + d(l, b)`,
+/// which is exact when a shortest path passes through `l`.
+/// More landmarks give better estimates, at the cost of memory and build time.
+#[derive(Clone, Debug)]
+pub struct DistanceOracle {
+    landmarks: Vec<usize>,
+    // Distances from every landmark to every node, `usize::MAX` when not reachable.
+    from: Vec<Vec<usize>>,
+    // Distances from every node to every landmark, `usize::MAX` when not reachable.
+    to: Vec<Vec<usize>>,
+}
+
+impl DistanceOracle {
+    /// Builds an oracle by searching from and to every landmark.
+    pub fn new<T, U>(graph: &Graph<T, U>, landmarks: &[usize]) -> DistanceOracle {
+        let inc = Incidence::new(graph);
+        let search = |adj: &[Vec<(usize, usize)>], start: usize| {
+            let mut dist = vec![usize::MAX; adj.len()];
+            let mut queue = std::collections::VecDeque::new();
+            dist[start] = 0;
+            queue.push_back(start);
+            while let Some(i) = queue.pop_front() {
+                for &(j, _) in &adj[i] {
+                    if dist[j] == usize::MAX {
+                        dist[j] = dist[i] + 1;
+                        queue.push_back(j);
+                    }
+                }
+            }
+            dist
+        };
+        DistanceOracle {
+            landmarks: landmarks.to_vec(),
+            from: landmarks.iter().map(|&l| search(&inc.out, l)).collect(),
+            to: landmarks.iter().map(|&l| search(&inc.inc, l)).collect(),
+        }
+    }
+
+    /// Picks `k` landmarks with the highest total degree and builds an oracle.
+    ///
+    /// Nodes with many edges are often on many shortest paths.
+    pub fn with_degree_landmarks<T, U>(graph: &Graph<T, U>, k: usize) -> DistanceOracle {
+        let inc = Incidence::new(graph);
+        let mut order: Vec<usize> = (0..graph.0.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(inc.out[i].len() + inc.inc[i].len()));
+        order.truncate(k);
+        DistanceOracle::new(graph, &order)
+    }
+
+    /// Returns the landmarks.
+    pub fn landmarks(&self) -> &[usize] {&self.landmarks}
+
+    /// Returns an upper bound of the distance from `a` to `b`, using paths through landmarks.
+    ///
+    /// Returns `None` if no landmark is on a path from `a` to `b`.
+    /// This does not mean that `b` is unreachable from `a`.
+    pub fn upper(&self, a: usize, b: usize) -> Option<usize> {
+        if a == b {return Some(0)};
+        (0..self.landmarks.len())
+            .filter(|&k| self.to[k][a] != usize::MAX && self.from[k][b] != usize::MAX)
+            .map(|k| self.to[k][a] + self.from[k][b])
+            .min()
+    }
+
+    /// Returns a lower bound of the distance from `a` to `b`, using the triangle inequality.
+    ///
+    /// Returns `None` if a landmark proves that `b` is unreachable from `a`.
+    pub fn lower(&self, a: usize, b: usize) -> Option<usize> {
+        let mut res = 0;
+        for k in 0..self.landmarks.len() {
+            let (from, to) = (&self.from[k], &self.to[k]);
+            // A landmark reaching `a` but not `b`, or reached by `b` but not `a`.
+            if from[a] != usize::MAX && from[b] == usize::MAX {return None};
+            if to[b] != usize::MAX && to[a] == usize::MAX {return None};
+            if from[a] != usize::MAX {res = res.max(from[b].saturating_sub(from[a]))};
+            if to[b] != usize::MAX {res = res.max(to[a].saturating_sub(to[b]))};
+        }
+        Some(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Generates a graph with `n` nodes and `m` edges from a linear congruential generator.
+    fn random_graph(n: usize, m: usize, seed: u64) -> Graph<(), ()> {
+        let mut x = seed;
+        let mut next = move || {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (x >> 33) as usize % n
+        };
+        (vec![(); n], (0..m).map(|_| ([next(), next()], ())).collect())
+    }
+
+    fn reachable(graph: &Graph<(), ()>, a: usize) -> Vec<bool> {
+        let mut res = vec![false; graph.0.len()];
+        for (i, _) in crate::traverse::bfs(graph, a) {res[i] = true}
+        res
+    }
+
+    #[test]
+    fn two_hop_labels() {
+        let graph = (vec![(); 5], vec![([0, 1], ()), ([1, 0], ()), ([1, 2], ()), ([3, 2], ())]);
+        let reach = Reachability::with_index(&graph, ReachIndex::TwoHop);
+        assert_eq!(reach.len(), 5);
+        assert!(reach.can_reach(0, 2) && reach.can_reach(1, 0) && reach.can_reach(3, 3));
+        assert!(!reach.can_reach(2, 0) && !reach.can_reach(0, 3) && !reach.can_reach(4, 2));
+    }
+
+    #[test]
+    fn indices_agree_with_traversal() {
+        for seed in 0..40 {
+            let n = 1 + seed as usize % 30;
+            let graph = random_graph(n, (seed as usize * 7) % (3 * n), seed);
+            let indices = [ReachIndex::Auto, ReachIndex::Closure,
+                ReachIndex::Intervals, ReachIndex::TwoHop];
+            let reach: Vec<Reachability> =
+                indices.iter().map(|&index| Reachability::with_index(&graph, index)).collect();
+            for a in 0..n {
+                let expected = reachable(&graph, a);
+                for (b, &e) in expected.iter().enumerate() {
+                    for r in &reach {assert_eq!(r.can_reach(a, b), e, "{} -> {}", a, b)}
+                }
+            }
+        }
+    }
+}
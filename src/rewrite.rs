@@ -0,0 +1,219 @@
+//! String-rewriting systems.
+//!
+//! A rewriting system is written in a small language,
+//! with an optional alphabet followed by one rule per line:
+//!
+//! ```text
+//! # Commuting generators.
+//! alphabet: a b
+//! "ab" -> "ba"
+//! "aa" <-> ""
+//! ```
+//!
+//! - `alphabet:` lists the letters, where whitespace is ignored
+//! - `"lhs" -> "rhs"` replaces one occurrence of `lhs` by `rhs`
+//! - `"lhs" <-> "rhs"` adds rules in both directions, e.g. relations of a monoid presentation
+//! - Words are quoted, or written without quotes when they are non-empty
+//! - `#` starts a comment
+//!
+//! The system compiles into closures `f`, `g` and `h` for `gen`,
+//! where an edge is the list of steps from one word to another.
+
+use crate::{gen_from_iter, strip_comment, GenResult, GenerateError, GenerateSettings, Graph};
+
+/// Stores a rewrite rule that replaces `lhs` by `rhs`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Rule {
+    /// The word to replace.
+    pub lhs: String,
+    /// The replacement.
+    pub rhs: String,
+}
+
+impl Rule {
+    /// Creates a new rule.
+    pub fn new<A: Into<String>, B: Into<String>>(lhs: A, rhs: B) -> Rule {
+        Rule {lhs: lhs.into(), rhs: rhs.into()}
+    }
+
+    /// Returns the rewrites of a word as `(position, new word)`,
+    /// where overlapping occurrences are rewritten separately.
+    pub fn apply(&self, word: &str) -> Vec<(usize, String)> {
+        let mut res = vec![];
+        for pos in 0..=word.len() {
+            if let Some(new_word) = self.apply_at(word, pos) {
+                res.push((pos, new_word));
+            }
+        }
+        res
+    }
+
+    /// Rewrites an occurrence at a byte position, if any.
+    pub fn apply_at(&self, word: &str, pos: usize) -> Option<String> {
+        if pos > word.len() || !word.is_char_boundary(pos) ||
+           !word[pos..].starts_with(&*self.lhs) {return None};
+        Some(format!("{}{}{}", &word[..pos], self.rhs, &word[pos + self.lhs.len()..]))
+    }
+}
+
+/// Stores a rewrite step, by rule index and byte position in the word.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Step {
+    /// The index of the rule.
+    pub rule: usize,
+    /// The byte position of the occurrence.
+    pub pos: usize,
+}
+
+/// Stores an error from parsing a rewriting system.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// The line number of the error, starting at 1.
+    pub line: usize,
+    /// Description of the error.
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, w: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(w, "Line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Stores a string-rewriting system.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct System {
+    /// The letters of words, or `None` to allow any letter.
+    pub alphabet: Option<Vec<char>>,
+    /// The rewrite rules.
+    pub rules: Vec<Rule>,
+}
+
+impl System {
+    /// Creates a new system from rules, allowing any letter.
+    pub fn new(rules: Vec<Rule>) -> System {
+        System {alphabet: None, rules}
+    }
+
+    /// Parses a system, see the module documentation for the syntax.
+    ///
+    /// Rules using letters outside the alphabet are rejected.
+    pub fn parse(src: &str) -> Result<System, ParseError> {
+        let mut sys = System::new(vec![]);
+        for (i, line) in src.lines().enumerate() {
+            let err = |message: &str| ParseError {line: i + 1, message: message.into()};
+            let line = strip_comment(line, false).trim();
+            if line.is_empty() {continue};
+            if let Some(letters) = line.strip_prefix("alphabet:") {
+                if sys.alphabet.is_some() {return Err(err("Alphabet is already declared"))};
+                sys.alphabet = Some(letters.chars().filter(|c| !c.is_whitespace()).collect());
+                continue;
+            }
+            let (lhs, rest) = parse_word(line).map_err(err)?;
+            let rest = rest.trim_start();
+            let (both, rest) = if let Some(rest) = rest.strip_prefix("<->") {(true, rest)}
+                else if let Some(rest) = rest.strip_prefix("->") {(false, rest)}
+                else {return Err(err("Expected `->` or `<->`"))};
+            let (rhs, rest) = parse_word(rest.trim_start()).map_err(err)?;
+            if !rest.trim().is_empty() {return Err(err("Unexpected text after rule"))};
+            if !sys.is_word(&lhs) || !sys.is_word(&rhs) {
+                return Err(err("Rule uses a letter outside the alphabet"));
+            }
+            sys.rules.push(Rule::new(lhs.clone(), rhs.clone()));
+            if both {sys.rules.push(Rule::new(rhs, lhs))};
+        }
+        Ok(sys)
+    }
+
+    /// Returns `true` if a word only uses letters of the alphabet.
+    pub fn is_word(&self, word: &str) -> bool {
+        match &self.alphabet {
+            None => true,
+            Some(alphabet) => word.chars().all(|c| alphabet.contains(&c)),
+        }
+    }
+
+    /// Returns `true` if no rule applies to a word.
+    pub fn is_irreducible(&self, word: &str) -> bool {
+        self.rules.iter().all(|rule| !word.contains(&*rule.lhs))
+    }
+
+    /// Returns the rewrites of a word, with the step of each rewrite.
+    pub fn successors(&self, word: &str) -> Vec<(String, Step)> {
+        let mut res = vec![];
+        for (k, rule) in self.rules.iter().enumerate() {
+            for (pos, new_word) in rule.apply(word) {
+                res.push((new_word, Step {rule: k, pos}));
+            }
+        }
+        res
+    }
+
+    /// Returns the number of operations per node for `gen` with words up to `max_len` bytes.
+    pub fn n(&self, max_len: usize) -> usize {
+        self.rules.len() * (max_len + 1)
+    }
+
+    /// Returns the function `f` for `gen` with words up to `max_len` bytes.
+    ///
+    /// Operation `j` applies rule `j / (max_len + 1)` at position `j % (max_len + 1)`.
+    /// Returns `Err(())` when the rule does not apply or the new word is too long.
+    pub fn f(
+        &self,
+        max_len: usize,
+    ) -> impl Fn(&String, usize) -> Result<(String, Vec<Step>), ()> + '_ {
+        move |word, j| {
+            let step = Step {rule: j / (max_len + 1), pos: j % (max_len + 1)};
+            let rule = self.rules.get(step.rule).ok_or(())?;
+            let new_word = rule.apply_at(word, step.pos).ok_or(())?;
+            if new_word.len() > max_len {return Err(())};
+            Ok((new_word, vec![step]))
+        }
+    }
+
+    /// Returns the filter `g` for `gen` that keeps irreducible words,
+    /// which are the normal forms of a terminating system.
+    pub fn g(&self) -> impl Fn(&String) -> bool + '_ {
+        move |word| self.is_irreducible(word)
+    }
+
+    /// Returns the composer `h` for `gen` that concatenates steps.
+    pub fn h<E>() -> impl Fn(&Vec<Step>, &Vec<Step>) -> Result<Vec<Step>, Option<E>> {
+        |a, b| Ok(a.iter().chain(b).cloned().collect())
+    }
+
+    /// Generates the graph of words reachable from the seed,
+    /// with words up to `max_len` bytes.
+    ///
+    /// Unlike `gen` with `f`, rules that do not apply are not reported as errors.
+    pub fn gen<G>(
+        &self,
+        seed: Vec<String>,
+        max_len: usize,
+        g: G,
+        settings: &GenerateSettings,
+    ) -> GenResult<Graph<String, Vec<Step>>, GenerateError>
+        where G: Fn(&String) -> bool
+    {
+        let f = |word: &String| self.successors(word).into_iter()
+            .filter(|(new_word, _)| new_word.len() <= max_len)
+            .map(|(new_word, step)| Ok((new_word, vec![step])));
+        gen_from_iter((seed, vec![]), f, g, System::h(), settings)
+    }
+}
+
+/// Parses a quoted or bare word at the start of `s` and returns it with the rest.
+fn parse_word(s: &str) -> Result<(String, &str), &'static str> {
+    if let Some(s) = s.strip_prefix('"') {
+        match s.find('"') {
+            Some(end) => Ok((s[..end].into(), &s[end + 1..])),
+            None => Err("Unterminated word"),
+        }
+    } else {
+        let end = s.find(|c: char| c.is_whitespace() || c == '-' || c == '<').unwrap_or(s.len());
+        if end == 0 {return Err("Expected word")};
+        Ok((s[..end].into(), &s[end..]))
+    }
+}
@@ -0,0 +1,741 @@
+//! Goal-directed and bounded generation.
+//!
+//! Instead of constructing the full graph,
+//! these functions stop generating when a node satisfying a goal is found,
+//! or limit which nodes are expanded.
+
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::hash::Hash;
+use std::ops::Add;
+
+use crate::{
+    expand, into_result, post_process, retain_mapped, EdgeSet, Emit, Fifo, Frontier,
+    GenResult, GenerateError, GenerateSettings, Graph,
+};
+use crate::trace::{TraceEntry, TraceResult};
+
+/// A path of nodes and the edges between them.
+///
+/// There is one edge less than there are nodes.
+pub type Path<T, U> = (Vec<T>, Vec<U>);
+
+/// Generates a graph until a node satisfying `goal` is found.
+///
+/// This is the same as `gen_until_all` with `max_goals` set to one.
+///
+/// Returns the path from a seed node to the first node satisfying the goal,
+/// or `None` if no such node was generated.
+///
+/// Nodes are expanded in the order they are generated,
+/// so the path has the fewest number of steps from the seed.
+/// If a seed node satisfies the goal, the path only contains that node.
+///
+/// The graph is not post-processed, since only the path is returned.
+/// Errors are handled the same way as in `gen`.
+pub fn gen_until<T, U, F, P, E>(
+    seed: Graph<T, U>,
+    n: usize,
+    f: F,
+    goal: P,
+    settings: &GenerateSettings,
+) -> GenResult<Option<Path<T, U>>, E>
+    where T: Eq + Hash + Clone,
+          U: Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          P: Fn(&T) -> bool,
+          E: From<GenerateError>
+{
+    match gen_until_all(seed, n, f, goal, 1, settings) {
+        Ok(paths) => Ok(paths.into_iter().next()),
+        Err((paths, err)) => Err((paths.into_iter().next(), err)),
+    }
+}
+
+/// Generates a graph until `max_goals` nodes satisfying `goal` are found.
+///
+/// Returns the path from a seed node to every node satisfying the goal,
+/// in the order they were found.
+/// Seed nodes that satisfy the goal are found first.
+///
+/// This is used to enumerate all solutions that are reachable within the limits.
+/// Hitting the limits before finding `max_goals` nodes returns an error
+/// together with the paths found so far.
+///
+/// The graph is not post-processed, since only the paths are returned.
+/// Errors are handled the same way as in `gen`.
+pub fn gen_until_all<T, U, F, P, E>(
+    seed: Graph<T, U>,
+    n: usize,
+    f: F,
+    goal: P,
+    max_goals: usize,
+    settings: &GenerateSettings,
+) -> GenResult<Vec<Path<T, U>>, E>
+    where T: Eq + Hash + Clone,
+          U: Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          P: Fn(&T) -> bool,
+          E: From<GenerateError>
+{
+    use std::collections::{HashMap, HashSet};
+
+    let mut hits: Vec<T> = vec![];
+    let mut has_hit: HashSet<T> = HashSet::new();
+    for node in &seed.0 {
+        if hits.len() < max_goals && goal(node) && has_hit.insert(node.clone()) {
+            hits.push(node.clone());
+        }
+    }
+    let seed_attr = vec![(); seed.0.len()];
+    let f = |t: &T, j| {
+        if hits.len() >= max_goals || j >= n {return None};
+        let res = f(t, j);
+        if let Ok((new_node, _)) = &res {
+            if goal(new_node) && !has_hit.contains(new_node) {
+                has_hit.insert(new_node.clone());
+                hits.push(new_node.clone());
+            }
+        }
+        Some(res.map(|(t, u)| (Emit::NewNode(t, u), ())))
+    };
+    let exp = expand(seed, seed_attr, f, &mut Fifo(0), settings);
+    let index: HashMap<&T, usize> = exp.graph.0.iter().enumerate()
+        .map(|(i, t)| (t, i)).rev().collect();
+    let paths = hits.iter().map(|hit| path_to(&exp.graph, &exp.parent_edge, index[hit])).collect();
+    into_result(paths, exp.error)
+}
+
+/// Searches for a node satisfying `goal` using iterative deepening.
+///
+/// Runs a depth-first search with depth limit `1, 2, ..., max_depth`,
+/// until a node satisfying the goal is found,
+/// or until the search space is explored without hitting the depth limit.
+///
+/// Returns a path with the fewest number of steps from a seed node to the goal,
+/// or `None` if no such path was found.
+/// If a seed node satisfies the goal, the path only contains that node.
+///
+/// Only the current path and the map of known nodes are kept in memory,
+/// which is shared between iterations.
+/// Seed edges are ignored and no edges are stored,
+/// so only `max_nodes` of the settings applies.
+/// Since nodes are expanded one step at a time,
+/// the position of the progress when hitting the limit is the number of steps taken.
+///
+/// Errors from `f` are handled the same way as in `gen`.
+pub fn gen_deepening<T, U, F, P, E>(
+    (nodes, _): Graph<T, U>,
+    n: usize,
+    f: F,
+    goal: P,
+    max_depth: usize,
+    settings: &GenerateSettings,
+) -> GenResult<Option<Path<T, U>>, E>
+    where T: Eq + Hash + Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          P: Fn(&T) -> bool,
+          E: From<GenerateError>
+{
+    if let Some(node) = nodes.iter().find(|t| goal(t)) {
+        return Ok(Some((vec![node.clone()], vec![])));
+    }
+    if max_depth == 0 {return Ok(None)};
+
+    let step = Cell::new(0);
+    let mut frontier = Deepening {
+        goal,
+        n,
+        max_depth,
+        seeds: nodes.len(),
+        step: &step,
+        limit: 1,
+        seed: 0,
+        cut: false,
+        best: vec![usize::MAX; nodes.len()],
+        stack: vec![],
+        path_edges: vec![],
+        child: None,
+        child_edge: None,
+        found: false,
+    };
+    let seed_attr = vec![(); nodes.len()];
+    let f = |t: &T, j| if j == 0 {
+        Some(f(t, step.get()).map(|(t, u)| (Emit::NewNode(t, u), ())))
+    } else {None};
+    let exp = expand((nodes, vec![]), seed_attr, f, &mut frontier, settings);
+    let path = if frontier.found {
+        let nodes = &exp.graph.0;
+        let path = frontier.stack.iter().map(|&(k, _)| nodes[k].clone()).collect();
+        Some((path, frontier.path_edges))
+    } else {None};
+    into_result(path, exp.error)
+}
+
+/// Expands nodes depth-first with an increasing depth limit, see `gen_deepening`.
+///
+/// Every node is returned once per step, which is stored in `step` for `f`.
+struct Deepening<'a, U, P> {
+    goal: P,
+    n: usize,
+    max_depth: usize,
+    seeds: usize,
+    step: &'a Cell<usize>,
+    /// The current depth limit.
+    limit: usize,
+    /// The next seed node to start from in this iteration.
+    seed: usize,
+    /// Whether some node was not expanded because of the depth limit.
+    cut: bool,
+    /// The smallest depth a node was reached in this iteration.
+    best: Vec<usize>,
+    /// Stores the node and the next step to try.
+    stack: Vec<(usize, usize)>,
+    path_edges: Vec<U>,
+    /// The node and edge of the last step.
+    child: Option<usize>,
+    child_edge: Option<U>,
+    /// Whether the last node on the stack satisfies the goal.
+    found: bool,
+}
+
+impl<'a, T, U, P: Fn(&T) -> bool> Frontier<T, U> for Deepening<'a, U, P> {
+    fn next(&mut self, nodes: &[T], _depth: &[usize]) -> Option<usize> {
+        if let (Some(id), Some(u)) = (self.child.take(), self.child_edge.take()) {
+            if (self.goal)(&nodes[id]) {
+                self.stack.push((id, 0));
+                self.path_edges.push(u);
+                self.found = true;
+                return None;
+            }
+            let depth = self.stack.len();
+            if depth < self.best[id] {
+                self.best[id] = depth;
+                self.stack.push((id, 0));
+                self.path_edges.push(u);
+            }
+        }
+        loop {
+            if let Some(&(i, j)) = self.stack.last() {
+                let depth = self.stack.len() - 1;
+                if j >= self.n || depth >= self.limit {
+                    if depth >= self.limit && self.n > 0 {self.cut = true};
+                    self.stack.pop();
+                    self.path_edges.pop();
+                    continue;
+                }
+                self.stack.last_mut().unwrap().1 += 1;
+                self.step.set(j);
+                return Some(i);
+            }
+            if self.seed < self.seeds {
+                let s = self.seed;
+                self.seed += 1;
+                if self.best[s] == 0 {continue};
+                self.best[s] = 0;
+                self.stack.push((s, 0));
+            } else if self.cut && self.limit < self.max_depth {
+                self.limit += 1;
+                self.seed = 0;
+                self.cut = false;
+                for best in &mut self.best {*best = usize::MAX}
+            } else {
+                return None;
+            }
+        }
+    }
+
+    fn add_edge(
+        &mut self,
+        _edges: &mut Vec<([usize; 2], U)>,
+        _has_edge: &EdgeSet,
+        (_, u): ([usize; 2], U),
+    ) -> bool {
+        self.child_edge = Some(u);
+        false
+    }
+
+    fn trace(&mut self, entry: TraceEntry) {
+        match entry.result {
+            TraceResult::NewNode(id) => {
+                self.best.push(usize::MAX);
+                self.child = Some(id);
+            }
+            TraceResult::Duplicate(id) => self.child = Some(id),
+            TraceResult::Error => {}
+        }
+    }
+}
+
+/// Explores a graph by random rollouts from the seed.
+///
+/// Every rollout starts at a random seed node and takes up to `depth` steps,
+/// where every step calls `f` with a random `j` below `n` and moves to the new node.
+/// A rollout ends early when `f` returns an error.
+/// All visited nodes and edges are recorded into one graph,
+/// where an edge between the same two nodes is only recorded once.
+///
+/// Returns the graph and how many times every node was visited.
+///
+/// The function `rand` returns a random number below its argument.
+/// For example, when using the `rand` crate: `|k| rng.gen_range(0..k)`.
+///
+/// This is used to get structural coverage of graphs that are too large to generate.
+/// The graph is not post-processed.
+/// Errors are handled the same way as in `gen`.
+pub fn gen_monte_carlo<T, U, F, R, E>(
+    seed: Graph<T, U>,
+    n: usize,
+    f: F,
+    rollouts: usize,
+    depth: usize,
+    rand: R,
+    settings: &GenerateSettings,
+) -> GenResult<(Graph<T, U>, Vec<usize>), E>
+    where T: Eq + Hash + Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          R: FnMut(usize) -> usize,
+          E: From<GenerateError>
+{
+    let seed_count = seed.0.len();
+    if seed_count == 0 || n == 0 {
+        return Ok((seed, vec![0; seed_count]));
+    }
+    let step = Cell::new(0);
+    let mut frontier = Rollouts {
+        rand,
+        n,
+        seeds: seed_count,
+        rollouts,
+        depth,
+        step: &step,
+        node: 0,
+        steps: depth,
+        visits: vec![0; seed_count],
+    };
+    let seed_attr = vec![(); seed_count];
+    let f = |t: &T, j| if j == 0 {
+        Some(f(t, step.get()).map(|(t, u)| (Emit::NewNode(t, u), ())))
+    } else {None};
+    let exp = expand(seed, seed_attr, f, &mut frontier, settings);
+    into_result((exp.graph, frontier.visits), exp.error)
+}
+
+/// Expands nodes along random rollouts, see `gen_monte_carlo`.
+///
+/// Every node is returned once per step, which is stored in `step` for `f`.
+struct Rollouts<'a, R> {
+    rand: R,
+    n: usize,
+    seeds: usize,
+    /// The number of rollouts left to start.
+    rollouts: usize,
+    depth: usize,
+    step: &'a Cell<usize>,
+    /// The current node of the rollout.
+    node: usize,
+    /// The number of steps taken in the current rollout.
+    steps: usize,
+    visits: Vec<usize>,
+}
+
+impl<'a, T, U, R: FnMut(usize) -> usize> Frontier<T, U> for Rollouts<'a, R> {
+    fn next(&mut self, _nodes: &[T], _depth: &[usize]) -> Option<usize> {
+        while self.steps >= self.depth {
+            if self.rollouts == 0 {return None};
+            self.rollouts -= 1;
+            self.node = (self.rand)(self.seeds);
+            self.visits[self.node] += 1;
+            self.steps = 0;
+        }
+        self.steps += 1;
+        self.step.set((self.rand)(self.n));
+        Some(self.node)
+    }
+
+    fn add_edge(
+        &mut self,
+        edges: &mut Vec<([usize; 2], U)>,
+        has_edge: &EdgeSet,
+        edge: ([usize; 2], U),
+    ) -> bool {
+        if has_edge.contains(&edge.0) {return false};
+        edges.push(edge);
+        true
+    }
+
+    fn trace(&mut self, entry: TraceEntry) {
+        match entry.result {
+            TraceResult::NewNode(id) => {
+                self.visits.push(1);
+                self.node = id;
+            }
+            TraceResult::Duplicate(id) => {
+                self.visits[id] += 1;
+                self.node = id;
+            }
+            // Ends the rollout.
+            TraceResult::Error => self.steps = self.depth,
+        }
+    }
+}
+
+/// Stores an estimate of the size of a graph, see `estimate`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Estimate {
+    /// The estimated number of nodes.
+    pub nodes: f64,
+    /// The estimated number of edges before post-processing,
+    /// assuming every node is expanded.
+    pub edges: f64,
+    /// The estimated average number of successful calls to `f` per node.
+    pub branching: f64,
+    /// The number of depth levels that were sampled.
+    pub depth: usize,
+    /// Whether sampling ran out of new nodes before running out of budget.
+    ///
+    /// When this is `false`, deeper levels were not sampled,
+    /// so the estimated number of nodes is a lower bound.
+    pub complete: bool,
+}
+
+/// Estimates the size of a graph without generating it.
+///
+/// Samples one depth level at a time, starting with the seed.
+/// From every level, a random sample of nodes is expanded,
+/// and the number of new nodes is scaled by the estimated size of the level
+/// to estimate the size of the next level.
+/// At most `sample_budget` nodes are expanded in total,
+/// with the square root of the budget as sample size per level.
+///
+/// Duplicates are only detected among sampled nodes,
+/// so the estimate is rough and tends to be too high for graphs with many cycles.
+/// This is used to choose sensible limits before generating.
+///
+/// Errors returned by `f` are counted as unsuccessful calls.
+/// The function `rand` returns a random number below its argument.
+pub fn estimate<T, U, F, R, E>(
+    seed: &[T],
+    n: usize,
+    f: F,
+    sample_budget: usize,
+    mut rand: R,
+) -> Estimate
+    where T: Eq + Hash + Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          R: FnMut(usize) -> usize
+{
+    use std::collections::HashSet;
+
+    let width = ((sample_budget as f64).sqrt().ceil() as usize).max(1);
+    let mut seen: HashSet<T> = HashSet::new();
+    let mut level: Vec<T> = seed.iter().filter(|t| seen.insert((*t).clone())).cloned().collect();
+    let mut level_size = level.len() as f64;
+    let (mut nodes, mut edges) = (level_size, 0.0);
+    let (mut expanded, mut depth) = (0, 0);
+    let complete = loop {
+        if level.is_empty() {break true};
+        if expanded >= sample_budget {break false};
+        let k = width.min(level.len()).min(sample_budget - expanded);
+        let mut next = vec![];
+        let mut results = 0;
+        for s in 0..k {
+            let r = s + rand(level.len() - s);
+            level.swap(s, r);
+            expanded += 1;
+            for j in 0..n {
+                if let Ok((t, _)) = f(&level[s], j) {
+                    results += 1;
+                    if seen.insert(t.clone()) {next.push(t)};
+                }
+            }
+        }
+        edges += level_size * results as f64 / k as f64;
+        level_size *= next.len() as f64 / k as f64;
+        nodes += level_size;
+        level = next;
+        depth += 1;
+    };
+    // Nodes of the last level were not expanded.
+    if !complete && nodes > level_size {edges += level_size * edges / (nodes - level_size)};
+    let branching = if nodes == 0.0 {0.0} else {edges / nodes};
+    Estimate {nodes, edges, branching, depth, complete}
+}
+
+/// Stores settings for beam search.
+#[derive(Clone, Debug)]
+pub struct Beam<S> {
+    /// The maximum number of nodes to expand per depth level.
+    pub width: usize,
+    /// Scores nodes, where nodes with higher score are expanded first.
+    pub score: S,
+    /// Whether to keep nodes that were discarded from expansion.
+    ///
+    /// When this is `false`, discarded nodes are removed during post-processing,
+    /// composing edges over them the same way as nodes removed by the filter.
+    pub keep_discarded: bool,
+}
+
+/// Generates a graph using beam search.
+///
+/// Nodes are expanded one depth level at a time.
+/// For every level, only the `beam.width` nodes with the highest score are expanded,
+/// and the rest are discarded.
+/// Nodes with equal score are expanded in the order they were generated.
+///
+/// This is used when exhaustive expansion is not possible.
+/// The result is post-processed the same way as in `gen`.
+pub fn gen_beam<T, U, F, S, K, G, H, E>(
+    seed: Graph<T, U>,
+    n: usize,
+    f: F,
+    beam: Beam<S>,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<Graph<T, U>, E>
+    where T: Eq + Hash + Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          S: Fn(&T) -> K,
+          K: Ord,
+          G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let seed_attr = vec![(); seed.0.len()];
+    let f = |t: &T, j| if j < n {Some(f(t, j).map(|(t, u)| (Emit::NewNode(t, u), ())))}
+        else {None};
+    let keep_discarded = beam.keep_discarded;
+    let mut frontier = BeamFrontier {
+        width: beam.width,
+        score: beam.score,
+        queue: vec![],
+        level_end: 0,
+        discarded: vec![],
+    };
+    let mut exp = expand(seed, seed_attr, f, &mut frontier, settings);
+    let discarded = frontier.discarded;
+    let keep = |i: usize, t: &T| {
+        g(t) && (keep_discarded || !discarded.get(i).cloned().unwrap_or(false))
+    };
+    let (graph, _) = post_process(exp.graph, &mut exp.has_edge, keep, h, settings, &mut exp.error);
+    into_result(graph, exp.error)
+}
+
+/// Expands the nodes with the highest score per depth level.
+struct BeamFrontier<S> {
+    width: usize,
+    score: S,
+    /// Nodes to expand in the current level, in reverse order.
+    queue: Vec<usize>,
+    /// The end of the current level.
+    level_end: usize,
+    /// Stores which nodes were discarded.
+    discarded: Vec<bool>,
+}
+
+impl<T, U, S, K> Frontier<T, U> for BeamFrontier<S>
+    where S: Fn(&T) -> K, K: Ord
+{
+    fn next(&mut self, nodes: &[T], _depth: &[usize]) -> Option<usize> {
+        use std::cmp::Reverse;
+
+        if self.queue.is_empty() {
+            if self.level_end >= nodes.len() {return None};
+
+            let mut level: Vec<usize> = (self.level_end..nodes.len()).collect();
+            self.level_end = nodes.len();
+            level.sort_by_cached_key(|&i| Reverse((self.score)(&nodes[i])));
+            self.discarded.resize(nodes.len(), false);
+            for &i in level.iter().skip(self.width) {self.discarded[i] = true}
+            level.truncate(self.width);
+            level.reverse();
+            self.queue = level;
+        }
+        self.queue.pop()
+    }
+}
+
+/// Generates a graph where `prune` stops expansion of nodes.
+///
+/// This works the same way as `gen`, but before a node is expanded,
+/// `prune` is called with the node and its depth.
+/// When `prune` returns `true`, the node is not expanded.
+///
+/// Unlike the filter `g`, pruned nodes are kept in the graph and can be used for composition.
+/// This is used to stop expanding regions of low value, while keeping the nodes themselves.
+pub fn gen_prune<T, U, F, P, G, H, E>(
+    seed: Graph<T, U>,
+    n: usize,
+    f: F,
+    prune: P,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<Graph<T, U>, E>
+    where T: Eq + Hash + Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          P: Fn(&T, usize) -> bool,
+          G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let seed_attr = vec![(); seed.0.len()];
+    let f = |t: &T, j| if j < n {Some(f(t, j).map(|(t, u)| (Emit::NewNode(t, u), ())))}
+        else {None};
+    let mut frontier = PruneFrontier {prune, next: 0};
+    let mut exp = expand(seed, seed_attr, f, &mut frontier, settings);
+    let (graph, _) =
+        post_process(exp.graph, &mut exp.has_edge, |_, t| g(t), h, settings, &mut exp.error);
+    into_result(graph, exp.error)
+}
+
+/// Expands nodes in the order they were generated, skipping pruned nodes.
+struct PruneFrontier<P> {
+    prune: P,
+    next: usize,
+}
+
+impl<T, U, P> Frontier<T, U> for PruneFrontier<P>
+    where P: Fn(&T, usize) -> bool
+{
+    fn next(&mut self, nodes: &[T], depth: &[usize]) -> Option<usize> {
+        while self.next < nodes.len() {
+            let i = self.next;
+            self.next += 1;
+            if !(self.prune)(&nodes[i], depth[i]) {return Some(i)};
+        }
+        None
+    }
+}
+
+/// Generates a graph by expanding nodes in order of accumulated edge cost.
+///
+/// The cost of a node is the smallest sum of edge costs on a path from the seed,
+/// where `cost` computes the cost of an edge.
+/// Seed nodes have zero cost, which is the default value of `C`.
+/// Edge costs must be non-negative.
+///
+/// Nodes are expanded in order of lowest cost, until the next node costs more than `budget`.
+/// Nodes that cost more than the budget are removed during post-processing,
+/// such that the graph contains everything reachable within the budget.
+///
+/// Returns the graph and the cost of every node.
+/// The result is post-processed the same way as in `gen`.
+#[allow(clippy::too_many_arguments)]
+pub fn gen_uniform_cost<T, U, F, K, C, G, H, E>(
+    seed: Graph<T, U>,
+    n: usize,
+    f: F,
+    cost: K,
+    budget: C,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<(Graph<T, U>, Vec<C>), E>
+    where T: Eq + Hash + Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          K: Fn(&U) -> C,
+          C: Copy + PartialOrd + Add<Output = C> + Default,
+          G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let seed_attr = vec![(); seed.0.len()];
+    let f = |t: &T, j| if j < n {Some(f(t, j).map(|(t, u)| (Emit::NewNode(t, u), ())))}
+        else {None};
+    let mut frontier = CostFrontier {
+        cost,
+        budget,
+        dist: vec![C::default(); seed.0.len()],
+        done: vec![false; seed.0.len()],
+        queue: (0..seed.0.len()).map(|i| CostEntry(C::default(), i)).collect(),
+    };
+    let mut exp = expand(seed, seed_attr, f, &mut frontier, settings);
+    let dist = frontier.dist;
+    let keep = |i: usize, t: &T| dist[i] <= budget && g(t);
+    let (graph, map_nodes) =
+        post_process(exp.graph, &mut exp.has_edge, keep, h, settings, &mut exp.error);
+    let dist = retain_mapped(dist, &map_nodes);
+    into_result((graph, dist), exp.error)
+}
+
+/// Expands nodes in order of lowest accumulated edge cost.
+struct CostFrontier<K, C> {
+    cost: K,
+    budget: C,
+    /// The lowest known cost of every node.
+    dist: Vec<C>,
+    /// Stores which nodes were expanded.
+    done: Vec<bool>,
+    queue: BinaryHeap<CostEntry<C>>,
+}
+
+impl<T, U, K, C> Frontier<T, U> for CostFrontier<K, C>
+    where K: Fn(&U) -> C,
+          C: Copy + PartialOrd + Add<Output = C>
+{
+    fn next(&mut self, _nodes: &[T], _depth: &[usize]) -> Option<usize> {
+        while let Some(CostEntry(c, i)) = self.queue.pop() {
+            if self.done[i] || c > self.dist[i] {continue};
+            if c > self.budget {return None};
+            self.done[i] = true;
+            return Some(i);
+        }
+        None
+    }
+
+    fn edge(&mut self, _nodes: &[T], edges: &mut Vec<([usize; 2], U)>) {
+        let ([a, b], ref u) = edges[edges.len() - 1];
+        let c = self.dist[a] + (self.cost)(u);
+        if b >= self.dist.len() {
+            self.dist.push(c);
+            self.done.push(false);
+        } else if self.done[b] || self.dist[b] <= c {
+            return;
+        }
+        self.dist[b] = c;
+        self.queue.push(CostEntry(c, b));
+    }
+}
+
+/// Orders nodes by lowest cost first.
+struct CostEntry<C>(C, usize);
+
+impl<C: PartialOrd> PartialEq for CostEntry<C> {
+    fn eq(&self, other: &Self) -> bool {self.cmp(other) == Ordering::Equal}
+}
+
+impl<C: PartialOrd> Eq for CostEntry<C> {}
+
+impl<C: PartialOrd> PartialOrd for CostEntry<C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {Some(self.cmp(other))}
+}
+
+impl<C: PartialOrd> Ord for CostEntry<C> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.partial_cmp(&self.0).unwrap_or(Ordering::Equal)
+            .then_with(|| other.1.cmp(&self.1))
+    }
+}
+
+/// Follows the edges from the parent of every node back to the seed.
+fn path_to<T: Clone, U: Clone>(
+    (nodes, edges): &Graph<T, U>,
+    parent_edge: &[Option<usize>],
+    mut id: usize
+) -> Path<T, U> {
+    let mut path_nodes = vec![nodes[id].clone()];
+    let mut path_edges = vec![];
+    while let Some(k) = parent_edge[id] {
+        let edge = &edges[k];
+        let i = edge.0[0];
+        path_nodes.push(nodes[i].clone());
+        path_edges.push(edge.1.clone());
+        id = i;
+    }
+    path_nodes.reverse();
+    path_edges.reverse();
+    (path_nodes, path_edges)
+}
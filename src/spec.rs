@@ -0,0 +1,574 @@
+//! Declarative specifications of string-rewriting graphs.
+//!
+//! A specification describes nodes as strings, rewrite rules,
+//! filters, how to compose edges and the settings for generating.
+//! It can be read from a TOML or JSON file, such that experiments are reproducible.
+//!
+//! TOML files are a list of `key = value` lines, where `#` starts a comment.
+//! Values are quoted strings, integers or arrays of quoted strings:
+//!
+//! ```text
+//! # Sorting words by swapping neighbours.
+//! seed = ["cba"]
+//! rules = ["ba -> ab", "cb -> bc", "ca -> ac"]
+//! max_len = 3
+//! keep_contains = ["a"]
+//! compose = "join"
+//! separator = "; "
+//! max_nodes = 1000
+//! ```
+//!
+//! JSON files are an object with the same keys and values, e.g.
+//! `{"seed": ["cba"], "rules": ["ba -> ab"]}`.
+//! Use `run_json` to generate the graph of a JSON specification as JSON,
+//! e.g. from a WebAssembly binding.
+//!
+//! - `seed` (required): initial nodes
+//! - `rules` (required): rewrite rules `lhs -> rhs` or `lhs <-> rhs`,
+//!   with words as in the `rewrite` module, e.g. `"aa" <-> ""`
+//! - `max_len`: longer nodes are not generated
+//! - `keep_max_len`: longer nodes are filtered out in post-processing
+//! - `keep_contains`: nodes not containing any of these strings are filtered out
+//! - `compose`: `"join"`, `"first"`, `"last"` or `"none"`, see `Compose`
+//! - `separator`: used by `"join"`, default `"; "`
+//! - `max_nodes`, `max_edges`, `max_compositions`: limits, see `GenerateSettings`
+//! - `edge_index`: `"auto"`, `"sparse"` or `"dense"`, see `EdgeIndex`
+
+use crate::export::to_cytoscape;
+use crate::rewrite::{Rule, System};
+use crate::{
+    gen_from_iter, strip_comment, EdgeIndex, GenResult, GenerateError, GenerateSettings, Graph,
+};
+
+/// Describes how to compose edge labels when removing nodes in post-processing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Compose {
+    /// Join labels with a separator.
+    Join(String),
+    /// Keep the label of the first edge.
+    First,
+    /// Keep the label of the last edge.
+    Last,
+    /// Do not compose edges, such that paths through removed nodes are lost.
+    None,
+}
+
+/// Stores a specification of a string-rewriting graph.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Spec {
+    /// Initial nodes.
+    pub seed: Vec<String>,
+    /// Rewrite rules, see `Rule::apply`.
+    pub rules: Vec<Rule>,
+    /// Longer nodes are not generated.
+    pub max_len: Option<usize>,
+    /// Longer nodes are filtered out in post-processing.
+    pub keep_max_len: Option<usize>,
+    /// Nodes not containing any of these strings are filtered out in post-processing.
+    pub keep_contains: Option<Vec<String>>,
+    /// How to compose edge labels.
+    pub compose: Compose,
+    /// Settings for generating.
+    pub settings: GenerateSettings,
+}
+
+/// Stores an error from reading a specification.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpecError {
+    /// The line number of the error, starting at 1.
+    ///
+    /// This is `None` when the error is not on a specific line, e.g. a missing key.
+    pub line: Option<usize>,
+    /// Description of the error.
+    pub message: String,
+}
+
+impl std::fmt::Display for SpecError {
+    fn fmt(&self, w: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self.line {
+            Some(line) => write!(w, "Line {}: {}", line, self.message),
+            None => write!(w, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for SpecError {}
+
+fn error(line: usize, message: String) -> SpecError {
+    SpecError {line: Some(line), message}
+}
+
+/// Stores the value of a specification entry.
+enum Value {
+    Str(String),
+    Int(usize),
+    List(Vec<String>),
+}
+
+impl Spec {
+    /// Creates a new specification from seed and rules.
+    ///
+    /// Edge labels are joined with `; `.
+    /// Uses `GenerateSettings::new(10_000, 10_000)`.
+    pub fn new(seed: Vec<String>, rules: Vec<Rule>) -> Spec {
+        Spec {
+            seed,
+            rules,
+            max_len: None,
+            keep_max_len: None,
+            keep_contains: None,
+            compose: Compose::Join("; ".into()),
+            settings: GenerateSettings::new(10_000, 10_000),
+        }
+    }
+
+    /// Reads a specification in TOML format.
+    pub fn from_toml(src: &str) -> Result<Spec, SpecError> {
+        let mut entries = vec![];
+        let mut lines = src.lines().enumerate();
+        while let Some((i, line)) = lines.next() {
+            let line_number = i + 1;
+            let line = strip_comment(line, true).trim();
+            if line.is_empty() {continue};
+            let mut parts = line.splitn(2, '=');
+            match (parts.next(), parts.next()) {
+                (Some(key), Some(val)) => {
+                    let mut val = val.trim().to_string();
+                    // Arrays may continue over several lines.
+                    while is_open_array(&val) {
+                        match lines.next() {
+                            Some((_, next)) => {
+                                val.push(' ');
+                                val.push_str(strip_comment(next, true).trim());
+                            }
+                            None => return Err(error(line_number, "Unterminated array".into())),
+                        }
+                    }
+                    let val = parse_toml_value(&val).map_err(|msg| error(line_number, msg))?;
+                    entries.push((key.trim().to_string(), val, line_number));
+                }
+                _ => return Err(error(line_number, "Expected `key = value`".into())),
+            }
+        }
+        Spec::from_entries(entries)
+    }
+
+    /// Reads a specification in JSON format.
+    pub fn from_json(src: &str) -> Result<Spec, SpecError> {
+        let mut parser = JsonParser {chars: src.chars().peekable(), line: 1};
+        let json = parser.value()?;
+        parser.skip_whitespace();
+        if parser.chars.next().is_some() {
+            return Err(error(parser.line, "Unexpected text after object".into()));
+        }
+        let members = match json {
+            Json::Obj(members) => members,
+            _ => return Err(error(1, "Expected object".into())),
+        };
+        let mut entries = vec![];
+        for (key, val, line) in members {
+            let val = match val {
+                Json::Str(s) => Value::Str(s),
+                Json::Num(x) if x >= 0.0 && x.fract() == 0.0 => Value::Int(x as usize),
+                Json::Arr(list) => Value::List(list.into_iter().map(|x| match x {
+                    Json::Str(s) => Ok(s),
+                    _ => Err(error(line, "Expected string in array".into())),
+                }).collect::<Result<_, _>>()?),
+                _ => return Err(error(line, format!("Invalid value for `{}`", key))),
+            };
+            entries.push((key, val, line));
+        }
+        Spec::from_entries(entries)
+    }
+
+    fn from_entries(entries: Vec<(String, Value, usize)>) -> Result<Spec, SpecError> {
+        let mut spec = Spec::new(vec![], vec![]);
+        let (mut has_seed, mut has_rules) = (false, false);
+        let mut compose = None;
+        let mut separator = None;
+        for (key, val, line) in entries {
+            let invalid = || error(line, format!("Invalid value for `{}`", key));
+            match (&*key, val) {
+                ("seed", Value::List(list)) => {spec.seed = list; has_seed = true}
+                ("seed", Value::Str(s)) => {spec.seed = vec![s]; has_seed = true}
+                ("rules", Value::List(list)) => {
+                    spec.rules = vec![];
+                    for rule in &list {
+                        spec.rules.extend(parse_rule(rule).map_err(|msg| error(line, msg))?);
+                    }
+                    has_rules = true;
+                }
+                ("max_len", Value::Int(x)) => spec.max_len = Some(x),
+                ("keep_max_len", Value::Int(x)) => spec.keep_max_len = Some(x),
+                ("keep_contains", Value::List(list)) => spec.keep_contains = Some(list),
+                ("compose", Value::Str(s)) => compose = Some((s, line)),
+                ("separator", Value::Str(s)) => separator = Some(s),
+                ("max_nodes", Value::Int(x)) => spec.settings.max_nodes = x,
+                ("max_edges", Value::Int(x)) => spec.settings.max_edges = x,
+                ("max_compositions", Value::Int(x)) => spec.settings.max_compositions = x,
+                ("edge_index", Value::Str(s)) => spec.settings.edge_index = match &*s {
+                    "auto" => EdgeIndex::Auto,
+                    "sparse" => EdgeIndex::Sparse,
+                    "dense" => EdgeIndex::Dense,
+                    _ => return Err(invalid()),
+                },
+                ("seed", _) | ("rules", _) | ("max_len", _) | ("keep_max_len", _) |
+                ("keep_contains", _) | ("compose", _) | ("separator", _) |
+                ("max_nodes", _) | ("max_edges", _) | ("max_compositions", _) |
+                ("edge_index", _) => return Err(invalid()),
+                _ => return Err(error(line, format!("Unknown key `{}`", key))),
+            }
+        }
+        let separator = separator.unwrap_or_else(|| "; ".into());
+        spec.compose = match compose {
+            None => Compose::Join(separator),
+            Some((s, line)) => match &*s {
+                "join" => Compose::Join(separator),
+                "first" => Compose::First,
+                "last" => Compose::Last,
+                "none" => Compose::None,
+                _ => return Err(error(line, "Invalid value for `compose`".into())),
+            },
+        };
+        if !has_seed {return Err(SpecError {line: None, message: "Missing `seed`".into()})};
+        if !has_rules {return Err(SpecError {line: None, message: "Missing `rules`".into()})};
+        Ok(spec)
+    }
+
+    /// Returns the rewrites of a node, with edges labeled by rule and position.
+    ///
+    /// An edge is labeled e.g. `ba -> ab @ 1`.
+    pub fn successors(&self, node: &str) -> Vec<(String, String)> {
+        let mut res = vec![];
+        for rule in &self.rules {
+            for (pos, new_node) in rule.apply(node) {
+                if self.max_len.map(|n| new_node.chars().count() > n).unwrap_or(false) {continue};
+                res.push((new_node, format!("{} -> {} @ {}", rule.lhs, rule.rhs, pos)));
+            }
+        }
+        res
+    }
+
+    /// Returns `true` if a node is kept in post-processing.
+    pub fn keep(&self, node: &str) -> bool {
+        self.keep_max_len.map(|n| node.chars().count() <= n).unwrap_or(true) &&
+        self.keep_contains.as_ref().map(|list| list.iter().any(|s| node.contains(&**s)))
+            .unwrap_or(true)
+    }
+
+    /// Generates the graph described by the specification.
+    pub fn run(&self) -> GenResult<Graph<String, String>, GenerateError> {
+        let f = |node: &String| self.successors(node).into_iter().map(Ok);
+        let g = |node: &String| self.keep(node);
+        let h = |a: &String, b: &String| match &self.compose {
+            Compose::Join(sep) => Ok(format!("{}{}{}", a, sep, b)),
+            Compose::First => Ok(a.clone()),
+            Compose::Last => Ok(b.clone()),
+            Compose::None => Err(None),
+        };
+        gen_from_iter((self.seed.clone(), vec![]), f, g, h, &self.settings)
+    }
+}
+
+/// Generates the graph of a JSON specification and returns it in the JSON format of Cytoscape.js.
+///
+/// This maps a string to a string, such that a separate binding crate,
+/// e.g. for WebAssembly, can expose it directly.
+/// This crate has no WebAssembly feature itself.
+/// Nodes and edges are labeled by their strings, see `export::to_cytoscape`.
+/// Reaching a limit is reported as an error without a line.
+///
+/// ```
+/// use graph_builder::spec::run_json;
+///
+/// let json = run_json(r#"{"seed": ["ba"], "rules": ["ba -> ab"]}"#).unwrap();
+/// assert!(json.contains(r#""label": "ab""#));
+/// ```
+pub fn run_json(src: &str) -> Result<String, SpecError> {
+    let spec = Spec::from_json(src)?;
+    let graph = spec.run()
+        .map_err(|(_, err)| SpecError {line: None, message: err.to_string()})?;
+    Ok(to_cytoscape(&graph, |_, node| vec![("label", node.clone().into())],
+        |_, edge| vec![("label", edge.clone().into())]))
+}
+
+/// Parses a rule with the syntax of `rewrite::System`,
+/// where `<->` gives a rule in both directions.
+fn parse_rule(rule: &str) -> Result<Vec<Rule>, String> {
+    if rule.contains('\n') {return Err(format!("Expected one rule in `{}`", rule))};
+    let sys = System::parse(rule).map_err(|err| format!("{} in `{}`", err.message, rule))?;
+    if sys.alphabet.is_some() || sys.rules.is_empty() {
+        return Err(format!("Expected `lhs -> rhs` in rule `{}`", rule));
+    }
+    Ok(sys.rules)
+}
+
+/// Returns `true` if a TOML value starts an array that is not closed,
+/// ignoring brackets inside strings.
+fn is_open_array(val: &str) -> bool {
+    if !val.starts_with('[') {return false};
+    let mut in_string = false;
+    let mut escape = false;
+    for c in val.chars() {
+        match c {
+            _ if escape => escape = false,
+            '\\' if in_string => escape = true,
+            '"' => in_string = !in_string,
+            ']' if !in_string => return false,
+            _ => {}
+        }
+    }
+    true
+}
+
+/// Parses a quoted TOML string at the start of `s` and returns it with the rest.
+fn parse_toml_string(s: &str) -> Result<(String, &str), String> {
+    let mut res = String::new();
+    let mut chars = s.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((res, &s[i + 1..])),
+            '\\' => match chars.next() {
+                Some((_, 'n')) => res.push('\n'),
+                Some((_, 't')) => res.push('\t'),
+                Some((_, c @ '"')) | Some((_, c @ '\\')) => res.push(c),
+                _ => return Err("Invalid escape in string".into()),
+            },
+            c => res.push(c),
+        }
+    }
+    Err("Unterminated string".into())
+}
+
+fn parse_toml_value(s: &str) -> Result<Value, String> {
+    let s = s.trim();
+    if s.starts_with('"') {
+        let (val, rest) = parse_toml_string(s)?;
+        if !rest.trim().is_empty() {return Err("Unexpected text after string".into())};
+        Ok(Value::Str(val))
+    } else if let Some(mut s) = s.strip_prefix('[') {
+        let mut list = vec![];
+        loop {
+            s = s.trim_start();
+            if let Some(rest) = s.strip_prefix(']') {
+                if !rest.trim().is_empty() {return Err("Unexpected text after array".into())};
+                return Ok(Value::List(list));
+            }
+            if !s.starts_with('"') {return Err("Expected string in array".into())};
+            let (val, rest) = parse_toml_string(s)?;
+            list.push(val);
+            s = rest.trim_start();
+            if let Some(rest) = s.strip_prefix(',') {s = rest}
+            else if !s.starts_with(']') {return Err("Expected `,` or `]` in array".into())};
+        }
+    } else {
+        s.parse().map(Value::Int).map_err(|_| format!("Invalid value `{}`", s))
+    }
+}
+
+/// Stores a JSON value, where object members have the line number of their key.
+enum Json {
+    Null,
+    Bool,
+    Num(f64),
+    Str(String),
+    Arr(Vec<Json>),
+    Obj(Vec<(String, Json, usize)>),
+}
+
+struct JsonParser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn skip_whitespace(&mut self) {
+        while let Some(&c) = self.chars.peek() {
+            if !c.is_whitespace() {break};
+            if c == '\n' {self.line += 1};
+            self.chars.next();
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), SpecError> {
+        self.skip_whitespace();
+        if self.chars.next() == Some(c) {Ok(())}
+        else {Err(error(self.line, format!("Expected `{}`", c)))}
+    }
+
+    fn keyword(&mut self, word: &str) -> Result<(), SpecError> {
+        for c in word.chars() {
+            if self.chars.next() != Some(c) {
+                return Err(error(self.line, "Invalid value".into()));
+            }
+        }
+        Ok(())
+    }
+
+    fn string(&mut self) -> Result<String, SpecError> {
+        self.expect('"')?;
+        let mut res = String::new();
+        loop {
+            match self.chars.next() {
+                None | Some('\n') => return Err(error(self.line, "Unterminated string".into())),
+                Some('"') => return Ok(res),
+                Some('\\') => match self.chars.next() {
+                    Some('n') => res.push('\n'),
+                    Some('t') => res.push('\t'),
+                    Some('r') => res.push('\r'),
+                    Some('b') => res.push('\u{8}'),
+                    Some('f') => res.push('\u{c}'),
+                    Some(c @ '"') | Some(c @ '\\') | Some(c @ '/') => res.push(c),
+                    Some('u') => {
+                        let hex: String = (0..4).filter_map(|_| self.chars.next()).collect();
+                        match u32::from_str_radix(&hex, 16).ok().and_then(std::char::from_u32) {
+                            Some(c) => res.push(c),
+                            None => return Err(error(self.line, "Invalid escape".into())),
+                        }
+                    }
+                    _ => return Err(error(self.line, "Invalid escape".into())),
+                },
+                Some(c) => res.push(c),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, SpecError> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some('"') => Ok(Json::Str(self.string()?)),
+            Some('n') => self.keyword("null").map(|_| Json::Null),
+            Some('t') => self.keyword("true").map(|_| Json::Bool),
+            Some('f') => self.keyword("false").map(|_| Json::Bool),
+            Some('[') => {
+                self.chars.next();
+                let mut list = vec![];
+                self.skip_whitespace();
+                if self.chars.peek() == Some(&']') {
+                    self.chars.next();
+                    return Ok(Json::Arr(list));
+                }
+                loop {
+                    list.push(self.value()?);
+                    self.skip_whitespace();
+                    match self.chars.next() {
+                        Some(',') => {}
+                        Some(']') => return Ok(Json::Arr(list)),
+                        _ => return Err(error(self.line, "Expected `,` or `]`".into())),
+                    }
+                }
+            }
+            Some('{') => {
+                self.chars.next();
+                let mut members = vec![];
+                self.skip_whitespace();
+                if self.chars.peek() == Some(&'}') {
+                    self.chars.next();
+                    return Ok(Json::Obj(members));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let line = self.line;
+                    let key = self.string()?;
+                    self.expect(':')?;
+                    members.push((key, self.value()?, line));
+                    self.skip_whitespace();
+                    match self.chars.next() {
+                        Some(',') => {}
+                        Some('}') => return Ok(Json::Obj(members)),
+                        _ => return Err(error(self.line, "Expected `,` or `}`".into())),
+                    }
+                }
+            }
+            Some(&c) if c == '-' || c.is_ascii_digit() => {
+                let mut s = String::new();
+                while let Some(&c) = self.chars.peek() {
+                    if !(c.is_ascii_digit() || "+-.eE".contains(c)) {break};
+                    s.push(c);
+                    self.chars.next();
+                }
+                s.parse().map(Json::Num).map_err(|_| error(self.line, "Invalid number".into()))
+            }
+            _ => Err(error(self.line, "Expected value".into())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn toml(src: &str) -> Result<Spec, SpecError> {Spec::from_toml(src)}
+
+    fn err(line: Option<usize>, message: &str) -> Result<Spec, SpecError> {
+        Err(SpecError {line, message: message.into()})
+    }
+
+    #[test]
+    fn toml_arrays_over_several_lines() {
+        let spec = toml("seed = [\n  \"ab\", # first\n  \"c]\"\n]\nrules = [\"ab -> ba\"]\n")
+            .unwrap();
+        assert_eq!(spec.seed, vec!["ab", "c]"]);
+        assert_eq!(toml("seed = [\"ab\",\n"), err(Some(1), "Unterminated array"));
+    }
+
+    #[test]
+    fn toml_strings() {
+        let src = "seed = \"a#b\" # comment\nrules = [\"a -> b\"]\nseparator = \"[\\\"\\t\\\\\"\n";
+        let spec = toml(src).unwrap();
+        assert_eq!(spec.seed, vec!["a#b"]);
+        assert_eq!(spec.compose, Compose::Join("[\"\t\\".into()));
+        assert_eq!(toml("seed = \"\\x\"\nrules = []\n"), err(Some(1), "Invalid escape in string"));
+    }
+
+    #[test]
+    fn rules() {
+        let spec = toml("seed = [\"aa\"]\nrules = [\"aa <-> \\\"\\\"\", \"\\\"a\\\" -> b\"]\n")
+            .unwrap();
+        assert_eq!(spec.rules, vec![Rule::new("aa", ""), Rule::new("", "aa"), Rule::new("a", "b")]);
+        assert_eq!(toml("seed = [\"a\"]\nrules = [\"a\"]\n"),
+            err(Some(2), "Expected `->` or `<->` in `a`"));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(toml("rules = [\"a -> b\"]\n"), err(None, "Missing `seed`"));
+        assert_eq!(toml("seed = \"a\"\n"), err(None, "Missing `rules`"));
+        assert_eq!(toml("seed = \"a\"\nrules = []\nsize = 3\n"),
+            err(Some(3), "Unknown key `size`"));
+        assert_eq!(toml("seed = \"a\"\nrules = []\nmax_len = \"3\"\n"),
+            err(Some(3), "Invalid value for `max_len`"));
+        assert_eq!(toml("seed = \"a\"\nrules = []\ncompose = \"[\"\n"),
+            err(Some(3), "Invalid value for `compose`"));
+        assert_eq!(toml("seed\n"), err(Some(1), "Expected `key = value`"));
+    }
+
+    #[test]
+    fn json() {
+        let spec = Spec::from_json(r#"{
+            "seed": ["cba"],
+            "rules": ["ba -> ab", "cb -> bc"],
+            "max_nodes": 20,
+            "compose": "first",
+            "keep_contains": ["a\u0062"]
+        }"#).unwrap();
+        assert_eq!(spec.seed, vec!["cba"]);
+        assert_eq!(spec.rules, vec![Rule::new("ba", "ab"), Rule::new("cb", "bc")]);
+        assert_eq!(spec.settings.max_nodes, 20);
+        assert_eq!(spec.compose, Compose::First);
+        assert_eq!(spec.keep_contains, Some(vec!["ab".into()]));
+
+        assert_eq!(Spec::from_json("{\"seed\": [\"a\"],\n \"rules\": 1}"),
+            err(Some(2), "Invalid value for `rules`"));
+        assert_eq!(Spec::from_json("[]"), err(Some(1), "Expected object"));
+        assert_eq!(Spec::from_json("{} x"), err(Some(1), "Unexpected text after object"));
+    }
+
+    #[test]
+    fn run() {
+        let spec = toml("seed = \"ba\"\nrules = [\"ba -> ab\"]\n").unwrap();
+        let (nodes, edges) = spec.run().unwrap();
+        assert_eq!(nodes, vec!["ba", "ab"]);
+        assert_eq!(edges, vec![([0, 1], "ba -> ab @ 0".to_string())]);
+    }
+}
@@ -0,0 +1,167 @@
+//! Recording the decisions made while generating a graph.
+//!
+//! A trace lists every call to `f` that produced a new node, a duplicate node or an error,
+//! in the order the calls were made.
+//! This is used to find out why a node was never generated,
+//! e.g. because `f` returned an error or because a limit was reached before expanding it.
+//!
+//! A trace can be replayed without calling `f` again, optionally stopping early,
+//! to find the point where generation went wrong.
+
+use std::hash::Hash;
+
+use crate::{
+    expand, filter_compose, into_result, post_process, Emit, Fifo, Frontier,
+    GenResult, GenerateError, GenerateSettings, Graph,
+};
+
+/// The result of one call to `f` while generating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TraceResult {
+    /// A node that did not exist was added, with its index before filtering.
+    NewNode(usize),
+    /// A node that already existed, with its index before filtering.
+    Duplicate(usize),
+    /// The function returned an error.
+    Error,
+}
+
+/// Stores one call to `f` while generating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TraceEntry {
+    /// The index of the expanded node before filtering.
+    pub node: usize,
+    /// The step `j` passed to `f`.
+    pub step: usize,
+    /// The result of the call.
+    pub result: TraceResult,
+}
+
+/// Stores the calls to `f` while generating, together with the graph before filtering.
+///
+/// Node indices in the entries refer to the nodes of `graph`.
+/// Every entry that is not an error added one edge, in the same order as the edges of `graph`
+/// that follow the seed edges.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace<T, U> {
+    /// The calls to `f`, in the order they were made.
+    pub entries: Vec<TraceEntry>,
+    /// The graph before post-processing.
+    pub graph: Graph<T, U>,
+}
+
+impl<T, U> Trace<T, U> {
+    /// Returns the index of a node before filtering, or `None` if it was never generated.
+    pub fn position(&self, node: &T) -> Option<usize>
+        where T: PartialEq
+    {
+        self.graph.0.iter().position(|t| t == node)
+    }
+
+    /// Returns the entry that generated a node the first time,
+    /// or `None` for seed nodes.
+    pub fn origin(&self, id: usize) -> Option<&TraceEntry> {
+        self.entries.iter().find(|e| e.result == TraceResult::NewNode(id))
+    }
+
+    /// Returns the entries from expanding a node.
+    ///
+    /// Returns nothing if the node was never expanded,
+    /// e.g. because a limit was reached first.
+    pub fn steps(&self, i: usize) -> impl Iterator<Item = &TraceEntry> {
+        self.entries.iter().filter(move |e| e.node == i)
+    }
+
+    /// Returns the number of entries where `f` returned an error.
+    pub fn errors(&self) -> usize {
+        self.entries.iter().filter(|e| e.result == TraceResult::Error).count()
+    }
+
+    /// Returns the graph before post-processing, as it was after the first `stop` entries.
+    ///
+    /// When `stop` is larger than the number of entries, this is a copy of `graph`.
+    pub fn prefix(&self, stop: usize) -> Graph<T, U>
+        where T: Clone, U: Clone
+    {
+        let count = |entries: &[TraceEntry]| {
+            let new_nodes = entries.iter()
+                .filter(|e| matches!(e.result, TraceResult::NewNode(_))).count();
+            let new_edges = entries.iter().filter(|e| e.result != TraceResult::Error).count();
+            (new_nodes, new_edges)
+        };
+        let (all_nodes, all_edges) = count(&self.entries);
+        let (nodes, edges) = count(&self.entries[..stop.min(self.entries.len())]);
+        let seed_nodes = self.graph.0.len() - all_nodes;
+        let seed_edges = self.graph.1.len() - all_edges;
+        (self.graph.0[..seed_nodes + nodes].to_vec(), self.graph.1[..seed_edges + edges].to_vec())
+    }
+}
+
+/// A generated graph together with its trace.
+pub type Traced<T, U> = (Graph<T, U>, Trace<T, U>);
+
+/// Records trace entries while expanding nodes in the order they were generated.
+struct TraceFrontier(Fifo, Vec<TraceEntry>);
+
+impl<T, U> Frontier<T, U> for TraceFrontier {
+    fn next(&mut self, nodes: &[T], depth: &[usize]) -> Option<usize> {
+        <Fifo as Frontier<T, U>>::next(&mut self.0, nodes, depth)
+    }
+
+    fn trace(&mut self, entry: TraceEntry) {self.1.push(entry)}
+}
+
+/// Generates a graph and a trace of every call to `f`.
+///
+/// This works the same way as `gen`, but also returns a `Trace`,
+/// which stores a copy of the graph before post-processing.
+pub fn gen_trace<T, U, F, G, H, E>(
+    seed: Graph<T, U>,
+    n: usize,
+    f: F,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<Traced<T, U>, E>
+    where T: Eq + Hash + Clone,
+          U: Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let seed_attr = vec![(); seed.0.len()];
+    let f = |t: &T, j| if j < n {Some(f(t, j).map(|(t, u)| (Emit::NewNode(t, u), ())))}
+        else {None};
+    let mut frontier = TraceFrontier(Fifo(0), vec![]);
+    let mut exp = expand(seed, seed_attr, f, &mut frontier, settings);
+    let trace = Trace {entries: frontier.1, graph: exp.graph.clone()};
+    let (graph, _) =
+        post_process(exp.graph, &mut exp.has_edge, |_, t| g(t), h, settings, &mut exp.error);
+    into_result((graph, trace), exp.error)
+}
+
+/// Reconstructs a generated graph from a trace, without calling `f`.
+///
+/// Replays the first `stop` entries, or all entries when `stop` is `None`,
+/// and post-processes the result the same way as in `gen`.
+/// When replaying all entries with the same `g`, `h` and settings,
+/// the result is the same graph as the one returned by `gen_trace`.
+///
+/// Errors returned by `f` are not stored in the trace, so only errors from
+/// post-processing are reported.
+pub fn replay<T, U, G, H, E>(
+    trace: &Trace<T, U>,
+    stop: Option<usize>,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<Graph<T, U>, E>
+    where T: Clone,
+          U: Clone,
+          G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    filter_compose(trace.prefix(stop.unwrap_or(usize::MAX)), g, h, settings)
+}
@@ -0,0 +1,413 @@
+//! Traversal of generated graphs.
+//!
+//! Traversals follow edges in their direction, using an `Incidence` index.
+//! Every traversal yields a node together with the index of the edge that entered it,
+//! which is `None` for the start node.
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+
+use crate::{Graph, Incidence};
+
+/// Iterates over nodes reachable from a start node in breadth-first order.
+pub struct Bfs<'a> {
+    inc: Cow<'a, Incidence>,
+    visited: Vec<bool>,
+    queue: VecDeque<(usize, Option<usize>)>,
+}
+
+impl<'a> Bfs<'a> {
+    /// Creates a new breadth-first traversal using an existing incidence index.
+    pub fn with_incidence(inc: &'a Incidence, start: usize) -> Bfs<'a> {
+        Bfs::from_cow(Cow::Borrowed(inc), start)
+    }
+
+    fn from_cow(inc: Cow<'a, Incidence>, start: usize) -> Bfs<'a> {
+        let mut visited = vec![false; inc.len()];
+        visited[start] = true;
+        let mut queue = VecDeque::new();
+        queue.push_back((start, None));
+        Bfs {inc, visited, queue}
+    }
+}
+
+impl<'a> Iterator for Bfs<'a> {
+    type Item = (usize, Option<usize>);
+
+    fn next(&mut self) -> Option<(usize, Option<usize>)> {
+        let (i, e) = self.queue.pop_front()?;
+        for &(j, k) in &self.inc.out[i] {
+            if !self.visited[j] {
+                self.visited[j] = true;
+                self.queue.push_back((j, Some(k)));
+            }
+        }
+        Some((i, e))
+    }
+}
+
+/// Iterates over nodes reachable from a start node in depth-first order.
+///
+/// Nodes are yielded the first time they are visited (pre-order),
+/// and edges are followed in the order they are stored in the graph.
+pub struct Dfs<'a> {
+    inc: Cow<'a, Incidence>,
+    visited: Vec<bool>,
+    stack: Vec<(usize, Option<usize>)>,
+}
+
+impl<'a> Dfs<'a> {
+    /// Creates a new depth-first traversal using an existing incidence index.
+    pub fn with_incidence(inc: &'a Incidence, start: usize) -> Dfs<'a> {
+        Dfs::from_cow(Cow::Borrowed(inc), start)
+    }
+
+    fn from_cow(inc: Cow<'a, Incidence>, start: usize) -> Dfs<'a> {
+        let visited = vec![false; inc.len()];
+        Dfs {inc, visited, stack: vec![(start, None)]}
+    }
+}
+
+impl<'a> Iterator for Dfs<'a> {
+    type Item = (usize, Option<usize>);
+
+    fn next(&mut self) -> Option<(usize, Option<usize>)> {
+        loop {
+            let (i, e) = self.stack.pop()?;
+            if self.visited[i] {continue};
+            self.visited[i] = true;
+            for &(j, k) in self.inc.out[i].iter().rev() {
+                if !self.visited[j] {self.stack.push((j, Some(k)))};
+            }
+            return Some((i, e));
+        }
+    }
+}
+
+/// Returns a breadth-first traversal from a start node.
+///
+/// This builds an incidence index of the graph.
+/// To reuse an existing index, use `Bfs::with_incidence`.
+pub fn bfs<T, U>(graph: &Graph<T, U>, start: usize) -> Bfs<'static> {
+    Bfs::from_cow(Cow::Owned(Incidence::new(graph)), start)
+}
+
+/// Returns a depth-first traversal from a start node.
+///
+/// This builds an incidence index of the graph.
+/// To reuse an existing index, use `Dfs::with_incidence`.
+pub fn dfs<T, U>(graph: &Graph<T, U>, start: usize) -> Dfs<'static> {
+    Dfs::from_cow(Cow::Owned(Incidence::new(graph)), start)
+}
+
+/// The kind of an edge found while visiting a graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EdgeKind {
+    /// The edge entered a node for the first time.
+    Tree,
+    /// The edge points to a node that is being visited,
+    /// which means there is a cycle.
+    ///
+    /// Only reported by depth-first visits.
+    Back,
+    /// Any other edge, pointing to a node that was already discovered.
+    Cross,
+}
+
+/// Receives events while visiting a graph.
+///
+/// All methods do nothing by default,
+/// such that one only implements the events of interest.
+pub trait Visitor<T, U> {
+    /// Called when a node is visited for the first time.
+    fn on_discover(&mut self, _i: usize, _node: &T) {}
+    /// Called for every edge from a discovered node, with the edge index.
+    fn on_edge(&mut self, _k: usize, _edge: &([usize; 2], U), _kind: EdgeKind) {}
+    /// Called when all edges from a node are visited.
+    ///
+    /// In depth-first visits, this happens after all nodes reachable
+    /// through tree edges are finished (post-order).
+    fn on_finish(&mut self, _i: usize, _node: &T) {}
+}
+
+/// Visits the nodes reachable from the roots in depth-first order.
+///
+/// Roots are visited in order, skipping those already discovered.
+pub fn visit_dfs<T, U, I, V>(graph: &Graph<T, U>, roots: I, visitor: &mut V)
+    where I: IntoIterator<Item = usize>, V: Visitor<T, U>
+{
+    let inc = Incidence::new(graph);
+    // Zero for new nodes, one for nodes being visited and two for finished nodes.
+    let mut state: Vec<u8> = vec![0; graph.0.len()];
+    // Stores nodes being visited with the position of the next edge.
+    let mut stack: Vec<(usize, usize)> = vec![];
+    for root in roots {
+        if state[root] != 0 {continue};
+        state[root] = 1;
+        visitor.on_discover(root, &graph.0[root]);
+        stack.push((root, 0));
+        while let Some(&(i, pos)) = stack.last() {
+            if let Some(&(j, k)) = inc.out[i].get(pos) {
+                stack.last_mut().unwrap().1 += 1;
+                let kind = match state[j] {
+                    0 => EdgeKind::Tree,
+                    1 => EdgeKind::Back,
+                    _ => EdgeKind::Cross,
+                };
+                visitor.on_edge(k, &graph.1[k], kind);
+                if kind == EdgeKind::Tree {
+                    state[j] = 1;
+                    visitor.on_discover(j, &graph.0[j]);
+                    stack.push((j, 0));
+                }
+            } else {
+                state[i] = 2;
+                visitor.on_finish(i, &graph.0[i]);
+                stack.pop();
+            }
+        }
+    }
+}
+
+/// Visits the nodes reachable from the roots in breadth-first order.
+///
+/// Roots are visited in order, skipping those already discovered.
+/// Nodes are finished when all their edges are visited.
+pub fn visit_bfs<T, U, I, V>(graph: &Graph<T, U>, roots: I, visitor: &mut V)
+    where I: IntoIterator<Item = usize>, V: Visitor<T, U>
+{
+    let inc = Incidence::new(graph);
+    let mut discovered = vec![false; graph.0.len()];
+    let mut queue = VecDeque::new();
+    for root in roots {
+        if discovered[root] {continue};
+        discovered[root] = true;
+        visitor.on_discover(root, &graph.0[root]);
+        queue.push_back(root);
+        while let Some(i) = queue.pop_front() {
+            for &(j, k) in &inc.out[i] {
+                if discovered[j] {
+                    visitor.on_edge(k, &graph.1[k], EdgeKind::Cross);
+                } else {
+                    visitor.on_edge(k, &graph.1[k], EdgeKind::Tree);
+                    discovered[j] = true;
+                    visitor.on_discover(j, &graph.0[j]);
+                    queue.push_back(j);
+                }
+            }
+            visitor.on_finish(i, &graph.0[i]);
+        }
+    }
+}
+
+/// Iterates over nodes visited by a random walk.
+pub struct RandomWalk<'a, R> {
+    inc: Cow<'a, Incidence>,
+    start: usize,
+    cur: Option<usize>,
+    steps: usize,
+    restart: f64,
+    rand: R,
+}
+
+/// The precision used when comparing random numbers with a probability.
+const PROBABILITY_SCALE: usize = 1 << 30;
+
+impl<'a, R> RandomWalk<'a, R>
+    where R: FnMut(usize) -> usize
+{
+    /// Creates a new random walk using an existing incidence index.
+    pub fn with_incidence(
+        inc: &'a Incidence,
+        start: usize,
+        steps: usize,
+        restart: f64,
+        rand: R
+    ) -> RandomWalk<'a, R> {
+        RandomWalk {inc: Cow::Borrowed(inc), start, cur: None, steps, restart, rand}
+    }
+}
+
+impl<'a, R> Iterator for RandomWalk<'a, R>
+    where R: FnMut(usize) -> usize
+{
+    type Item = (usize, Option<usize>);
+
+    fn next(&mut self) -> Option<(usize, Option<usize>)> {
+        let i = if let Some(i) = self.cur {i}
+            else {
+                self.cur = Some(self.start);
+                return Some((self.start, None));
+            };
+        if self.steps == 0 {return None};
+        self.steps -= 1;
+
+        let out = &self.inc.out[i];
+        let restart = out.is_empty() ||
+            ((self.rand)(PROBABILITY_SCALE) as f64) < self.restart * PROBABILITY_SCALE as f64;
+        if restart {
+            self.cur = Some(self.start);
+            Some((self.start, None))
+        } else {
+            let (j, k) = out[(self.rand)(out.len())];
+            self.cur = Some(j);
+            Some((j, Some(k)))
+        }
+    }
+}
+
+/// Returns a random walk that takes `steps` steps from a start node.
+///
+/// Every step follows a random outgoing edge.
+/// With probability `restart`, or when there are no outgoing edges,
+/// the walk restarts at the start node instead, yielding no edge.
+/// The start node is yielded first, so the walk yields `steps + 1` nodes.
+///
+/// The function `rand` returns a random number below its argument.
+/// For example, when using the `rand` crate: `|k| rng.gen_range(0..k)`.
+///
+/// This builds an incidence index of the graph.
+/// To reuse an existing index, use `RandomWalk::with_incidence`.
+pub fn random_walk<T, U, R>(
+    graph: &Graph<T, U>,
+    start: usize,
+    steps: usize,
+    restart: f64,
+    rand: R,
+) -> RandomWalk<'static, R>
+    where R: FnMut(usize) -> usize
+{
+    let inc = Cow::Owned(Incidence::new(graph));
+    RandomWalk {inc, start, cur: None, steps, restart, rand}
+}
+
+/// Finds a shortest path from `a` to `b` by searching forward from `a`
+/// and backward from `b` until the searches meet.
+///
+/// Returns the nodes and edge indices of the path, or `None` if there is no path.
+/// Every step expands a whole layer of the smaller frontier,
+/// which visits far fewer nodes than a breadth-first traversal on graphs with many edges.
+///
+/// This builds an incidence index of the graph.
+/// To reuse an existing index, use `bidirectional_bfs_with_incidence`.
+pub fn bidirectional_bfs<T, U>(
+    graph: &Graph<T, U>,
+    a: usize,
+    b: usize
+) -> Option<(Vec<usize>, Vec<usize>)> {
+    bidirectional_bfs_with_incidence(&Incidence::new(graph), a, b)
+}
+
+/// Finds a shortest path from `a` to `b` using an existing incidence index,
+/// see `bidirectional_bfs`.
+pub fn bidirectional_bfs_with_incidence(
+    inc: &Incidence,
+    a: usize,
+    b: usize
+) -> Option<(Vec<usize>, Vec<usize>)> {
+    if a == b {return Some((vec![a], vec![]))};
+    // Distance with the previous node and edge, for nodes found by the forward
+    // and backward search, where the backward search follows edges in reverse.
+    type Found = Option<(usize, Option<(usize, usize)>)>;
+    let mut fwd: Vec<Found> = vec![None; inc.len()];
+    let mut bwd: Vec<Found> = vec![None; inc.len()];
+    fwd[a] = Some((0, None));
+    bwd[b] = Some((0, None));
+    let (mut fwd_layer, mut bwd_layer) = (vec![a], vec![b]);
+    // The shortest length found so far, with the meeting edge as source, target and index.
+    let mut best: Option<(usize, [usize; 3])> = None;
+    while best.is_none() && !fwd_layer.is_empty() && !bwd_layer.is_empty() {
+        let forward = fwd_layer.len() <= bwd_layer.len();
+        let (layer, adj, this, other) = if forward {
+            (&mut fwd_layer, &inc.out, &mut fwd, &bwd)
+        } else {
+            (&mut bwd_layer, &inc.inc, &mut bwd, &fwd)
+        };
+        let mut next = vec![];
+        for &i in layer.iter() {
+            let d = this[i].unwrap().0;
+            for &(j, k) in &adj[i] {
+                if let Some((e, _)) = other[j] {
+                    if best.map(|(len, _)| d + 1 + e < len).unwrap_or(true) {
+                        best = Some((d + 1 + e, if forward {[i, j, k]} else {[j, i, k]}));
+                    }
+                }
+                if this[j].is_none() {
+                    this[j] = Some((d + 1, Some((i, k))));
+                    next.push(j);
+                }
+            }
+        }
+        *layer = next;
+    }
+    let (_, [i, j, k]) = best?;
+    // Walks back to `a` through the forward search, then on to `b` through the backward search.
+    let (mut nodes, mut edges) = (vec![i], vec![]);
+    let mut x = i;
+    while let Some((_, Some((y, e)))) = fwd[x] {
+        nodes.push(y);
+        edges.push(e);
+        x = y;
+    }
+    nodes.reverse();
+    edges.reverse();
+    nodes.push(j);
+    edges.push(k);
+    let mut x = j;
+    while let Some((_, Some((y, e)))) = bwd[x] {
+        nodes.push(y);
+        edges.push(e);
+        x = y;
+    }
+    Some((nodes, edges))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn distances(graph: &Graph<(), ()>, a: usize) -> Vec<Option<usize>> {
+        let inc = Incidence::new(graph);
+        let mut dist = vec![None; graph.0.len()];
+        dist[a] = Some(0);
+        for (i, k) in Bfs::with_incidence(&inc, a) {
+            if let Some(k) = k {dist[i] = Some(dist[graph.1[k].0[0]].unwrap() + 1)}
+        }
+        dist
+    }
+
+    #[test]
+    fn bidirectional_shortest_paths() {
+        // A long path `0 -> 1 -> 2 -> 3 -> 4` with a shortcut `1 -> 4` and a dead end `5`.
+        let graph = (vec![(); 6], vec![([0, 1], ()), ([1, 2], ()), ([2, 3], ()), ([3, 4], ()),
+            ([1, 4], ()), ([5, 0], ())]);
+        assert_eq!(bidirectional_bfs(&graph, 0, 4), Some((vec![0, 1, 4], vec![0, 4])));
+        assert_eq!(bidirectional_bfs(&graph, 2, 4), Some((vec![2, 3, 4], vec![2, 3])));
+        assert_eq!(bidirectional_bfs(&graph, 3, 3), Some((vec![3], vec![])));
+        assert_eq!(bidirectional_bfs(&graph, 4, 0), None);
+        assert_eq!(bidirectional_bfs(&graph, 0, 5), None);
+
+        // Paths are valid and as short as found by breadth-first traversal.
+        let mut x = 7u64;
+        let mut next = move |n: usize| {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (x >> 33) as usize % n
+        };
+        for n in 1..25 {
+            let graph = (vec![(); n], (0..2 * n).map(|_| ([next(n), next(n)], ())).collect());
+            for a in 0..n {
+                let dist = distances(&graph, a);
+                for (b, &d) in dist.iter().enumerate() {
+                    let path = bidirectional_bfs(&graph, a, b);
+                    assert_eq!(path.as_ref().map(|p| p.1.len()), d);
+                    if let Some((nodes, edges)) = path {
+                        assert_eq!((nodes[0], nodes[nodes.len() - 1]), (a, b));
+                        for (k, &e) in edges.iter().enumerate() {
+                            assert_eq!(graph.1[e].0, [nodes[k], nodes[k + 1]]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
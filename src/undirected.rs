@@ -0,0 +1,168 @@
+//! Undirected graphs.
+//!
+//! An undirected graph uses the same representation as a directed graph,
+//! but every edge `[a, b]` has `a <= b` and there is at most one edge between two nodes.
+//! `UGraph` keeps these rules, such that a directed graph can not be used by mistake.
+//! The data of an edge is assumed to be the same in both directions,
+//! e.g. a self-inverse operation, or a relation that is symmetric.
+//!
+//! When removing nodes, edges `{a, b}` and `{b, c}` are composed into `{a, c}`
+//! with the data in path order, the same way as directed edges `a -> b -> c`.
+//! An edge is never composed with itself, so going back and forth along an edge
+//! does not create a self-loop.
+//! This makes it unnecessary to generate both directions and filter them with `bidir`.
+
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use crate::{
+    expand_unit, into_result, post_process_walk, EdgeSet, Errors, GenResult, GenerateError,
+    GenerateSettings, Graph, Walk,
+};
+
+/// An undirected graph, where every edge `[a, b]` has `a <= b`
+/// and there is at most one edge between two nodes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UGraph<T, U>(Graph<T, U>);
+
+impl<T, U> UGraph<T, U> {
+    /// Creates an undirected graph, see `from_directed`.
+    pub fn new(graph: Graph<T, U>) -> UGraph<T, U> {from_directed(graph)}
+
+    /// Returns the nodes.
+    pub fn nodes(&self) -> &[T] {&(self.0).0}
+
+    /// Returns the edges.
+    pub fn edges(&self) -> &[([usize; 2], U)] {&(self.0).1}
+
+    /// Returns the graph, where every edge has the smaller index first.
+    pub fn graph(&self) -> &Graph<T, U> {&self.0}
+
+    /// Converts into a graph, where every edge has the smaller index first.
+    pub fn into_graph(self) -> Graph<T, U> {self.0}
+}
+
+impl<T, U> From<Graph<T, U>> for UGraph<T, U> {
+    fn from(graph: Graph<T, U>) -> UGraph<T, U> {from_directed(graph)}
+}
+
+/// Returns the indices of an undirected edge, with the smaller index first.
+pub fn key(e: [usize; 2]) -> [usize; 2] {Walk::Undirected.key(e)}
+
+/// Converts a directed graph into an undirected graph.
+///
+/// Edges between the same nodes are merged into one,
+/// keeping the data of the first edge.
+/// This keeps the order of the edges.
+pub fn from_directed<T, U>((nodes, edges): Graph<T, U>) -> UGraph<T, U> {
+    let mut has_edge: HashSet<[usize; 2]> = HashSet::new();
+    let edges = edges.into_iter()
+        .map(|(e, u)| (key(e), u))
+        .filter(|(e, _)| has_edge.insert(*e))
+        .collect();
+    UGraph((nodes, edges))
+}
+
+/// Converts an undirected graph into a directed graph, with edges in both directions.
+///
+/// Self-loops are only added once.
+/// This is used to call algorithms for directed graphs, e.g. in `traverse`.
+pub fn to_directed<T, U: Clone>(UGraph((nodes, edges)): UGraph<T, U>) -> Graph<T, U> {
+    let mut res = Vec::with_capacity(2 * edges.len());
+    for ([a, b], u) in edges {
+        if a != b {res.push(([b, a], u.clone()))};
+        res.push(([a, b], u));
+    }
+    (nodes, res)
+}
+
+/// Generates an undirected graph.
+///
+/// This works the same way as `crate::gen`, but edges created by `f` are undirected,
+/// such that an edge from a new node back to an existing one is merged with the edge
+/// that created the node. Nodes are removed the same way as in `filter_compose`.
+pub fn gen<T, U, F, G, H, E>(
+    seed: UGraph<T, U>,
+    n: usize,
+    f: F,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<UGraph<T, U>, E>
+    where T: Eq + Hash + Clone,
+          F: Fn(&T, usize) -> Result<(T, U), E>,
+          G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let mut exp = expand_unit(seed.0, n, f, settings);
+    let graph = post_process(from_directed(exp.graph), g, h, settings, &mut exp.error);
+    into_result(graph, exp.error)
+}
+
+/// Removes nodes that do not pass the filter `g` from an undirected graph,
+/// composing edges with `h` such that they no longer refer to removed nodes.
+///
+/// This is the undirected version of `crate::filter_compose`.
+pub fn filter_compose<T, U, G, H, E>(
+    graph: UGraph<T, U>,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+) -> GenResult<UGraph<T, U>, E>
+    where G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let mut error = Errors::new();
+    let graph = post_process(graph, g, h, settings, &mut error);
+    into_result(graph, error)
+}
+
+/// Removes nodes that do not pass the filter `g`,
+/// composing edges through removed nodes with `h`.
+fn post_process<T, U, G, H, E>(
+    UGraph(graph): UGraph<T, U>,
+    g: G,
+    h: H,
+    settings: &GenerateSettings,
+    error: &mut Errors<E>,
+) -> UGraph<T, U>
+    where G: Fn(&T) -> bool,
+          H: Fn(&U, &U) -> Result<U, Option<E>>,
+          E: From<GenerateError>
+{
+    let mut has_edge = EdgeSet::new(settings.edge_index, settings.max_nodes);
+    for edge in &graph.1 {has_edge.insert(edge.0)}
+    let (graph, _, _) = post_process_walk(graph, &mut has_edge, |_, t| g(t),
+        |_, _, _, x, y| h(x, y), settings, error, |_, _, _| {}, Walk::Undirected);
+    UGraph(graph)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_normalizes_edges() {
+        let graph = UGraph::new((vec![0, 1, 2], vec![([1, 0], 'a'), ([0, 1], 'b'), ([2, 1], 'c')]));
+        assert_eq!(graph.edges(), &[([0, 1], 'a'), ([1, 2], 'c')]);
+        let edges = to_directed(graph).1;
+        assert_eq!(edges, vec![([1, 0], 'a'), ([0, 1], 'a'), ([2, 1], 'c'), ([1, 2], 'c')]);
+    }
+
+    #[test]
+    fn compose_through_removed_node() {
+        // `0 - 1 - 2 - 3`, where `1` and `2` are removed.
+        let graph = UGraph::new((vec![0, 1, 2, 3], vec![([1, 0], 1), ([2, 1], 2), ([2, 3], 3)]));
+        let settings = GenerateSettings::new(100, 100);
+        let res: GenResult<_, ()> =
+            filter_compose(graph, |&x| x == 0 || x == 3, |a, b| Ok(a * 10 + b), &settings);
+        let graph = res.unwrap();
+        assert_eq!(graph.nodes(), &[0, 3]);
+        assert_eq!(graph.edges().len(), 1);
+        assert_eq!(graph.edges()[0].0, [0, 1]);
+        // The data is composed in path order from `0` to `3`.
+        assert_eq!(graph.edges()[0].1, 123);
+    }
+}